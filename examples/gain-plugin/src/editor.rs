@@ -1,6 +1,6 @@
 use std::{cell::RefCell, rc::Rc};
 
-use plinth_plugin::{raw_window_handle::RawWindowHandle, Editor, Host};
+use plinth_plugin::{raw_window_handle::RawWindowHandle, Editor, Host, MidiLearn};
 use plugin_canvas_slint::{editor::{EditorHandle, SlintEditor}, plugin_canvas::window::WindowAttributes};
 
 use crate::{parameters::GainParameters, view::GainPluginView};
@@ -22,21 +22,24 @@ pub struct GainPluginEditor {
     editor_handle: Option<Rc<EditorHandle>>,
     parameters: Rc<GainParameters>,
     settings: Rc<RefCell<EditorSettings>>,
+    midi_learn: Rc<MidiLearn>,
 }
 
 impl GainPluginEditor {
-    pub fn new(host: Rc<dyn Host>, parameters: Rc<GainParameters>, settings: Rc<RefCell<EditorSettings>>) -> Self {
+    pub fn new(host: Rc<dyn Host>, parameters: Rc<GainParameters>, settings: Rc<RefCell<EditorSettings>>, midi_learn: Rc<MidiLearn>) -> Self {
         Self {
             host,
             editor_handle: None,
             parameters,
             settings,
+            midi_learn,
         }
     }
 }
 
 impl Editor for GainPluginEditor {
     const DEFAULT_SIZE: (f64, f64) = (400.0, 300.0);
+    const SUPPORTS_FLOATING: bool = true;
 
     fn window_size(&self) -> (f64, f64) {
         let scale = self.settings.borrow().scale;
@@ -63,6 +66,27 @@ impl Editor for GainPluginEditor {
 
         let editor_handle = SlintEditor::open(
             parent,
+            WindowAttributes::new(Self::DEFAULT_SIZE.into(), scale),
+            {
+                let parameters = self.parameters.clone();
+                let host = self.host.clone();
+                let midi_learn = self.midi_learn.clone();
+
+                move |_| {
+                    GainPluginView::new(parameters.clone(), host.clone(), midi_learn.clone())
+                }
+            },
+        );
+
+        self.editor_handle = Some(editor_handle);
+    }
+
+    fn open_floating(&mut self) {
+        self.close();
+
+        let scale = self.settings.borrow().scale;
+
+        let editor_handle = SlintEditor::open_floating(
             WindowAttributes::new(Self::DEFAULT_SIZE.into(), scale),
             {
                 let parameters = self.parameters.clone();
@@ -74,6 +98,11 @@ impl Editor for GainPluginEditor {
             },
         );
 
+        editor_handle.set_close_callback({
+            let host = self.host.clone();
+            move || host.editor_closed()
+        });
+
         self.editor_handle = Some(editor_handle);
     }
 
@@ -81,6 +110,30 @@ impl Editor for GainPluginEditor {
         self.editor_handle = None;
     }
 
+    fn set_transient(&self, window: RawWindowHandle) {
+        if let Some(editor_handle) = self.editor_handle.as_ref() {
+            editor_handle.set_transient_for(window);
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        if let Some(editor_handle) = self.editor_handle.as_ref() {
+            editor_handle.set_title(title);
+        }
+    }
+
+    fn show(&self) {
+        if let Some(editor_handle) = self.editor_handle.as_ref() {
+            editor_handle.show();
+        }
+    }
+
+    fn hide(&self) {
+        if let Some(editor_handle) = self.editor_handle.as_ref() {
+            editor_handle.hide();
+        }
+    }
+
     fn on_frame(&self) {
         if let Some(editor_handle) = self.editor_handle.as_ref() {
             editor_handle.on_frame();
@@ -4,4 +4,4 @@ mod plugin;
 mod processor;
 mod view;
 
-pub use plugin::GainPlugin;
+pub use plugin::{vst3_factory, GainPlugin};
@@ -0,0 +1,13 @@
+use std::path::PathBuf;
+
+use gain_plugin::vst3_factory;
+
+/// Writes moduleinfo.json into the given .vst3 bundle, or prints it if no bundle is given
+fn main() {
+    let factory = vst3_factory();
+
+    match std::env::args_os().nth(1).map(PathBuf::from) {
+        Some(bundle_path) => factory.write_module_info(&bundle_path).expect("Failed to write moduleinfo.json"),
+        None => print!("{}", factory.module_info()),
+    }
+}
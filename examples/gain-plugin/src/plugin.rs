@@ -4,17 +4,18 @@ use std::io::{Read, Write};
 use std::rc::Rc;
 
 use plinth_plugin::error::Error;
-use plinth_plugin::{export_clap, export_vst3, Event, Host, HostInfo, Parameters, Plugin, ProcessorConfig};
+use plinth_plugin::{export_clap, export_vst3, Event, Host, HostInfo, MidiCcAssignment, MidiLearn, Parameters, Plugin, ProcessorConfig, StateContext};
 use plinth_plugin::clap::ClapPlugin;
 use plinth_plugin::vst3::Vst3Plugin;
 
 use crate::editor::{EditorSettings, GainPluginEditor};
-use crate::{parameters::GainParameters, processor::GainPluginProcessor};
+use crate::{parameters::{GainParameter, GainParameters}, processor::GainPluginProcessor};
 
 #[derive(Default)]
 pub struct GainPlugin {
     parameters: Rc<GainParameters>,
     editor_settings: Rc<RefCell<EditorSettings>>,
+    midi_learn: Rc<MidiLearn>,
 }
 
 impl Plugin for GainPlugin {
@@ -46,10 +47,10 @@ impl Plugin for GainPlugin {
     }
 
     fn create_editor(&mut self, host: Rc<dyn Host>) -> Self::Editor {
-        GainPluginEditor::new(host, self.parameters.clone(), self.editor_settings.clone())
+        GainPluginEditor::new(host, self.parameters.clone(), self.editor_settings.clone(), self.midi_learn.clone())
     }
 
-    fn save_state(&self, writer: &mut impl Write) -> Result<(), Error> {
+    fn save_state(&self, writer: &mut impl Write, _context: StateContext) -> Result<(), Error> {
         let serialized_parameters: HashMap<_, _> = self.parameters.serialize().collect();
         let parameters_json = serde_json::to_string(&serialized_parameters)
             .map_err(|_| Error::SerializationError)?;
@@ -58,7 +59,7 @@ impl Plugin for GainPlugin {
         Ok(())
     }
 
-    fn load_state(&mut self, reader: &mut impl Read) -> Result<(), Error> {
+    fn load_state(&mut self, reader: &mut impl Read, _context: StateContext) -> Result<(), Error> {
         let mut parameters_json = String::new();
         reader.read_to_string(&mut parameters_json)?;
 
@@ -68,6 +69,44 @@ impl Plugin for GainPlugin {
 
         Ok(())
     }
+
+    fn midi_cc_assignments(&self) -> Vec<MidiCcAssignment> {
+        // Channel volume
+        vec![MidiCcAssignment::new(7, GainParameter::Gain)]
+    }
+
+    // Learned controllers are saved with the editor state by the wrappers
+    fn midi_learn(&self) -> Option<&MidiLearn> {
+        Some(&self.midi_learn)
+    }
+
+    fn save_editor_state(&self, writer: &mut impl Write) -> Result<(), Error> {
+        let settings_json = serde_json::json!({
+            "scale": self.editor_settings.borrow().scale,
+        });
+        write!(writer, "{settings_json}")?;
+
+        Ok(())
+    }
+
+    fn load_editor_state(&mut self, reader: &mut impl Read) -> Result<(), Error> {
+        let mut settings_json = String::new();
+        reader.read_to_string(&mut settings_json)?;
+
+        // Hosts may hand us an empty editor state
+        if settings_json.is_empty() {
+            return Ok(());
+        }
+
+        let settings: serde_json::Value = serde_json::from_str(&settings_json)
+            .map_err(|_| Error::SerializationError)?;
+
+        if let Some(scale) = settings["scale"].as_f64() {
+            self.editor_settings.borrow_mut().scale = scale;
+        }
+
+        Ok(())
+    }
 }
 
 impl ClapPlugin for GainPlugin {
@@ -86,8 +125,16 @@ impl Vst3Plugin for GainPlugin {
     ];
 }
 
+/// Factory with every exported plugin, also used to generate moduleinfo.json
+pub fn vst3_factory() -> plinth_plugin::vst3::Factory {
+    plinth_plugin::vst3::Factory::new()
+        .with_plugin::<GainPlugin>()
+}
+
 export_clap!(GainPlugin);
-export_vst3!(GainPlugin);
+export_vst3!(factory = vst3_factory);
 
 #[cfg(feature = "standalone")]
-impl plinth_plugin::standalone::StandalonePlugin for GainPlugin {}
+impl plinth_plugin::standalone::StandalonePlugin for GainPlugin {
+    const HAS_AUDIO_INPUT: bool = true;
+}
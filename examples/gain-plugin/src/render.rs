@@ -0,0 +1,6 @@
+use plinth_plugin::render::run_render;
+use gain_plugin::GainPlugin;
+
+fn main() {
+    run_render::<GainPlugin>();
+}
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use plinth_plugin::{FloatParameter, Host, Parameter, Parameters};
+use plinth_plugin::{FloatParameter, Host, MidiLearn, Parameter, Parameters};
 use plugin_canvas_slint::{plugin_canvas::{event::EventResponse, Event}, view::PluginView};
 
 use crate::parameters::{GainParameter, GainParameters};
@@ -10,10 +10,11 @@ slint::include_modules!();
 pub struct GainPluginView {
     plugin_window: PluginWindow,
     parameters: Rc<GainParameters>,
+    midi_learn: Rc<MidiLearn>,
 }
 
 impl GainPluginView {
-    pub fn new(parameters: Rc<GainParameters>, host: Rc<dyn Host>) -> Self {
+    pub fn new(parameters: Rc<GainParameters>, host: Rc<dyn Host>, midi_learn: Rc<MidiLearn>) -> Self {
         let plugin_window = PluginWindow::new().unwrap();
 
         plugin_window.on_start_parameter_change({
@@ -40,9 +41,18 @@ impl GainPluginView {
             }
         });
 
+        plugin_window.on_start_midi_learn({
+            let midi_learn = midi_learn.clone();
+
+            move |id| {
+                midi_learn.start_learning(id as _);
+            }
+        });
+
         Self {
             plugin_window,
             parameters,
+            midi_learn,
         }
     }
 }
@@ -64,6 +74,7 @@ impl PluginView for GainPluginView {
                     default_normalized_value: gain_parameter.info().default_normalized_value() as _,
                     display_value: gain_parameter.to_string().into(),
                 });
+                self.plugin_window.set_learning(self.midi_learn.learning() == Some(gain_parameter.info().id()));
             }
 
             _ => {}
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use plinth_plugin::testing::{clap::TestHost, test_plugin::TestPlugin};
+use plinth_plugin_fuzz::FuzzBlocks;
+
+fuzz_target!(|input: FuzzBlocks| {
+    let block_size = usize::from(input.block_size).max(1);
+
+    let mut host = TestHost::<TestPlugin>::new();
+    assert!(host.activate(48000.0, block_size));
+
+    let mut buffer = vec![vec![0.5; block_size]; 2];
+    for events in input.events() {
+        host.process(&mut buffer, &events);
+        host.flush(&events);
+    }
+
+    host.deactivate();
+});
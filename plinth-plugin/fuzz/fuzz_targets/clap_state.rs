@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use plinth_plugin::testing::{clap::TestHost, test_plugin::TestPlugin};
+
+fuzz_target!(|data: &[u8]| {
+    let mut host = TestHost::<TestPlugin>::new();
+
+    // Whatever got loaded has to be saveable again
+    if host.load_state(data) {
+        assert!(host.save_state().is_some());
+    }
+});
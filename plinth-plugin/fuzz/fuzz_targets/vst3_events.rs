@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use plinth_plugin::{testing::{test_plugin::TestPlugin, vst3::TestHost}, Event};
+use plinth_plugin_fuzz::FuzzBlocks;
+
+fuzz_target!(|input: FuzzBlocks| {
+    let block_size = usize::from(input.block_size).max(1);
+
+    let mut host = TestHost::<TestPlugin>::new();
+    assert!(host.activate(48000.0, block_size));
+
+    let mut buffer = vec![vec![0.5; block_size]; 2];
+    for events in input.events() {
+        host.process(&mut buffer, &events);
+
+        // Edit controller changes take a different path than the ones in process()
+        for event in &events {
+            if let Event::ParameterValue { id, value, .. } = *event {
+                host.set_parameter(id, value);
+            }
+        }
+    }
+
+    host.deactivate();
+});
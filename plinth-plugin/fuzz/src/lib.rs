@@ -0,0 +1,41 @@
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use plinth_plugin::Event;
+
+/// Event as a host could send it, with any id, value or offset
+#[derive(Arbitrary, Debug)]
+pub enum FuzzEvent {
+    NoteOn { sample_offset: u16, channel: i16, key: i16, note: i32, velocity: f64 },
+    NoteOff { sample_offset: u16, channel: i16, key: i16, note: i32, velocity: f64 },
+    PitchBend { sample_offset: u16, channel: i16, semitones: f64 },
+    ControlChange { sample_offset: u16, channel: i16, controller: i16, value: f64 },
+    ParameterValue { sample_offset: u16, id: u8, value: f64 },
+    ParameterModulation { sample_offset: u16, id: u8, amount: f64 },
+}
+
+impl From<&FuzzEvent> for Event {
+    fn from(event: &FuzzEvent) -> Self {
+        // Small ids so known ones come up often
+        match *event {
+            FuzzEvent::NoteOn { sample_offset, channel, key, note, velocity } => Event::NoteOn { sample_offset: sample_offset as _, channel, key, note, velocity },
+            FuzzEvent::NoteOff { sample_offset, channel, key, note, velocity } => Event::NoteOff { sample_offset: sample_offset as _, channel, key, note, velocity },
+            FuzzEvent::PitchBend { sample_offset, channel, semitones } => Event::PitchBend { sample_offset: sample_offset as _, channel, key: -1, note: -1, semitones },
+            FuzzEvent::ControlChange { sample_offset, channel, controller, value } => Event::ControlChange { sample_offset: sample_offset as _, channel, controller, value },
+            FuzzEvent::ParameterValue { sample_offset, id, value } => Event::ParameterValue { sample_offset: sample_offset as _, id: id as _, value },
+            FuzzEvent::ParameterModulation { sample_offset, id, amount } => Event::ParameterModulation { sample_offset: sample_offset as _, id: id as _, amount },
+        }
+    }
+}
+
+/// Input for the event targets: a block size and the events of each block
+#[derive(Arbitrary, Debug)]
+pub struct FuzzBlocks {
+    pub block_size: u8,
+    pub blocks: Vec<Vec<FuzzEvent>>,
+}
+
+impl FuzzBlocks {
+    pub fn events(&self) -> impl Iterator<Item = Vec<Event>> + '_ {
+        self.blocks.iter()
+            .map(|events| events.iter().map(Event::from).collect())
+    }
+}
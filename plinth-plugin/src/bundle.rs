@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+/// Finds the bundle directory containing `binary_path`, for example `Plugin.vst3` for
+/// `Plugin.vst3/Contents/x86_64-linux/Plugin.so`
+/// Returns `binary_path` itself if the binary isn't inside a bundle
+pub(crate) fn bundle_path(binary_path: &Path, bundle_extension: &str) -> PathBuf {
+    binary_path
+        .ancestors()
+        .skip(1)
+        .find(|path| path.extension().is_some_and(|extension| extension == bundle_extension))
+        .unwrap_or(binary_path)
+        .to_path_buf()
+}
+
+/// Resources directory for a bundle, or for a plugin binary that isn't in a bundle
+///
+/// A single-file plugin gets its own directory named after the file, so that plugins sharing a folder
+/// don't share resources.
+pub(crate) fn resources_dir(bundle_path: &Path) -> Option<PathBuf> {
+    if bundle_path.is_dir() {
+        return Some(bundle_path.join("Contents").join("Resources"));
+    }
+
+    let stem = bundle_path.file_stem()?;
+    bundle_path.parent().map(|path| path.join(stem))
+}
+
+/// Path of the bundle containing the currently executing binary
+pub(crate) fn current_bundle_path(bundle_extension: &str) -> Option<PathBuf> {
+    current_binary_path().map(|binary_path| bundle_path(&binary_path, bundle_extension))
+}
+
+/// Path of the shared library (or executable) containing this code
+#[cfg(unix)]
+fn current_binary_path() -> Option<PathBuf> {
+    use std::{ffi::CStr, os::unix::ffi::OsStrExt};
+
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    let address = current_binary_path as *const libc::c_void;
+
+    if unsafe { libc::dladdr(address, &mut info) } == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+
+    let path = unsafe { CStr::from_ptr(info.dli_fname) };
+    let path = Path::new(std::ffi::OsStr::from_bytes(path.to_bytes()));
+
+    // dladdr() may return a relative path if the library was loaded with one
+    std::fs::canonicalize(path).ok()
+}
+
+/// Path of the DLL (or executable) containing this code
+#[cfg(target_os="windows")]
+fn current_binary_path() -> Option<PathBuf> {
+    use std::{ffi::{c_void, OsString}, os::windows::ffi::OsStringExt};
+
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetModuleHandleExW(flags: u32, module_name: *const u16, module: *mut *mut c_void) -> i32;
+        fn GetModuleFileNameW(module: *mut c_void, filename: *mut u16, size: u32) -> u32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    let address = current_binary_path as *const u16;
+
+    let result = unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            address,
+            &mut module,
+        )
+    };
+    if result == 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; 1024];
+
+    loop {
+        let length = unsafe { GetModuleFileNameW(module, buffer.as_mut_ptr(), buffer.len() as _) } as usize;
+
+        if length == 0 {
+            return None;
+        }
+
+        // The path was truncated, try again with a bigger buffer
+        if length >= buffer.len() {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+
+        return Some(OsString::from_wide(&buffer[..length]).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{bundle_path, resources_dir};
+
+    #[test]
+    fn vst3_bundle_layouts() {
+        let linux = Path::new("/plugins/Gain.vst3/Contents/x86_64-linux/Gain.so");
+        assert_eq!(bundle_path(linux, "vst3"), Path::new("/plugins/Gain.vst3"));
+
+        let windows = Path::new("/plugins/Gain.vst3/Contents/x86_64-win/Gain.vst3");
+        assert_eq!(bundle_path(windows, "vst3"), Path::new("/plugins/Gain.vst3"));
+
+        let single_file = Path::new("/plugins/Gain.vst3");
+        assert_eq!(bundle_path(single_file, "vst3"), single_file);
+    }
+
+    #[test]
+    fn single_file_resources_are_per_plugin() {
+        let gain = resources_dir(Path::new("/plugins/Gain.clap")).unwrap();
+        let reverb = resources_dir(Path::new("/plugins/Reverb.clap")).unwrap();
+
+        assert_eq!(gain, Path::new("/plugins/Gain"));
+        assert_eq!(reverb, Path::new("/plugins/Reverb"));
+    }
+}
@@ -1,14 +1,126 @@
 use raw_window_handle::RawWindowHandle;
 
+use crate::TrackInfo;
+
 #[cfg(target_os="linux")]
 pub(crate) const FRAME_TIMER_MILLISECONDS: u64 = 16;
 
+/// Limits on how the editor window can be resized, in the same units as Editor::window_size()
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResizeConstraints {
+    pub min_size: Option<(f64, f64)>,
+    pub max_size: Option<(f64, f64)>,
+    /// Fixed width:height ratio, for example (16, 9)
+    pub aspect_ratio: Option<(u32, u32)>,
+    /// Preferred size steps, counted from the minimum size; ignored if aspect_ratio is set
+    pub increments: Option<(f64, f64)>,
+}
+
+impl ResizeConstraints {
+    pub fn with_min_size(mut self, width: f64, height: f64) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn with_max_size(mut self, width: f64, height: f64) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn with_aspect_ratio(mut self, width: u32, height: u32) -> Self {
+        self.aspect_ratio = Some((width, height));
+        self
+    }
+
+    pub fn with_increments(mut self, width: f64, height: f64) -> Self {
+        self.increments = Some((width, height));
+        self
+    }
+
+    pub fn can_resize_horizontally(&self) -> bool {
+        match (self.min_size, self.max_size) {
+            (Some(min_size), Some(max_size)) => min_size.0 < max_size.0,
+            _ => true,
+        }
+    }
+
+    pub fn can_resize_vertically(&self) -> bool {
+        match (self.min_size, self.max_size) {
+            (Some(min_size), Some(max_size)) => min_size.1 < max_size.1,
+            _ => true,
+        }
+    }
+
+    /// Returns the supported size closest to the given size
+    pub fn constrain(&self, size: (f64, f64)) -> (f64, f64) {
+        let (min_width, min_height) = self.min_size.unwrap_or((0.0, 0.0));
+        let (max_width, max_height) = self.max_size.unwrap_or((f64::INFINITY, f64::INFINITY));
+
+        if let Some((ratio_width, ratio_height)) = self.aspect_ratio && ratio_width > 0 && ratio_height > 0 {
+            let ratio_width = ratio_width as f64;
+            let ratio_height = ratio_height as f64;
+
+            // Follow whichever dimension grew more, then fit the result between min and max size
+            let scale = (size.0 / ratio_width).max(size.1 / ratio_height)
+                .min(max_width / ratio_width)
+                .min(max_height / ratio_height)
+                .max(min_width / ratio_width)
+                .max(min_height / ratio_height);
+
+            return (ratio_width * scale, ratio_height * scale);
+        }
+
+        let mut width = size.0.clamp(min_width, max_width.max(min_width));
+        let mut height = size.1.clamp(min_height, max_height.max(min_height));
+
+        if let Some((width_step, height_step)) = self.increments {
+            if width_step > 0.0 {
+                width = Self::snap(width, min_width, max_width, width_step);
+            }
+
+            if height_step > 0.0 {
+                height = Self::snap(height, min_height, max_height, height_step);
+            }
+        }
+
+        (width, height)
+    }
+
+    fn snap(value: f64, min: f64, max: f64, step: f64) -> f64 {
+        let snapped = min + ((value - min) / step).round() * step;
+
+        if snapped > max {
+            snapped - step
+        } else {
+            snapped
+        }
+    }
+}
+
 pub trait Editor {
     const DEFAULT_SIZE: (f64, f64);
 
+    /// Set to true if the editor can be opened in its own top-level window with open_floating()
+    const SUPPORTS_FLOATING: bool = false;
+
     fn open(&mut self, parent: RawWindowHandle);
     fn close(&mut self);
 
+    /// Open the editor in a top-level window; the window should stay hidden until show() is called
+    fn open_floating(&mut self) {}
+
+    /// Keep a floating editor window on top of the given host window
+    fn set_transient(&self, _window: RawWindowHandle) {}
+
+    /// Set the title of a floating editor window
+    fn set_title(&self, _title: &str) {}
+
+    /// Show a floating editor window
+    fn show(&self) {}
+
+    /// Hide a floating editor window
+    fn hide(&self) {}
+
     /// Returns current window size
     fn window_size(&self) -> (f64, f64) {
         Self::DEFAULT_SIZE
@@ -18,11 +130,21 @@ pub trait Editor {
         false
     }
 
+    /// Limits for resizing the editor; only used if can_resize() returns true
+    fn resize_constraints(&self) -> ResizeConstraints {
+        ResizeConstraints::default()
+    }
+
     /// Called by the host to see if a window size is supported
     /// Return Some with a supported size if resizing based on the incoming size is supported
     /// Otherwise, return None
-    fn check_window_size(&self, _size: (f64, f64)) -> Option<(f64, f64)> {
-        None
+    /// By default, the incoming size is fitted to resize_constraints() if the editor can be resized
+    fn check_window_size(&self, size: (f64, f64)) -> Option<(f64, f64)> {
+        if self.can_resize() {
+            Some(self.resize_constraints().constrain(size))
+        } else {
+            None
+        }
     }
 
     /// Set new window size; should only be called when window is created and after a previous call to check_window_size()
@@ -35,6 +157,9 @@ pub trait Editor {
 
     fn on_frame(&self);
 
+    /// Called when the host reports new information about the track the plugin is on
+    fn track_info_changed(&self, _info: &TrackInfo) {}
+
     // Return true if key was handled
     fn on_key_down(&self, _key_code: keyboard_types::Code, _text: Option<String>) -> bool { false }
     fn on_key_up(&self, _key_code: keyboard_types::Code, _text: Option<String>) -> bool { false }
@@ -50,3 +175,41 @@ impl Editor for NoEditor {
 
     fn on_frame(&self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ResizeConstraints;
+
+    #[test]
+    fn constrain_min_max() {
+        let constraints = ResizeConstraints::default()
+            .with_min_size(200.0, 100.0)
+            .with_max_size(800.0, 600.0);
+
+        assert_eq!(constraints.constrain((100.0, 50.0)), (200.0, 100.0));
+        assert_eq!(constraints.constrain((1000.0, 300.0)), (800.0, 300.0));
+        assert!(constraints.can_resize_horizontally());
+    }
+
+    #[test]
+    fn constrain_aspect_ratio() {
+        let constraints = ResizeConstraints::default()
+            .with_aspect_ratio(4, 3)
+            .with_max_size(800.0, 600.0);
+
+        assert_eq!(constraints.constrain((400.0, 100.0)), (400.0, 300.0));
+        assert_eq!(constraints.constrain((100.0, 450.0)), (600.0, 450.0));
+        assert_eq!(constraints.constrain((2000.0, 100.0)), (800.0, 600.0));
+    }
+
+    #[test]
+    fn constrain_increments() {
+        let constraints = ResizeConstraints::default()
+            .with_min_size(100.0, 100.0)
+            .with_max_size(190.0, 400.0)
+            .with_increments(50.0, 25.0);
+
+        assert_eq!(constraints.constrain((130.0, 160.0)), (150.0, 150.0));
+        assert_eq!(constraints.constrain((185.0, 160.0)), (150.0, 150.0));
+    }
+}
@@ -33,6 +33,14 @@ pub enum Event {
         semitones: f64,
     },
 
+    /// MIDI CC that isn't assigned to a parameter by default
+    ControlChange {
+        sample_offset: usize,
+        channel: i16,
+        controller: i16,
+        value: f64,
+    },
+
     // Parameter events
     StartParameterChange {
         id: ParameterId,
@@ -69,12 +77,26 @@ impl Event {
             Event::NoteOn { sample_offset, .. } => *sample_offset,
             Event::NoteOff { sample_offset, .. } => *sample_offset,
             Event::PitchBend { sample_offset, .. } => *sample_offset,
+            Event::ControlChange { sample_offset, .. } => *sample_offset,
             Event::ParameterValue { sample_offset, .. } => *sample_offset,
             Event::ParameterModulation { sample_offset, .. } => *sample_offset,
 
             _ => 0
         }
     }
+
+    /// Returns the event with its sample offset, if it has one, mapped through `f`
+    pub fn with_sample_offset(self, f: impl FnOnce(usize) -> usize) -> Event {
+        match self {
+            Event::NoteOn { sample_offset, channel, key, note, velocity } => Event::NoteOn { sample_offset: f(sample_offset), channel, key, note, velocity },
+            Event::NoteOff { sample_offset, channel, key, note, velocity } => Event::NoteOff { sample_offset: f(sample_offset), channel, key, note, velocity },
+            Event::PitchBend { sample_offset, channel, key, note, semitones } => Event::PitchBend { sample_offset: f(sample_offset), channel, key, note, semitones },
+            Event::ControlChange { sample_offset, channel, controller, value } => Event::ControlChange { sample_offset: f(sample_offset), channel, controller, value },
+            Event::ParameterValue { sample_offset, id, value } => Event::ParameterValue { sample_offset: f(sample_offset), id, value },
+            Event::ParameterModulation { sample_offset, id, amount } => Event::ParameterModulation { sample_offset: f(sample_offset), id, amount },
+            event => event,
+        }
+    }
 }
 
 pub struct SignalSplitter<'signal, I, S>
@@ -131,7 +153,8 @@ where
             match next_event {
                 Event::ParameterValue { sample_offset, .. } |
                 Event::ParameterModulation { sample_offset, .. } => {
-                    let sample_offset = usize::min(sample_offset, signal.len());
+                    // Keep slices in bounds even if the host sends events out of order or beyond the signal
+                    let sample_offset = sample_offset.clamp(self.offset, signal.len());
 
                     let result = (signal.slice_mut(self.offset..sample_offset), Some(next_event));
                     self.offset = sample_offset;
@@ -11,8 +11,12 @@ pub mod vst3;
 pub enum PluginFormat {
     Auv3,
     Clap,
+    #[cfg(feature = "render")]
+    Render,
     #[cfg(feature = "standalone")]
     Standalone,
+    #[cfg(feature = "testing")]
+    Testing,
     Vst3,
 }
 
@@ -21,8 +25,12 @@ impl Display for PluginFormat {
         match self {
             PluginFormat::Auv3 => f.write_str("AUv3"),
             PluginFormat::Clap => f.write_str("CLAP"),
+            #[cfg(feature = "render")]
+            PluginFormat::Render => f.write_str("Render"),
             #[cfg(feature = "standalone")]
             PluginFormat::Standalone => f.write_str("Standalone"),
+            #[cfg(feature = "testing")]
+            PluginFormat::Testing => f.write_str("Testing"),
             PluginFormat::Vst3 => f.write_str("VST3"),
         }
     }
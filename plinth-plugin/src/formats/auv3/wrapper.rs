@@ -5,7 +5,7 @@ use plinth_core::{signals::ptr_signal::{PtrSignal, PtrSignalMut}, util::ptr::{an
 use portable_atomic::AtomicF64;
 use raw_window_handle::{AppKitWindowHandle, RawWindowHandle};
 
-use crate::{formats::PluginFormat, host::HostInfo, Editor, Event, ParameterId, Parameters, ProcessMode, ProcessState, Processor, ProcessorConfig, Transport};
+use crate::{formats::PluginFormat, host::HostInfo, rt_check, Editor, Event, ParameterId, Parameters, ProcessMode, ProcessState, Processor, ProcessorConfig, StateContext, Transport};
 use crate::auv3::{plugin::Auv3Plugin, Auv3Host, EventIterator, PLINTH_AUV3_MAX_STRING_LENGTH};
 use crate::parameters::{self, group::ParameterGroupRef, has_duplicates};
 use crate::string::copy_str_to_char8;
@@ -40,6 +40,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
         let host_info = HostInfo {
             name: None,
             format: PluginFormat::Auv3,
+            bundle_path: None,
         };
         let plugin = P::new(host_info);
 
@@ -109,6 +110,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
             min_block_size: 0,
             max_block_size: max_block_size as _,
             process_mode: ProcessMode::Realtime, // TODO
+            thread_pool: Default::default(),
         };
 
         self.sample_rate.store(sample_rate, Ordering::Release);
@@ -241,7 +243,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
         let mut reader = Auv3Reader::new(context, read);
 
         let mut plugin = self.plugin.lock().unwrap();
-        plugin.load_state(&mut reader).unwrap();
+        plugin.load_state(&mut reader, StateContext::Project).unwrap();
 
         // Send events to processor
         // TODO: Thread safety
@@ -268,7 +270,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
     ) {
         let plugin = self.plugin.lock().unwrap();
         let mut writer = Auv3Writer::new(context, write);
-        plugin.save_state(&mut writer).unwrap();
+        plugin.save_state(&mut writer, StateContext::Project).unwrap();
     }
 
     /// # Safety
@@ -373,7 +375,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
 
         let event_count = self.events_to_processor_receiver.slots();
         if event_count > 0 {
-            processor.process_events(self.events_to_processor_receiver.read_chunk(event_count).unwrap().into_iter());
+            rt_check::realtime(|| processor.process_events(self.events_to_processor_receiver.read_chunk(event_count).unwrap().into_iter()));
         }
 
         let transport = Transport::new(playing, tempo, position_samples);
@@ -394,11 +396,11 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
                 output.copy_from_signal(input);
             }
             
-            let state = processor.process(
+            let state = rt_check::realtime(|| processor.process(
                 output,
                 aux.as_ref(),
                 Some(transport),
-                &mut EventIterator::new(first_event, &self.parameter_ids));
+                &mut EventIterator::new(first_event, &self.parameter_ids)));
 
                 let tail_length_samples = match state {
                     ProcessState::Error => {
@@ -415,7 +417,7 @@ impl<P: Auv3Plugin> Auv3Wrapper<P> {
                 let tail_length_seconds = tail_length_samples as f64 / sample_rate;
                 self.tail_length_seconds.store(tail_length_seconds, ::std::sync::atomic::Ordering::Release);
         } else {
-            processor.process_events(&mut EventIterator::new(first_event, &self.parameter_ids));
+            rt_check::realtime(|| processor.process_events(&mut EventIterator::new(first_event, &self.parameter_ids)));
         };
     }
 }
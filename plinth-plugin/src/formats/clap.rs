@@ -5,11 +5,14 @@ mod extensions;
 mod factory;
 mod features;
 mod host;
+mod host_log;
 mod macros;
 mod parameters;
 mod plugin;
 mod plugin_instance;
 mod stream;
+mod thread_pool;
+mod track_info;
 mod transport;
 
 pub use entry_point::EntryPoint;
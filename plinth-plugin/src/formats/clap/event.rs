@@ -1,15 +1,19 @@
 use std::collections::BTreeMap;
 use std::ffi::c_void;
+use std::ptr::null;
 
-use clap_sys::events::{clap_event_note, clap_event_note_expression, clap_event_param_mod, clap_event_param_value, clap_input_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_TUNING};
+use clap_sys::events::{clap_event_midi, clap_event_note, clap_event_note_expression, clap_event_param_mod, clap_event_param_value, clap_input_events, clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_TUNING};
 
-use crate::{parameters::info::ParameterInfo, Event, ParameterId};
+use crate::{midi::{parse_midi, LearnedControllers}, parameters::info::ParameterInfo, Event, MidiCcAssignment, ParameterId};
 
-use super::parameters::map_parameter_value_from_clap;
+use super::parameters::{map_parameter_value_from_clap, send_event_to_host};
 
 pub struct EventIterator<'a> {
     parameter_info: &'a BTreeMap<ParameterId, ParameterInfo>,
     events: &'a clap_input_events,
+    midi_cc_assignments: &'a [MidiCcAssignment],
+    learned_controllers: Option<&'a LearnedControllers>,
+    out_events: *const clap_output_events,
     index: u32,
 }
 
@@ -18,15 +22,36 @@ impl<'a> EventIterator<'a> {
         Self {
             parameter_info,
             events,
+            midi_cc_assignments: &[],
+            learned_controllers: None,
+            out_events: null(),
             index: 0,
         }
     }
 
-    fn parameter_info(&self, parameter_id: u32, cookie: *mut c_void) -> &ParameterInfo {
+    /// Maps raw MIDI CCs to parameter changes
+    pub fn with_midi_cc_assignments(mut self, midi_cc_assignments: &'a [MidiCcAssignment]) -> Self {
+        self.midi_cc_assignments = midi_cc_assignments;
+        self
+    }
+
+    /// Maps raw MIDI CCs learned by the plugin's MIDI learn service, before the default assignments
+    pub fn with_learned_controllers(mut self, learned_controllers: Option<&'a LearnedControllers>) -> Self {
+        self.learned_controllers = learned_controllers;
+        self
+    }
+
+    /// Informs the host about parameter changes mapped from MIDI CCs, so it can keep its own state in sync
+    pub fn with_output_events(mut self, out_events: *const clap_output_events) -> Self {
+        self.out_events = out_events;
+        self
+    }
+
+    fn parameter_info(&self, parameter_id: u32, cookie: *mut c_void) -> Option<&ParameterInfo> {
         if !cookie.is_null() {
-            unsafe { &*(cookie as *mut ParameterInfo) }
+            Some(unsafe { &*(cookie as *mut ParameterInfo) })
         } else {
-            self.parameter_info.get(&parameter_id).unwrap()
+            self.parameter_info.get(&parameter_id)
         }
     }
 }
@@ -89,9 +114,30 @@ impl Iterator for EventIterator<'_> {
                     }
                 }
 
+                CLAP_EVENT_MIDI => {
+                    let event = unsafe { &*(header as *const clap_event_midi) };
+
+                    let Some(midi_event) = parse_midi(&event.data, event.header.time as _, self.midi_cc_assignments, self.learned_controllers) else {
+                        continue;
+                    };
+
+                    if matches!(midi_event, Event::ParameterValue { .. }) && !self.out_events.is_null() {
+                        send_event_to_host(self.parameter_info, self.out_events, &midi_event);
+                    }
+
+                    midi_event
+                }
+
                 CLAP_EVENT_PARAM_VALUE => {
                     let event = unsafe { &*(header as *const clap_event_param_value) };
-                    let parameter_info = self.parameter_info(event.param_id, event.cookie);
+
+                    // Hosts can send ids that were never announced, or garbage values
+                    let Some(parameter_info) = self.parameter_info(event.param_id, event.cookie) else {
+                        continue;
+                    };
+                    if !event.value.is_finite() {
+                        continue;
+                    }
 
                     let value = map_parameter_value_from_clap(parameter_info, event.value);
 
@@ -104,7 +150,13 @@ impl Iterator for EventIterator<'_> {
     
                 CLAP_EVENT_PARAM_MOD => {
                     let event = unsafe { &*(header as *const clap_event_param_mod) };
-                    let parameter_info = self.parameter_info(event.param_id, event.cookie);
+
+                    let Some(parameter_info) = self.parameter_info(event.param_id, event.cookie) else {
+                        continue;
+                    };
+                    if !event.amount.is_finite() {
+                        continue;
+                    }
 
                     let amount = map_parameter_value_from_clap(parameter_info, event.amount);
 
@@ -124,3 +176,63 @@ impl Iterator for EventIterator<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, ptr::{null, null_mut}};
+
+    use clap_sys::events::{clap_event_header, clap_event_param_value, clap_input_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE};
+
+    use crate::{parameters::info::ParameterInfo, Event};
+
+    use super::EventIterator;
+
+    fn param_value(time: u32, param_id: u32, value: f64) -> clap_event_param_value {
+        clap_event_param_value {
+            header: clap_event_header {
+                size: size_of::<clap_event_param_value>() as _,
+                time,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE,
+                flags: 0,
+            },
+            param_id,
+            cookie: null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value,
+        }
+    }
+
+    unsafe extern "C" fn size(list: *const clap_input_events) -> u32 {
+        let events = unsafe { &*((*list).ctx as *const Vec<clap_event_param_value>) };
+        events.len() as _
+    }
+
+    unsafe extern "C" fn get(list: *const clap_input_events, index: u32) -> *const clap_event_header {
+        let events = unsafe { &*((*list).ctx as *const Vec<clap_event_param_value>) };
+        events.get(index as usize).map_or(null(), |event| &event.header)
+    }
+
+    #[test]
+    fn unknown_parameters_and_non_finite_values_are_skipped() {
+        let parameter_info = BTreeMap::from([(1, ParameterInfo::new(1, "Gain".into()))]);
+
+        let events = vec![
+            param_value(0, 2, 0.5),
+            param_value(1, 1, f64::NAN),
+            param_value(2, 1, f64::NEG_INFINITY),
+            param_value(3, 1, 0.25),
+        ];
+        let input_events = clap_input_events {
+            ctx: &events as *const Vec<clap_event_param_value> as _,
+            size: Some(size),
+            get: Some(get),
+        };
+
+        let events: Vec<_> = EventIterator::new(&parameter_info, &input_events).collect();
+        assert!(matches!(events[..], [Event::ParameterValue { sample_offset: 3, id: 1, value }] if value == 0.25));
+    }
+}
@@ -2,8 +2,12 @@ pub mod audio_ports;
 pub mod gui;
 pub mod latency;
 pub mod note_ports;
+pub mod param_indication;
 pub mod params;
 pub mod render;
 pub mod state;
+pub mod state_context;
 pub mod tail;
+pub mod thread_pool;
 pub mod timer_support;
+pub mod track_info;
@@ -1,4 +1,4 @@
-use std::{ffi::{c_char, CStr}, marker::PhantomData, rc::Rc};
+use std::{ffi::{c_char, CStr}, marker::PhantomData, rc::Rc, sync::atomic::Ordering};
 
 use clap_sys::{ext::gui::{clap_gui_resize_hints, clap_plugin_gui, clap_window}, plugin::clap_plugin};
 
@@ -44,7 +44,7 @@ impl<P: ClapPlugin> Gui<P> {
     unsafe extern "C" fn is_api_supported(_plugin: *const clap_plugin, api: *const c_char, is_floating: bool) -> bool {
         log::trace!("gui::is_api_supported");
 
-        if is_floating {
+        if is_floating && !P::Editor::SUPPORTS_FLOATING {
             return false;
         }
 
@@ -66,7 +66,7 @@ impl<P: ClapPlugin> Gui<P> {
     unsafe extern "C" fn is_api_supported(_plugin: *const clap_plugin, api: *const c_char, is_floating: bool) -> bool {
         log::trace!("gui::is_api_supported");
 
-        if is_floating {
+        if is_floating && !P::Editor::SUPPORTS_FLOATING {
             return false;
         }
 
@@ -82,7 +82,8 @@ impl<P: ClapPlugin> Gui<P> {
     unsafe extern "C" fn create(plugin: *const clap_plugin, _api: *const c_char, is_floating: bool) -> bool {
         log::trace!("gui::create");
 
-        if is_floating {
+        // Floating windows aren't supported on macOS
+        if is_floating && (!P::Editor::SUPPORTS_FLOATING || cfg!(target_os="macos")) {
             return false;
         }
 
@@ -93,9 +94,13 @@ impl<P: ClapPlugin> Gui<P> {
                 instance.host_ext_params,
                 instance.host_ext_state,
                 instance.parameter_event_map.clone(),
+                instance.track_info.clone(),
+                instance.parameter_indications.clone(),
+                instance.editor_closed.clone(),
             ));
 
             instance.editor = Some(instance.plugin.as_mut().unwrap().create_editor(host));
+            instance.editor_floating = is_floating;
 
             #[cfg(target_os="linux")]
             if !instance.host_ext_timer_support.is_null() {
@@ -118,7 +123,12 @@ impl<P: ClapPlugin> Gui<P> {
             }
 
             instance.editor = None;
+            instance.editor_open = false;
+            instance.editor_floating = false;
             instance.parent_window_handle = None;
+            instance.transient_window_handle = None;
+            instance.editor_title = None;
+            instance.editor_closed.store(false, Ordering::Release);
         });
     }
 
@@ -168,10 +178,37 @@ impl<P: ClapPlugin> Gui<P> {
         })
     }
     
-    unsafe extern "C" fn get_resize_hints(_plugin: *const clap_plugin, _hints: *mut clap_gui_resize_hints) -> bool {
+    unsafe extern "C" fn get_resize_hints(plugin: *const clap_plugin, hints: *mut clap_gui_resize_hints) -> bool {
         log::trace!("gui::get_resize_hints");
 
-        false
+        if hints.is_null() {
+            return false;
+        }
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            let Some(editor) = instance.editor.as_ref() else {
+                return false;
+            };
+
+            let can_resize = editor.can_resize();
+            let constraints = editor.resize_constraints();
+            let hints = unsafe { &mut *hints };
+
+            hints.can_resize_horizontally = can_resize && constraints.can_resize_horizontally();
+            hints.can_resize_vertically = can_resize && constraints.can_resize_vertically();
+
+            if let Some((width, height)) = constraints.aspect_ratio {
+                hints.preserve_aspect_ratio = true;
+                hints.aspect_ratio_width = width;
+                hints.aspect_ratio_height = height;
+            } else {
+                hints.preserve_aspect_ratio = false;
+                hints.aspect_ratio_width = 0;
+                hints.aspect_ratio_height = 0;
+            }
+
+            true
+        })
     }
 
     unsafe extern "C" fn adjust_size(plugin: *const clap_plugin, width: *mut u32, height: *mut u32) -> bool {
@@ -234,21 +271,87 @@ impl<P: ClapPlugin> Gui<P> {
         })
     }
 
-    unsafe extern "C" fn set_transient(_plugin: *const clap_plugin, _window: *const clap_window) -> bool {
+    unsafe extern "C" fn set_transient(plugin: *const clap_plugin, window: *const clap_window) -> bool {
         log::trace!("gui::set_transient");
 
-        false
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if !instance.editor_floating || window.is_null() {
+                return false;
+            }
+
+            let transient_window_handle = crate::window_handle::from_ptr(unsafe { (*window).specific.ptr });
+            instance.transient_window_handle = Some(transient_window_handle);
+
+            if instance.editor_open && let Some(editor) = instance.editor.as_ref() {
+                editor.set_transient(transient_window_handle);
+            }
+
+            true
+        })
     }
     
-    unsafe extern "C" fn suggest_title(_plugin: *const clap_plugin, _title: *const c_char) {
+    unsafe extern "C" fn suggest_title(plugin: *const clap_plugin, title: *const c_char) {
         log::trace!("gui::suggest_title");
+
+        if title.is_null() {
+            return;
+        }
+
+        let title = unsafe { CStr::from_ptr(title) }.to_string_lossy().to_string();
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if !instance.editor_floating {
+                return;
+            }
+
+            if instance.editor_open && let Some(editor) = instance.editor.as_ref() {
+                editor.set_title(&title);
+            }
+
+            instance.editor_title = Some(title.clone());
+        });
     }
 
-    unsafe extern "C" fn show(_plugin: *const clap_plugin) -> bool {
-        true
+    unsafe extern "C" fn show(plugin: *const clap_plugin) -> bool {
+        log::trace!("gui::show");
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if !instance.editor_floating {
+                return true;
+            }
+
+            let Some(editor) = instance.editor.as_mut() else {
+                return false;
+            };
+
+            // The floating window is opened lazily so that the scale and size set by the host are applied
+            if !instance.editor_open {
+                editor.open_floating();
+                instance.editor_open = true;
+
+                if let Some(transient_window_handle) = instance.transient_window_handle {
+                    editor.set_transient(transient_window_handle);
+                }
+
+                if let Some(title) = instance.editor_title.as_ref() {
+                    editor.set_title(title);
+                }
+            }
+
+            editor.show();
+            true
+        })
     }
 
-    unsafe extern "C" fn hide(_plugin: *const clap_plugin) -> bool {
-        true
+    unsafe extern "C" fn hide(plugin: *const clap_plugin) -> bool {
+        log::trace!("gui::hide");
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if instance.editor_floating && instance.editor_open && let Some(editor) = instance.editor.as_ref() {
+                editor.hide();
+            }
+
+            true
+        })
     }
 }
@@ -1,8 +1,8 @@
 use std::marker::PhantomData;
 
-use clap_sys::{ext::note_ports::{clap_note_port_info, clap_plugin_note_ports, CLAP_NOTE_DIALECT_CLAP}, plugin::clap_plugin};
+use clap_sys::{ext::note_ports::{clap_note_port_info, clap_plugin_note_ports, CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI}, plugin::clap_plugin};
 
-use crate::{clap::ClapPlugin, string::copy_str_to_char8};
+use crate::{clap::{plugin_instance::PluginInstance, ClapPlugin}, string::copy_str_to_char8};
 
 #[repr(C)]
 pub struct NotePorts<P: ClapPlugin> {
@@ -29,8 +29,13 @@ impl<P: ClapPlugin> NotePorts<P> {
 
     // Number of ports, for either input or output
     // [main-thread]
-    unsafe extern "C" fn count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
-        if (is_input && P::HAS_NOTE_INPUT) || (!is_input && P::HAS_NOTE_OUTPUT) {
+    unsafe extern "C" fn count(plugin: *const clap_plugin, is_input: bool) -> u32 {
+        // Plugins with MIDI CC assignments or MIDI learn need a note port to receive MIDI
+        let has_note_input = P::HAS_NOTE_INPUT || PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            !instance.midi_cc_assignments.is_empty() || instance.learned_controllers.is_some()
+        });
+
+        if (is_input && has_note_input) || (!is_input && P::HAS_NOTE_OUTPUT) {
             1
         } else {
             0
@@ -43,14 +48,19 @@ impl<P: ClapPlugin> NotePorts<P> {
     unsafe extern "C" fn get(
         _plugin: *const clap_plugin,
         index: u32,
-        _is_input: bool,
+        is_input: bool,
         info: *mut clap_note_port_info,
     ) -> bool
     {
         let info = unsafe { &mut *info };
 
         info.id = index;
-        info.supported_dialects = CLAP_NOTE_DIALECT_CLAP;
+        info.supported_dialects = if is_input {
+            // MIDI is accepted for CC assignments and hosts that only send MIDI
+            CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI
+        } else {
+            CLAP_NOTE_DIALECT_CLAP
+        };
         info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
         copy_str_to_char8("Main", &mut info.name);
 
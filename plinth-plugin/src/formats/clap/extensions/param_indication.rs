@@ -0,0 +1,114 @@
+use std::{ffi::{c_char, CStr}, marker::PhantomData};
+
+use clap_sys::{color::clap_color, ext::param_indication::{clap_plugin_param_indication, CLAP_PARAM_INDICATION_AUTOMATION_NONE, CLAP_PARAM_INDICATION_AUTOMATION_OVERRIDING, CLAP_PARAM_INDICATION_AUTOMATION_PLAYING, CLAP_PARAM_INDICATION_AUTOMATION_PRESENT, CLAP_PARAM_INDICATION_AUTOMATION_RECORDING}, id::clap_id, plugin::clap_plugin};
+
+use crate::{AutomationState, Color, ParameterMapping};
+use crate::clap::{plugin_instance::PluginInstance, ClapPlugin};
+
+#[repr(transparent)]
+pub struct ParamIndication<P: ClapPlugin> {
+    raw: clap_plugin_param_indication,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: ClapPlugin> ParamIndication<P> {
+    pub const fn new() -> Self {
+        Self {
+            raw: clap_plugin_param_indication {
+                set_mapping: Some(Self::set_mapping),
+                set_automation: Some(Self::set_automation),
+            },
+
+            _phantom_plugin: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> *const clap_plugin_param_indication {
+        &self.raw
+    }
+
+    unsafe extern "C" fn set_mapping(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        has_mapping: bool,
+        color: *const clap_color,
+        label: *const c_char,
+        description: *const c_char,
+    ) {
+        log::trace!("param_indication::set_mapping");
+
+        let mapping = if has_mapping {
+            Some(ParameterMapping {
+                color: unsafe { convert_color(color) },
+                label: unsafe { convert_string(label) },
+                description: unsafe { convert_string(description) },
+            })
+        } else {
+            None
+        };
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if !instance.parameter_info.contains_key(&param_id) {
+                return;
+            }
+
+            let mut parameter_indications = instance.parameter_indications.lock().unwrap();
+            parameter_indications.entry(param_id).or_default().mapping = mapping.clone();
+        });
+    }
+
+    unsafe extern "C" fn set_automation(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        automation_state: u32,
+        color: *const clap_color,
+    ) {
+        log::trace!("param_indication::set_automation");
+
+        let automation = match automation_state {
+            CLAP_PARAM_INDICATION_AUTOMATION_NONE => AutomationState::None,
+            CLAP_PARAM_INDICATION_AUTOMATION_PRESENT => AutomationState::Present,
+            CLAP_PARAM_INDICATION_AUTOMATION_PLAYING => AutomationState::Playing,
+            CLAP_PARAM_INDICATION_AUTOMATION_RECORDING => AutomationState::Recording,
+            CLAP_PARAM_INDICATION_AUTOMATION_OVERRIDING => AutomationState::Overriding,
+            _ => {
+                log::warn!("Unknown automation state: {automation_state}");
+                return;
+            }
+        };
+
+        let automation_color = unsafe { convert_color(color) };
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            if !instance.parameter_info.contains_key(&param_id) {
+                return;
+            }
+
+            let mut parameter_indications = instance.parameter_indications.lock().unwrap();
+            let indication = parameter_indications.entry(param_id).or_default();
+            indication.automation = automation;
+            indication.automation_color = automation_color;
+        });
+    }
+}
+
+unsafe fn convert_color(color: *const clap_color) -> Option<Color> {
+    if color.is_null() {
+        return None;
+    }
+
+    let color = unsafe { &*color };
+    Some(Color::new(color.red, color.green, color.blue, color.alpha))
+}
+
+unsafe fn convert_string(string: *const c_char) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .ok()
+        .map(|string| string.to_string())
+}
@@ -1,8 +1,8 @@
 use std::{ffi::{c_char, CStr}, marker::PhantomData, sync::atomic::Ordering};
 
-use clap_sys::{events::{clap_input_events, clap_output_events}, ext::params::{CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_STEPPED, CLAP_PARAM_REQUIRES_PROCESS, clap_param_info, clap_plugin_params}, id::clap_id, plugin::clap_plugin};
+use clap_sys::{events::{clap_input_events, clap_output_events}, ext::params::{CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_ENUM, CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_READONLY, CLAP_PARAM_IS_STEPPED, CLAP_PARAM_REQUIRES_PROCESS, clap_param_info, clap_plugin_params}, id::clap_id, plugin::clap_plugin};
 
-use crate::{Parameters, clap::{ClapPlugin, event::EventIterator, parameters::{map_parameter_value_from_clap, map_parameter_value_to_clap}, plugin_instance::PluginInstance}, parameters::info::ParameterInfo, processor::Processor, string::copy_str_to_char8};
+use crate::{Parameters, clap::{ClapPlugin, event::EventIterator, parameters::{map_parameter_value_from_clap, map_parameter_value_to_clap}, plugin_instance::PluginInstance}, parameters::info::ParameterInfo, processor::Processor, rt_check, string::copy_str_to_char8};
 
 #[repr(transparent)]
 pub struct Params<P: ClapPlugin> {
@@ -57,15 +57,23 @@ impl<P: ClapPlugin> Params<P> {
             if parameter_info.is_output() {
                 clap_param_info.flags |= CLAP_PARAM_REQUIRES_PROCESS;
             }
-            if parameter_info.visible() {
+            if parameter_info.is_read_only() {
+                clap_param_info.flags |= CLAP_PARAM_IS_READONLY;
+            } else if parameter_info.visible() {
                 clap_param_info.flags |= CLAP_PARAM_IS_AUTOMATABLE | CLAP_PARAM_IS_MODULATABLE
-            } else {
+            }
+
+            if !parameter_info.visible() {
                 clap_param_info.flags |= CLAP_PARAM_IS_HIDDEN;
             }
 
             let steps = parameter_info.steps();
             if steps > 0 {
                 clap_param_info.flags |= CLAP_PARAM_IS_STEPPED;
+
+                if parameter_info.is_list() {
+                    clap_param_info.flags |= CLAP_PARAM_IS_ENUM;
+                }
                 clap_param_info.max_value = steps as f64;
             } else {
                 clap_param_info.max_value = 1.0;
@@ -142,7 +150,10 @@ impl<P: ClapPlugin> Params<P> {
         PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
             instance.process_events_to_plugin();
 
-            let host_events = EventIterator::new(&instance.parameter_info, unsafe { &*in_events });    
+            let host_events = EventIterator::new(&instance.parameter_info, unsafe { &*in_events })
+                .with_midi_cc_assignments(&instance.midi_cc_assignments)
+                .with_learned_controllers(instance.learned_controllers.as_deref())
+                .with_output_events(out_events);
             let editor_events = instance.parameter_event_map.iter_and_send_to_host(&instance.parameter_info, out_events);
             let all_events = host_events.chain(editor_events);
 
@@ -153,7 +164,7 @@ impl<P: ClapPlugin> Params<P> {
                 };
 
                 // When we have a processor, process events directly
-                processor.process_events(all_events);
+                rt_check::realtime(|| processor.process_events(all_events));
                 drop(processor_ref);
     
                 // Also send them to the main thread through the queue
@@ -163,8 +174,9 @@ impl<P: ClapPlugin> Params<P> {
                 unsafe { ((*instance.host).request_callback.unwrap())(instance.host); }
             } else {
                 // When we don't have a processor, this is called from the main thread so we can process events directly
+                let all_events: Vec<_> = all_events.collect();
                 for event in all_events {
-                    instance.plugin.as_mut().unwrap().process_event(&event);
+                    instance.process_event(&event);
                 }
             }
         })
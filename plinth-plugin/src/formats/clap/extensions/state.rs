@@ -2,6 +2,8 @@ use std::marker::PhantomData;
 
 use clap_sys::{ext::state::clap_plugin_state, plugin::clap_plugin, stream::{clap_istream, clap_ostream}};
 
+use crate::StateContext;
+use crate::state::{load_plugin_state, save_plugin_state};
 use crate::clap::{plugin_instance::PluginInstance, stream::{InputStream, OutputStream}, ClapPlugin};
 
 #[repr(transparent)]
@@ -27,13 +29,13 @@ impl<P: ClapPlugin> State<P> {
         &self.raw
     }
 
-    unsafe extern "C" fn save(plugin: *const clap_plugin, stream: *const clap_ostream) -> bool {
+    pub(super) fn save_with_context(plugin: *const clap_plugin, stream: *const clap_ostream, context: StateContext) -> bool {
         let mut stream = OutputStream::new(stream);
 
         PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
             instance.process_events_to_plugin();
 
-            match instance.plugin.as_ref().unwrap().save_state(&mut stream) {
+            match save_plugin_state(instance.plugin.as_ref().unwrap(), &mut stream, context) {
                 Ok(_) => true,
                 Err(e) => {
                     log::error!("Error saving state: {:?}", e);
@@ -43,13 +45,13 @@ impl<P: ClapPlugin> State<P> {
         })
     }
 
-    unsafe extern "C" fn load(plugin: *const clap_plugin, stream: *const clap_istream) -> bool {
+    pub(super) fn load_with_context(plugin: *const clap_plugin, stream: *const clap_istream, context: StateContext) -> bool {
         let mut stream = InputStream::new(stream);
 
         PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
             instance.process_events_to_plugin();
 
-            match instance.plugin.as_mut().unwrap().load_state(&mut stream) {
+            match load_plugin_state(instance.plugin.as_mut().unwrap(), &mut stream, context) {
                 Ok(_) => true,
                 Err(e) => {
                     log::error!("Error loading state: {e:?}");
@@ -58,4 +60,13 @@ impl<P: ClapPlugin> State<P> {
             }
         })
     }
+
+    unsafe extern "C" fn save(plugin: *const clap_plugin, stream: *const clap_ostream) -> bool {
+        // Hosts use the state context extension for anything other than project saves
+        Self::save_with_context(plugin, stream, StateContext::Project)
+    }
+
+    unsafe extern "C" fn load(plugin: *const clap_plugin, stream: *const clap_istream) -> bool {
+        Self::load_with_context(plugin, stream, StateContext::Project)
+    }
 }
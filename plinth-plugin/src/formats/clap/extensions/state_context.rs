@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+
+use clap_sys::{ext::state_context::{clap_plugin_state_context, clap_plugin_state_context_type, CLAP_STATE_CONTEXT_FOR_DUPLICATE, CLAP_STATE_CONTEXT_FOR_PRESET, CLAP_STATE_CONTEXT_FOR_PROJECT}, plugin::clap_plugin, stream::{clap_istream, clap_ostream}};
+
+use crate::StateContext;
+use crate::clap::ClapPlugin;
+
+use super::state::State;
+
+#[repr(transparent)]
+pub struct StateContextExtension<P: ClapPlugin> {
+    raw: clap_plugin_state_context,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: ClapPlugin> StateContextExtension<P> {
+    pub const fn new() -> Self {
+        Self {
+            raw: clap_plugin_state_context {
+                save: Some(Self::save),
+                load: Some(Self::load),
+            },
+
+            _phantom_plugin: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> *const clap_plugin_state_context {
+        &self.raw
+    }
+
+    unsafe extern "C" fn save(plugin: *const clap_plugin, stream: *const clap_ostream, context_type: clap_plugin_state_context_type) -> bool {
+        let Some(context) = convert_context_type(context_type) else {
+            return false;
+        };
+
+        State::<P>::save_with_context(plugin, stream, context)
+    }
+
+    unsafe extern "C" fn load(plugin: *const clap_plugin, stream: *const clap_istream, context_type: clap_plugin_state_context_type) -> bool {
+        let Some(context) = convert_context_type(context_type) else {
+            return false;
+        };
+
+        State::<P>::load_with_context(plugin, stream, context)
+    }
+}
+
+fn convert_context_type(context_type: clap_plugin_state_context_type) -> Option<StateContext> {
+    match context_type {
+        CLAP_STATE_CONTEXT_FOR_PRESET => Some(StateContext::Preset),
+        CLAP_STATE_CONTEXT_FOR_DUPLICATE => Some(StateContext::Duplicate),
+        CLAP_STATE_CONTEXT_FOR_PROJECT => Some(StateContext::Project),
+        _ => None,
+    }
+}
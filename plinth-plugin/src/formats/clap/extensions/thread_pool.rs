@@ -0,0 +1,41 @@
+use std::marker::PhantomData;
+
+use clap_sys::{ext::thread_pool::clap_plugin_thread_pool, plugin::clap_plugin};
+
+use crate::clap::{thread_pool::TaskSlot, ClapPlugin};
+
+#[repr(transparent)]
+pub struct ThreadPool<P: ClapPlugin> {
+    raw: clap_plugin_thread_pool,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: ClapPlugin> ThreadPool<P> {
+    pub const fn new() -> Self {
+        Self {
+            raw: clap_plugin_thread_pool {
+                exec: Some(Self::exec),
+            },
+
+            _phantom_plugin: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> *const clap_plugin_thread_pool {
+        &self.raw
+    }
+
+    unsafe extern "C" fn exec(plugin: *const clap_plugin, task_index: u32) {
+        assert!(!plugin.is_null());
+
+        // This is called from several threads at once while the audio thread is borrowing the plugin instance,
+        // so only go through the task slot that plugin_data points to
+        let task_slot = unsafe { (*plugin).plugin_data } as *const TaskSlot;
+        if task_slot.is_null() {
+            return;
+        }
+
+        unsafe { (*task_slot).exec(task_index) };
+    }
+}
@@ -0,0 +1,36 @@
+use std::marker::PhantomData;
+
+use clap_sys::{ext::track_info::clap_plugin_track_info, plugin::clap_plugin};
+
+use crate::clap::{plugin_instance::PluginInstance, ClapPlugin};
+
+#[repr(transparent)]
+pub struct TrackInfoExtension<P: ClapPlugin> {
+    raw: clap_plugin_track_info,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: ClapPlugin> TrackInfoExtension<P> {
+    pub const fn new() -> Self {
+        Self {
+            raw: clap_plugin_track_info {
+                changed: Some(Self::changed),
+            },
+
+            _phantom_plugin: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> *const clap_plugin_track_info {
+        &self.raw
+    }
+
+    unsafe extern "C" fn changed(plugin: *const clap_plugin) {
+        log::trace!("track_info::changed");
+
+        PluginInstance::with_plugin_instance(plugin, |instance: &mut PluginInstance<P>| {
+            instance.update_track_info();
+        });
+    }
+}
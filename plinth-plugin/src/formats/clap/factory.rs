@@ -1,20 +1,27 @@
-use std::{ffi::{c_char, CStr}, marker::PhantomData, ptr::null};
+use std::{ffi::{c_char, CStr}, path::{Path, PathBuf}, ptr::null};
 
 use clap_sys::{factory::plugin_factory::clap_plugin_factory, host::clap_host, plugin::{clap_plugin, clap_plugin_descriptor}};
 
 use super::{descriptor::Descriptor, plugin::ClapPlugin, plugin_instance::PluginInstance};
 
+type CreatePluginFn = fn(descriptor: &Descriptor, host: *const clap_host, bundle_path: Option<PathBuf>) -> *const clap_plugin;
+
+struct FactoryEntry {
+    descriptor: Descriptor,
+    create_plugin: CreatePluginFn,
+}
+
 #[repr(C)]
-pub struct Factory<P: ClapPlugin> {
+pub struct Factory {
     raw: clap_plugin_factory,
-    count: usize,
+    // Number of init() calls without a matching deinit(), not the number of plugins
+    ref_count: usize,
 
-    descriptor: Descriptor,
-    
-    _phantom_plugin: PhantomData<P>,
+    entries: Vec<FactoryEntry>,
+    bundle_path: Option<PathBuf>,
 }
 
-impl<P: ClapPlugin> Factory<P> {
+impl Factory {
     pub fn new() -> Self {
         Self {
             raw: clap_plugin_factory {
@@ -22,31 +29,64 @@ impl<P: ClapPlugin> Factory<P> {
                 get_plugin_descriptor: Some(Self::get_plugin_descriptor),
                 create_plugin: Some(Self::create_plugin),
             },
-            count: 1,
+            ref_count: 1,
 
-            descriptor: Descriptor::new::<P>(),
+            entries: Vec::new(),
+            bundle_path: None,
+        }
+    }
 
-            _phantom_plugin: PhantomData,
+    /// Sets the plugin path passed to the entry point's `init()`
+    ///
+    /// # Safety
+    ///
+    /// `plugin_path` must be null or a valid C string
+    pub unsafe fn with_plugin_path(mut self, plugin_path: *const c_char) -> Self {
+        if !plugin_path.is_null() && let Ok(plugin_path) = unsafe { CStr::from_ptr(plugin_path) }.to_str() {
+            self.bundle_path = Some(Path::new(plugin_path).to_path_buf());
         }
+
+        self
+    }
+
+    pub fn with_plugin<P: ClapPlugin>(mut self) -> Self {
+        let descriptor = Descriptor::new::<P>();
+
+        assert!(
+            self.entries.iter().all(|entry| entry.descriptor.id() != descriptor.id()),
+            "Duplicate CLAP id {}", P::CLAP_ID,
+        );
+
+        self.entries.push(FactoryEntry {
+            descriptor,
+            create_plugin: create_plugin::<P>,
+        });
+
+        self
     }
 
     pub fn as_raw(&self) -> *const clap_plugin_factory {
         &self.raw
     }
 
-    pub fn count(&self) -> usize {
-        self.count
+    /// Number of plugins registered with with_plugin()
+    pub fn plugin_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn ref_count(&self) -> usize {
+        self.ref_count
     }
 
     pub fn add_ref(&mut self) -> usize {
-        self.count += 1;
-        self.count
+        self.ref_count += 1;
+        self.ref_count
     }
 
     pub fn remove_ref(&mut self) -> usize {
-        assert!(self.count > 0);
-        self.count -= 1;
-        self.count
+        assert!(self.ref_count > 0);
+        self.ref_count -= 1;
+        self.ref_count
     }
 
     /// # Safety
@@ -64,8 +104,9 @@ impl<P: ClapPlugin> Factory<P> {
         true
     }
 
-    unsafe extern "C" fn get_plugin_count(_factory: *const clap_plugin_factory) -> u32 {
-        1
+    unsafe extern "C" fn get_plugin_count(factory: *const clap_plugin_factory) -> u32 {
+        let factory = unsafe { &*(factory as *const Self) };
+        factory.plugin_count() as _
     }
     
     unsafe extern "C" fn get_plugin_descriptor(
@@ -75,10 +116,9 @@ impl<P: ClapPlugin> Factory<P> {
     {
         let factory = unsafe { &*(factory as *const Self) };
 
-        if index == 0 {
-            factory.descriptor.as_raw()
-        } else {
-            null()
+        match factory.entries.get(index as usize) {
+            Some(entry) => entry.descriptor.as_raw(),
+            None => null(),
         }
     }
     
@@ -93,19 +133,25 @@ impl<P: ClapPlugin> Factory<P> {
         if plugin_id.is_null() {
             return null();
         }
-        if unsafe { CStr::from_ptr(plugin_id) } != factory.descriptor.id() {
-            return null();
-        }
 
-        let instance = Box::new(PluginInstance::<P>::new(&factory.descriptor, host));
-        Box::into_raw(instance) as _
+        let plugin_id = unsafe { CStr::from_ptr(plugin_id) };
+
+        match factory.entries.iter().find(|entry| entry.descriptor.id() == plugin_id) {
+            Some(entry) => (entry.create_plugin)(&entry.descriptor, host, factory.bundle_path.clone()),
+            None => null(),
+        }
     }
 }
 
-impl<P: ClapPlugin> Default for Factory<P> {
+fn create_plugin<P: ClapPlugin>(descriptor: &Descriptor, host: *const clap_host, bundle_path: Option<PathBuf>) -> *const clap_plugin {
+    let instance = Box::new(PluginInstance::<P>::new(descriptor, host, bundle_path));
+    Box::into_raw(instance) as _
+}
+
+impl Default for Factory {
     fn default() -> Self {
         Self::new()
     }
 }
 
-unsafe impl<P: ClapPlugin> Send for Factory<P> {}
+unsafe impl Send for Factory {}
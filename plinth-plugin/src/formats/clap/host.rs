@@ -1,8 +1,8 @@
-use std::{sync::{atomic::Ordering, Arc}};
+use std::{collections::BTreeMap, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
 
 use clap_sys::{ext::{gui::clap_host_gui, params::clap_host_params, state::clap_host_state}, host::clap_host};
 
-use crate::{Host, ParameterId, ParameterValue};
+use crate::{Host, ParameterId, ParameterIndication, ParameterValue, TrackInfo};
 
 use super::parameters::ParameterEventMap;
 
@@ -12,6 +12,9 @@ pub struct ClapHost {
     host_ext_params: *const clap_host_params,
     host_ext_state: *const clap_host_state,
     parameter_event_map: Arc<ParameterEventMap>,
+    track_info: Arc<Mutex<Option<TrackInfo>>>,
+    parameter_indications: Arc<Mutex<BTreeMap<ParameterId, ParameterIndication>>>,
+    editor_closed: Arc<AtomicBool>,
 }
 
 impl ClapHost {
@@ -21,6 +24,9 @@ impl ClapHost {
         host_ext_params: *const clap_host_params,
         host_ext_state: *const clap_host_state,
         parameter_event_map: Arc<ParameterEventMap>,
+        track_info: Arc<Mutex<Option<TrackInfo>>>,
+        parameter_indications: Arc<Mutex<BTreeMap<ParameterId, ParameterIndication>>>,
+        editor_closed: Arc<AtomicBool>,
     ) -> Self {
         assert!(!raw.is_null());
 
@@ -30,6 +36,9 @@ impl ClapHost {
             host_ext_params,
             host_ext_state,
             parameter_event_map,
+            track_info,
+            parameter_indications,
+            editor_closed,
         }
     }
 }
@@ -85,6 +94,24 @@ impl Host for ClapHost {
             unsafe { ((*self.host_ext_state).mark_dirty.unwrap())(self.raw) };
         }
     }
+
+    // This is usually called from the editor's event handling, and the host might destroy the editor
+    // in clap_host_gui::closed(), so it's reported from on_main_thread() instead
+    fn editor_closed(&self) {
+        self.editor_closed.store(true, Ordering::Release);
+        unsafe { ((*self.raw).request_callback.unwrap())(self.raw) };
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.track_info.lock().unwrap().clone()
+    }
+
+    fn parameter_indication(&self, id: ParameterId) -> ParameterIndication {
+        self.parameter_indications.lock().unwrap()
+            .get(&id)
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
 /// SAFETY: clap_host functions are thread-safe
@@ -0,0 +1,43 @@
+use std::ffi::CString;
+
+use clap_sys::{ext::log::{clap_host_log, clap_log_severity, CLAP_LOG_DEBUG, CLAP_LOG_ERROR, CLAP_LOG_INFO, CLAP_LOG_WARNING}, host::clap_host};
+use log::Level;
+
+use crate::logging::LogSink;
+
+pub struct ClapLogSink {
+    host: *const clap_host,
+    host_ext_log: *const clap_host_log,
+}
+
+impl ClapLogSink {
+    pub fn new(host: *const clap_host, host_ext_log: *const clap_host_log) -> Self {
+        assert!(!host.is_null());
+        assert!(!host_ext_log.is_null());
+
+        Self {
+            host,
+            host_ext_log,
+        }
+    }
+}
+
+impl LogSink for ClapLogSink {
+    fn log(&self, level: Level, message: &str) {
+        let severity: clap_log_severity = match level {
+            Level::Error => CLAP_LOG_ERROR,
+            Level::Warn => CLAP_LOG_WARNING,
+            Level::Info => CLAP_LOG_INFO,
+            Level::Debug | Level::Trace => CLAP_LOG_DEBUG,
+        };
+
+        let message = message.replace('\0', " ");
+        let message = CString::new(message).unwrap();
+
+        unsafe { ((*self.host_ext_log).log.unwrap())(self.host, severity, message.as_ptr()) };
+    }
+}
+
+/// SAFETY: clap_host_log functions are thread-safe
+unsafe impl Send for ClapLogSink {}
+unsafe impl Sync for ClapLogSink {}
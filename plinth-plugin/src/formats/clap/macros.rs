@@ -1,9 +1,9 @@
 #[macro_export]
 macro_rules! export_clap {
-    ($plugin:ty) => {
-        static FACTORY: ::std::sync::Mutex<Option<::plinth_plugin::clap::Factory::<$plugin>>> = ::std::sync::Mutex::new(None);
+    ($($plugin:ty),+ $(,)?) => {
+        static FACTORY: ::std::sync::Mutex<Option<::plinth_plugin::clap::Factory>> = ::std::sync::Mutex::new(None);
 
-        unsafe extern "C" fn init(_plugin_path: *const ::std::ffi::c_char) -> bool {
+        unsafe extern "C" fn init(plugin_path: *const ::std::ffi::c_char) -> bool {
             let mut factory = FACTORY.lock().unwrap();
         
             match factory.as_mut() {
@@ -12,7 +12,10 @@ macro_rules! export_clap {
                 },
         
                 None => {
-                    *factory = Some(::plinth_plugin::clap::Factory::<$plugin>::new());
+                    *factory = Some(
+                        unsafe { ::plinth_plugin::clap::Factory::new().with_plugin_path(plugin_path) }
+                            $(.with_plugin::<$plugin>())+
+                    );
                 }
             }
         
@@ -37,7 +40,7 @@ macro_rules! export_clap {
         }
         
         unsafe extern "C" fn get_factory(factory_id: *const ::std::ffi::c_char) -> *const ::std::ffi::c_void {
-            if unsafe { !::plinth_plugin::clap::Factory::<$plugin>::is_valid_factory_id(factory_id) } {
+            if unsafe { !::plinth_plugin::clap::Factory::is_valid_factory_id(factory_id) } {
                 return ::std::ptr::null();
             }
         
@@ -68,65 +68,7 @@ pub struct ParameterEventMapIterator<'a> {
 
 impl ParameterEventMapIterator<'_> {
     pub fn send_event_to_host(&self, event: &Event) {
-        let out_events = unsafe { &*self.out_events };
-
-        match event {
-            Event::StartParameterChange { id } => {
-                let clap_event = clap_event_param_gesture {
-                    header: clap_event_header {
-                        size: size_of::<clap_event_param_gesture>() as _,
-                        time: 0,
-                        space_id: CLAP_CORE_EVENT_SPACE_ID,
-                        type_: CLAP_EVENT_PARAM_GESTURE_BEGIN,
-                        flags: CLAP_EVENT_IS_LIVE,
-                    },
-                    param_id: *id,
-                };
-
-                unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_gesture as _) };
-            },
-
-            Event::EndParameterChange { id } => {
-                let clap_event = clap_event_param_gesture {
-                    header: clap_event_header {
-                        size: size_of::<clap_event_param_gesture>() as _,
-                        time: 0,
-                        space_id: CLAP_CORE_EVENT_SPACE_ID,
-                        type_: CLAP_EVENT_PARAM_GESTURE_END,
-                        flags: CLAP_EVENT_IS_LIVE,
-                    },
-                    param_id: *id,
-                };
-
-                unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_gesture as _) };                    
-            },
-
-            Event::ParameterValue { id, value, .. } => {
-                let parameter_info = self.parameter_info.get(id).unwrap();
-                let value = map_parameter_value_to_clap(parameter_info, *value);
-
-                let clap_event = clap_event_param_value {
-                    header: clap_event_header {
-                        size: size_of::<clap_event_param_value>() as _,
-                        time: 0,
-                        space_id: CLAP_CORE_EVENT_SPACE_ID,
-                        type_: CLAP_EVENT_PARAM_VALUE,
-                        flags: CLAP_EVENT_IS_LIVE,
-                    },
-                    param_id: *id,
-                    cookie: null_mut(),
-                    note_id: 0,
-                    port_index: 0,
-                    channel: 0,
-                    key: 0,
-                    value,
-                };
-
-                unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_value as _) };
-            },
-
-            _ => {},
-        }
+        send_event_to_host(self.parameter_info, self.out_events, event);
     }
 }
 
@@ -165,6 +107,69 @@ impl Iterator for ParameterEventMapIterator<'_> {
     }
 }
 
+/// Informs the host about a parameter change or gesture originating from the plugin
+pub fn send_event_to_host(parameter_info: &BTreeMap<ParameterId, ParameterInfo>, out_events: *const clap_output_events, event: &Event) {
+    let out_events = unsafe { &*out_events };
+
+    match event {
+        Event::StartParameterChange { id } => {
+            let clap_event = clap_event_param_gesture {
+                header: clap_event_header {
+                    size: size_of::<clap_event_param_gesture>() as _,
+                    time: 0,
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: CLAP_EVENT_PARAM_GESTURE_BEGIN,
+                    flags: CLAP_EVENT_IS_LIVE,
+                },
+                param_id: *id,
+            };
+
+            unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_gesture as _) };
+        },
+
+        Event::EndParameterChange { id } => {
+            let clap_event = clap_event_param_gesture {
+                header: clap_event_header {
+                    size: size_of::<clap_event_param_gesture>() as _,
+                    time: 0,
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: CLAP_EVENT_PARAM_GESTURE_END,
+                    flags: CLAP_EVENT_IS_LIVE,
+                },
+                param_id: *id,
+            };
+
+            unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_gesture as _) };                    
+        },
+
+        Event::ParameterValue { id, value, .. } => {
+            let parameter_info = parameter_info.get(id).unwrap();
+            let value = map_parameter_value_to_clap(parameter_info, *value);
+
+            let clap_event = clap_event_param_value {
+                header: clap_event_header {
+                    size: size_of::<clap_event_param_value>() as _,
+                    time: 0,
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: CLAP_EVENT_PARAM_VALUE,
+                    flags: CLAP_EVENT_IS_LIVE,
+                },
+                param_id: *id,
+                cookie: null_mut(),
+                note_id: 0,
+                port_index: 0,
+                channel: 0,
+                key: 0,
+                value,
+            };
+
+            unsafe { (out_events.try_push.unwrap())(out_events, &clap_event as *const clap_event_param_value as _) };
+        },
+
+        _ => {},
+    }
+}
+
 pub fn map_parameter_value_to_clap(info: &ParameterInfo, value: f64) -> f64 {
     let steps = info.steps();
     if steps > 0 {
@@ -1,17 +1,17 @@
-use std::{collections::BTreeMap, ffi::{CStr, c_char, c_void}, iter::zip, ptr::{null, null_mut}, sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}}};
+use std::{collections::BTreeMap, ffi::{CStr, c_char, c_void}, iter::zip, mem::MaybeUninit, path::PathBuf, ptr::null, sync::{Arc, Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}}};
 
 use atomic_refcell::AtomicRefCell;
-use clap_sys::{events::clap_input_events, ext::{audio_ports::CLAP_EXT_AUDIO_PORTS, gui::{clap_host_gui, CLAP_EXT_GUI}, latency::CLAP_EXT_LATENCY, note_ports::CLAP_EXT_NOTE_PORTS, params::{clap_host_params, CLAP_EXT_PARAMS}, render::CLAP_EXT_RENDER, state::{clap_host_state, CLAP_EXT_STATE}, tail::{clap_host_tail, CLAP_EXT_TAIL}, timer_support::{clap_host_timer_support, CLAP_EXT_TIMER_SUPPORT}}, host::clap_host, plugin::clap_plugin, process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE, CLAP_PROCESS_CONTINUE_IF_NOT_QUIET, CLAP_PROCESS_ERROR, CLAP_PROCESS_TAIL}};
+use clap_sys::{events::clap_input_events, ext::{audio_ports::CLAP_EXT_AUDIO_PORTS, gui::{clap_host_gui, CLAP_EXT_GUI}, latency::CLAP_EXT_LATENCY, log::{clap_host_log, CLAP_EXT_LOG}, note_ports::CLAP_EXT_NOTE_PORTS, param_indication::{CLAP_EXT_PARAM_INDICATION, CLAP_EXT_PARAM_INDICATION_COMPAT}, params::{clap_host_params, CLAP_EXT_PARAMS}, render::CLAP_EXT_RENDER, state::{clap_host_state, CLAP_EXT_STATE}, state_context::CLAP_EXT_STATE_CONTEXT, tail::{clap_host_tail, CLAP_EXT_TAIL}, thread_pool::{clap_host_thread_pool, CLAP_EXT_THREAD_POOL}, timer_support::{clap_host_timer_support, CLAP_EXT_TIMER_SUPPORT}, track_info::{clap_host_track_info, CLAP_EXT_TRACK_INFO, CLAP_EXT_TRACK_INFO_COMPAT}}, host::clap_host, plugin::clap_plugin, process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE, CLAP_PROCESS_CONTINUE_IF_NOT_QUIET, CLAP_PROCESS_ERROR, CLAP_PROCESS_TAIL}};
 use log::error;
 use plinth_core::signals::{ptr_signal::{PtrSignal, PtrSignalMut}, signal::SignalMut};
 use raw_window_handle::RawWindowHandle;
 
-use crate::{formats::PluginFormat, host::HostInfo, Event, ParameterId, ProcessMode, ProcessState, Processor, ProcessorConfig};
-use crate::clap::{event::EventIterator, transport::convert_transport};
-use crate::parameters::{info::ParameterInfo, has_duplicates, Parameters};
+use crate::{formats::PluginFormat, host::HostInfo, logging::{self, LogRegistration}, midi::LearnedControllers, rt_check, Event, MidiCcAssignment, MidiLearn, ParameterId, ProcessMode, ProcessState, Processor, ProcessorConfig, ThreadPool, TrackInfo};
+use crate::clap::{event::EventIterator, host_log::ClapLogSink, thread_pool::{ClapThreadPool, TaskSlot}, track_info::convert_track_info, transport::convert_transport};
+use crate::parameters::{indication::ParameterIndication, info::ParameterInfo, has_duplicates, Parameters};
 
 use super::descriptor::Descriptor;
-use super::extensions::{audio_ports::AudioPorts, gui::Gui, latency::Latency, note_ports::NotePorts, params::Params, render::Render, state::State, tail::Tail, timer_support::TimerSupport};
+use super::extensions::{audio_ports::AudioPorts, gui::Gui, latency::Latency, note_ports::NotePorts, param_indication::ParamIndication, params::Params, render::Render, state::State, state_context::StateContextExtension, tail::Tail, thread_pool::ThreadPool as ThreadPoolExtension, timer_support::TimerSupport, track_info::TrackInfoExtension};
 use super::parameters::ParameterEventMap;
 use super::plugin::ClapPlugin;
 
@@ -41,7 +41,13 @@ pub struct PluginInstance<P: ClapPlugin> {
     pub(super) plugin: Option<P>,
     pub(super) editor: Option<P::Editor>,
     pub(super) editor_open: bool,
+    pub(super) editor_floating: bool,
+    pub(super) transient_window_handle: Option<RawWindowHandle>,
+    pub(super) editor_title: Option<String>,
+    pub(super) editor_closed: Arc<AtomicBool>,
     pub(super) parameter_info: BTreeMap<ParameterId, ParameterInfo>,
+    pub(super) midi_cc_assignments: Vec<MidiCcAssignment>,
+    pub(super) learned_controllers: Option<Arc<LearnedControllers>>,
 
     sample_rate: f64,
     pub(super) timer_id: Option<u32>,
@@ -50,15 +56,24 @@ pub struct PluginInstance<P: ClapPlugin> {
     pub(super) to_plugin_event_sender: rtrb::Producer<Event>,
     to_plugin_event_receiver: rtrb::Consumer<Event>,
     pub(super) parameter_event_map: Arc<ParameterEventMap>,
+    pub(super) track_info: Arc<Mutex<Option<TrackInfo>>>,
+    pub(super) parameter_indications: Arc<Mutex<BTreeMap<ParameterId, ParameterIndication>>>,
+    pub(super) thread_pool: Option<Arc<ClapThreadPool>>,
+    // Pointed to by raw.plugin_data
+    thread_pool_task_slot: Arc<TaskSlot>,
+    log_registration: Option<LogRegistration>,
 
     pub(super) audio_thread_state: AudioThreadState<P>,
 
     // Host extensions
     pub(super) host_ext_gui: *const clap_host_gui,
+    host_ext_log: *const clap_host_log,
     pub(super) host_ext_params: *const clap_host_params,
     pub(super) host_ext_state: *const clap_host_state,
     host_ext_tail: *const clap_host_tail,
+    host_ext_thread_pool: *const clap_host_thread_pool,
     pub(super) host_ext_timer_support: *const clap_host_timer_support,
+    host_ext_track_info: *const clap_host_track_info,
 }
 
 impl<P: ClapPlugin> PluginInstance<P> {
@@ -67,13 +82,17 @@ impl<P: ClapPlugin> PluginInstance<P> {
     const EXT_GUI: Gui<P> = Gui::new();
     const EXT_LATENCY: Latency<P> = Latency::new();
     const EXT_NOTE_PORTS: NotePorts<P> = NotePorts::new();
+    const EXT_PARAM_INDICATION: ParamIndication<P> = ParamIndication::new();
     const EXT_PARAMS: Params<P> = Params::new();
     const EXT_RENDER: Render<P> = Render::new();
     const EXT_STATE: State<P> = State::new();
+    const EXT_STATE_CONTEXT: StateContextExtension<P> = StateContextExtension::new();
     const EXT_TAIL: Tail<P> = Tail::new();
+    const EXT_THREAD_POOL: ThreadPoolExtension<P> = ThreadPoolExtension::new();
     const EXT_TIMER_SUPPORT: TimerSupport<P> = TimerSupport::new();
+    const EXT_TRACK_INFO: TrackInfoExtension<P> = TrackInfoExtension::new();
 
-    pub fn new(descriptor: &Descriptor, host: *const clap_host) -> Self {
+    pub fn new(descriptor: &Descriptor, host: *const clap_host, bundle_path: Option<PathBuf>) -> Self {
         let host_name = unsafe { CStr::from_ptr((*host).name)
             .to_str()
             .ok()
@@ -83,6 +102,7 @@ impl<P: ClapPlugin> PluginInstance<P> {
         let host_info = HostInfo {
             name: host_name,
             format: PluginFormat::Clap,
+            bundle_path,
         };
 
         let plugin = P::new(host_info);
@@ -110,10 +130,19 @@ impl<P: ClapPlugin> PluginInstance<P> {
             Arc::new(ParameterEventMap::new(parameters))
         });
 
+        let midi_cc_assignments = plugin.midi_cc_assignments();
+        assert!(
+            midi_cc_assignments.iter().all(|assignment| parameter_info.contains_key(&assignment.parameter_id)),
+            "MIDI CCs can only be assigned to existing parameters"
+        );
+        let learned_controllers = plugin.midi_learn().map(MidiLearn::learned_controllers);
+
+        let thread_pool_task_slot = Arc::new(TaskSlot::default());
+
         Self {
             raw: clap_plugin {
                 desc: descriptor.as_raw() as _,
-                plugin_data: null_mut(),
+                plugin_data: Arc::as_ptr(&thread_pool_task_slot) as *mut c_void,
                 init: Some(Self::init),
                 destroy: Some(Self::destroy),
                 activate: Some(Self::activate),
@@ -131,7 +160,13 @@ impl<P: ClapPlugin> PluginInstance<P> {
             plugin: Some(plugin),
             editor: None,
             editor_open: false,
+            editor_floating: false,
+            transient_window_handle: None,
+            editor_title: None,
+            editor_closed: Default::default(),
             parameter_info,
+            midi_cc_assignments,
+            learned_controllers,
 
             sample_rate: 0.0,
             timer_id: None,
@@ -140,14 +175,22 @@ impl<P: ClapPlugin> PluginInstance<P> {
             to_plugin_event_sender,
             to_plugin_event_receiver,
             parameter_event_map,
+            track_info: Default::default(),
+            parameter_indications: Default::default(),
+            thread_pool: None,
+            thread_pool_task_slot,
+            log_registration: None,
 
             audio_thread_state: Default::default(),
 
             host_ext_gui: null(),
+            host_ext_log: null(),
             host_ext_params: null(),
             host_ext_state: null(),
             host_ext_tail: null(),
+            host_ext_thread_pool: null(),
             host_ext_timer_support: null(),
+            host_ext_track_info: null(),
         }
     }
 
@@ -162,7 +205,9 @@ impl<P: ClapPlugin> PluginInstance<P> {
     }
 
     pub(super) fn send_events_to_plugin(&mut self, in_events: *const clap_input_events) {
-        let events = EventIterator::new(&self.parameter_info, unsafe { &*in_events });
+        let events = EventIterator::new(&self.parameter_info, unsafe { &*in_events })
+            .with_midi_cc_assignments(&self.midi_cc_assignments)
+            .with_learned_controllers(self.learned_controllers.as_deref());
 
         for event in events {
             match self.to_plugin_event_sender.push(event) {
@@ -178,7 +223,38 @@ impl<P: ClapPlugin> PluginInstance<P> {
 
     pub(super) fn process_events_to_plugin(&mut self) {
         while let Ok(event) = self.to_plugin_event_receiver.pop() {
-            self.plugin.as_mut().unwrap().process_event(&event);
+            self.process_event(&event);
+        }
+    }
+
+    pub(super) fn process_event(&mut self, event: &Event) {
+        let plugin = self.plugin.as_mut().unwrap();
+
+        // Learned controllers are mapped on the audio thread, so control changes only end up here
+        // if they're unassigned or about to be learned
+        if plugin.midi_learn().is_some_and(|midi_learn| midi_learn.process_event(event)) {
+            return;
+        }
+
+        plugin.process_event(event);
+    }
+
+    pub(super) fn update_track_info(&mut self) {
+        if self.host_ext_track_info.is_null() {
+            return;
+        }
+
+        let mut info = MaybeUninit::zeroed();
+        if !unsafe { ((*self.host_ext_track_info).get.unwrap())(self.host, info.as_mut_ptr()) } {
+            return;
+        }
+
+        let track_info = convert_track_info(unsafe { info.assume_init_ref() });
+        self.plugin.as_mut().unwrap().track_info_changed(&track_info);
+        *self.track_info.lock().unwrap() = Some(track_info.clone());
+
+        if let Some(editor) = self.editor.as_ref() {
+            editor.track_info_changed(&track_info);
         }
     }
 
@@ -189,12 +265,35 @@ impl<P: ClapPlugin> PluginInstance<P> {
         Self::with_plugin_instance(plugin, |instance| {
             // Grab host extensions
             instance.host_ext_gui = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_GUI.as_ptr()) as _ };
+            instance.host_ext_log = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_LOG.as_ptr()) as _ };
             instance.host_ext_params = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_PARAMS.as_ptr()) as _ };
             instance.host_ext_state = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_STATE.as_ptr()) as _ };
             instance.host_ext_tail = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_TAIL.as_ptr()) as _ };
+            instance.host_ext_thread_pool = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_THREAD_POOL.as_ptr()) as _ };
             instance.host_ext_timer_support = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_TIMER_SUPPORT.as_ptr()) as _ };
+            instance.host_ext_track_info = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_TRACK_INFO.as_ptr()) as _ };
+
+            if instance.host_ext_track_info.is_null() {
+                instance.host_ext_track_info = unsafe { ((*instance.host).get_extension.unwrap())(instance.host, CLAP_EXT_TRACK_INFO_COMPAT.as_ptr()) as _ };
+            }
+
+            // Logging is best effort, another global logger might already be installed
+            if let Some(level) = P::LOG_LEVEL {
+                let registration = if instance.host_ext_log.is_null() {
+                    logging::register_file_sink(level, P::VENDOR, P::NAME)
+                } else {
+                    logging::register_sink(level, Arc::new(ClapLogSink::new(instance.host, instance.host_ext_log)))
+                };
+
+                instance.log_registration = registration.ok();
+            }
+
+            if !instance.host_ext_thread_pool.is_null() {
+                instance.thread_pool = Some(Arc::new(ClapThreadPool::new(instance.host, instance.host_ext_thread_pool, instance.thread_pool_task_slot.clone())));
+            }
 
             instance.plugin.as_mut().unwrap().init();
+            instance.update_track_info();
         });
 
         true
@@ -205,6 +304,7 @@ impl<P: ClapPlugin> PluginInstance<P> {
 
         Self::with_plugin_instance(plugin, |instance| {
             instance.plugin = None;
+            instance.log_registration = None;
         })
     }
 
@@ -223,6 +323,9 @@ impl<P: ClapPlugin> PluginInstance<P> {
                 min_block_size: min_frames_count as _,
                 max_block_size: max_frames_count as _,
                 process_mode: instance.process_mode,
+                thread_pool: instance.thread_pool.clone()
+                    .map(|thread_pool| ThreadPool::new(thread_pool))
+                    .unwrap_or_default(),
             };
 
             instance.sample_rate = sample_rate;
@@ -331,10 +434,13 @@ impl<P: ClapPlugin> PluginInstance<P> {
             };
 
             // Process events coming from the host and events coming from the editor
-            let host_events = EventIterator::new(&instance.parameter_info, unsafe { &*process.in_events });
+            let host_events = EventIterator::new(&instance.parameter_info, unsafe { &*process.in_events })
+                .with_midi_cc_assignments(&instance.midi_cc_assignments)
+                .with_learned_controllers(instance.learned_controllers.as_deref())
+                .with_output_events(process.out_events);
             let events = host_events.chain(editor_events);
 
-            let result = match processor.process(&mut output, aux.as_ref(), transport, events) {
+            let result = match rt_check::realtime(|| processor.process(&mut output, aux.as_ref(), transport, events)) {
                 ProcessState::Error => CLAP_PROCESS_ERROR,
                 ProcessState::Normal => CLAP_PROCESS_CONTINUE_IF_NOT_QUIET,
                 ProcessState::Tail(tail) => {
@@ -372,16 +478,24 @@ impl<P: ClapPlugin> PluginInstance<P> {
             Self::EXT_LATENCY.as_raw() as _
         } else if id == CLAP_EXT_NOTE_PORTS {
             Self::EXT_NOTE_PORTS.as_raw() as _
+        } else if id == CLAP_EXT_PARAM_INDICATION || id == CLAP_EXT_PARAM_INDICATION_COMPAT {
+            Self::EXT_PARAM_INDICATION.as_raw() as _
         } else if id == CLAP_EXT_PARAMS {
             Self::EXT_PARAMS.as_raw() as _
         } else if id == CLAP_EXT_RENDER {
             Self::EXT_RENDER.as_raw() as _
         } else if id == CLAP_EXT_STATE {
             Self::EXT_STATE.as_raw() as _
+        } else if id == CLAP_EXT_STATE_CONTEXT {
+            Self::EXT_STATE_CONTEXT.as_raw() as _
         } else if id == CLAP_EXT_TAIL {
             Self::EXT_TAIL.as_raw() as _
+        } else if id == CLAP_EXT_THREAD_POOL {
+            Self::EXT_THREAD_POOL.as_raw() as _
         } else if id == CLAP_EXT_TIMER_SUPPORT {
             Self::EXT_TIMER_SUPPORT.as_raw() as _
+        } else if id == CLAP_EXT_TRACK_INFO || id == CLAP_EXT_TRACK_INFO_COMPAT {
+            Self::EXT_TRACK_INFO.as_raw() as _
         } else {
             null()
         }
@@ -392,6 +506,10 @@ impl<P: ClapPlugin> PluginInstance<P> {
 
         Self::with_plugin_instance(plugin, |instance| {
             instance.process_events_to_plugin();
+
+            if instance.editor_closed.swap(false, Ordering::AcqRel) && instance.editor_floating && !instance.host_ext_gui.is_null() {
+                unsafe { ((*instance.host_ext_gui).closed.unwrap())(instance.host, false) };
+            }
         })
     }
 }
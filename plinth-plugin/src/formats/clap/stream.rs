@@ -23,7 +23,7 @@ impl Read for InputStream {
 
             let bytes_read = unsafe {
                 let istream = &*self.raw;
-                (istream.read.unwrap())(self.raw, buf.as_ptr().add(index) as _, remaining_bytes as _)
+                (istream.read.unwrap())(self.raw, buf.as_mut_ptr().add(index) as _, remaining_bytes as _)
             };
 
             if bytes_read < 0 {
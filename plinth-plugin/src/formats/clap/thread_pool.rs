@@ -0,0 +1,126 @@
+use std::ptr::null_mut;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use clap_sys::{ext::thread_pool::clap_host_thread_pool, host::clap_host};
+
+use crate::thread_pool::ThreadPoolBackend;
+
+type TaskRef<'a> = &'a (dyn Fn(usize) + Sync + 'a);
+
+/// Holds the task of a pending request_exec() call
+///
+/// The slot only points to a task while ClapThreadPool::execute() is blocking, and is empty at all other times.
+/// The host reaches it through clap_plugin::plugin_data, so exec() never touches the plugin instance,
+/// which the audio thread is mutably borrowing at the same time.
+#[derive(Default)]
+pub struct TaskSlot {
+    task: AtomicPtr<()>,
+}
+
+impl TaskSlot {
+    // Called by the host from its worker threads while request_exec() is blocking
+    pub fn exec(&self, task_index: u32) {
+        let task = self.task.load(Ordering::Acquire) as *const TaskRef<'_>;
+        if task.is_null() {
+            log::error!("Host called thread_pool::exec() without a pending task");
+            return;
+        }
+
+        // SAFETY: The slot is cleared before the task it points to goes out of scope
+        unsafe { (*task)(task_index as usize) };
+    }
+}
+
+pub struct ClapThreadPool {
+    host: *const clap_host,
+    host_ext_thread_pool: *const clap_host_thread_pool,
+    task_slot: Arc<TaskSlot>,
+}
+
+impl ClapThreadPool {
+    pub fn new(host: *const clap_host, host_ext_thread_pool: *const clap_host_thread_pool, task_slot: Arc<TaskSlot>) -> Self {
+        assert!(!host.is_null());
+        assert!(!host_ext_thread_pool.is_null());
+
+        Self {
+            host,
+            host_ext_thread_pool,
+            task_slot,
+        }
+    }
+}
+
+impl ThreadPoolBackend for ClapThreadPool {
+    fn execute(&self, task_count: usize, task: &(dyn Fn(usize) + Sync)) -> bool {
+        let task: TaskRef<'_> = task;
+        self.task_slot.task.store(&task as *const TaskRef<'_> as *mut (), Ordering::Release);
+
+        let result = unsafe { ((*self.host_ext_thread_pool).request_exec.unwrap())(self.host, task_count as _) };
+
+        // Clear the slot before the borrowed task goes away, so late exec() calls return early
+        self.task_slot.task.store(null_mut(), Ordering::Release);
+
+        result
+    }
+}
+
+/// SAFETY: clap_host_thread_pool functions are thread-safe
+unsafe impl Send for ClapThreadPool {}
+unsafe impl Sync for ClapThreadPool {}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::null;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use clap_sys::{ext::thread_pool::clap_host_thread_pool, host::clap_host, version::CLAP_VERSION};
+
+    use crate::thread_pool::ThreadPoolBackend;
+
+    use super::{ClapThreadPool, TaskSlot};
+
+    unsafe extern "C" fn request_exec(host: *const clap_host, num_tasks: u32) -> bool {
+        let task_slot = unsafe { &*((*host).host_data as *const TaskSlot) };
+
+        for task_index in 0..num_tasks {
+            task_slot.exec(task_index);
+        }
+
+        true
+    }
+
+    #[test]
+    fn task_slot_is_cleared_after_execute() {
+        let task_slot = Arc::new(TaskSlot::default());
+
+        let host = clap_host {
+            clap_version: CLAP_VERSION,
+            host_data: Arc::as_ptr(&task_slot) as _,
+            name: null(),
+            vendor: null(),
+            url: null(),
+            version: null(),
+            get_extension: None,
+            request_restart: None,
+            request_process: None,
+            request_callback: None,
+        };
+
+        let host_ext_thread_pool = clap_host_thread_pool {
+            request_exec: Some(request_exec),
+        };
+
+        let thread_pool = ClapThreadPool::new(&host, &host_ext_thread_pool, task_slot.clone());
+
+        let call_count = AtomicUsize::new(0);
+        let task = |_: usize| { call_count.fetch_add(1, Ordering::Relaxed); };
+        assert!(thread_pool.execute(4, &task));
+        assert_eq!(call_count.load(Ordering::Relaxed), 4);
+
+        // A late call from the host must not reach the finished task
+        task_slot.exec(0);
+        assert_eq!(call_count.load(Ordering::Relaxed), 4);
+    }
+}
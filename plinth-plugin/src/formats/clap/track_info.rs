@@ -0,0 +1,45 @@
+use std::ffi::CStr;
+
+use clap_sys::ext::track_info::{clap_track_info, CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL, CLAP_TRACK_INFO_HAS_TRACK_COLOR, CLAP_TRACK_INFO_HAS_TRACK_NAME, CLAP_TRACK_INFO_IS_FOR_BUS, CLAP_TRACK_INFO_IS_FOR_MASTER, CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK};
+
+use crate::{Color, TrackInfo, TrackType};
+
+pub fn convert_track_info(info: &clap_track_info) -> TrackInfo {
+    let name = if info.flags & CLAP_TRACK_INFO_HAS_TRACK_NAME != 0 {
+        unsafe { CStr::from_ptr(info.name.as_ptr()) }
+            .to_str()
+            .ok()
+            .map(|name| name.to_string())
+    } else {
+        None
+    };
+
+    let color = if info.flags & CLAP_TRACK_INFO_HAS_TRACK_COLOR != 0 {
+        Some(Color::new(info.color.red, info.color.green, info.color.blue, info.color.alpha))
+    } else {
+        None
+    };
+
+    let channel_count = if info.flags & CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL != 0 && info.audio_channel_count >= 0 {
+        Some(info.audio_channel_count as usize)
+    } else {
+        None
+    };
+
+    let track_type = if info.flags & CLAP_TRACK_INFO_IS_FOR_MASTER != 0 {
+        TrackType::Master
+    } else if info.flags & CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK != 0 {
+        TrackType::Return
+    } else if info.flags & CLAP_TRACK_INFO_IS_FOR_BUS != 0 {
+        TrackType::Bus
+    } else {
+        TrackType::Regular
+    };
+
+    TrackInfo {
+        name,
+        color,
+        channel_count,
+        track_type,
+    }
+}
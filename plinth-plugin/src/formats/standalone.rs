@@ -1,11 +1,19 @@
+mod args;
 mod audio;
 mod config;
+mod engine;
 mod host;
+mod input;
 mod midi;
 mod parameters;
 mod plugin;
 mod runner;
+mod settings;
+#[cfg(feature = "standalone-settings")]
+mod settings_window;
+mod state;
 
-pub use config::{AudioDeviceDriver, AudioOutputConfig, MidiInputConfig};
+pub use args::Args;
+pub use config::{AudioDeviceDriver, AudioInputConfig, AudioOutputConfig, MidiInputConfig};
 pub use plugin::StandalonePlugin;
-pub use runner::{run_standalone, run_standalone_with_config};
+pub use runner::{run_standalone, run_standalone_from_args, run_standalone_with_config, run_standalone_with_input_config, StandaloneError};
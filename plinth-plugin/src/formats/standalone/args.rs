@@ -0,0 +1,100 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use super::settings::SETTINGS_KEYS;
+
+/// Command-line arguments of the standalone app
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    /// State file to open, one that doesn't exist yet is created when the state is saved
+    pub state_file: Option<PathBuf>,
+    /// Settings that override the saved ones for this run, as `(key, value)` pairs
+    pub settings: Vec<(String, String)>,
+    /// Open the settings window on launch
+    pub show_settings: bool,
+    /// Print the usage instead of running
+    pub show_help: bool,
+}
+
+impl Args {
+    /// Parses arguments without the program name, settings are given as `--key value` or `--key=value`
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self, String> {
+        let mut result = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.to_str().and_then(|arg| arg.strip_prefix("--")) else {
+                if result.state_file.is_some() {
+                    return Err(format!("Unexpected argument '{}'", arg.display()));
+                }
+
+                result.state_file = Some(arg.into());
+                continue;
+            };
+
+            match option {
+                "help" => result.show_help = true,
+                "settings" => result.show_settings = true,
+                _ => {
+                    let (key, value) = match option.split_once('=') {
+                        Some((key, value)) => (key.to_string(), value.to_string()),
+                        None => {
+                            let value = args.next()
+                                .ok_or_else(|| format!("Missing value for '--{option}'"))?
+                                .into_string()
+                                .map_err(|value| format!("Invalid value '{}' for '--{option}'", value.display()))?;
+
+                            (option.to_string(), value)
+                        }
+                    };
+
+                    if !SETTINGS_KEYS.iter().any(|(settings_key, _)| *settings_key == key) {
+                        return Err(format!("Unknown option '--{key}'"));
+                    }
+
+                    result.settings.push((key, value));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        let mut usage = format!("Usage: {program_name} [OPTIONS] [STATE FILE]\n\nOptions:\n");
+        usage.push_str("  --help                  Print this help\n");
+        usage.push_str("  --settings              Open the audio and MIDI settings\n");
+
+        for (key, description) in SETTINGS_KEYS {
+            usage.push_str(&format!("  --{:<22}{description}\n", format!("{key} <VALUE>")));
+        }
+
+        usage.push_str("\nSettings given here are used instead of the saved ones, without changing them.\n");
+        usage.push_str("A state file that doesn't exist yet is created when the state is saved.\n");
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Args;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(Into::into))
+    }
+
+    #[test]
+    fn parses_settings_and_state_file() {
+        let args = parse(&["--sample-rate", "48000", "live.state", "--midi-port=Keys", "--settings"]).unwrap();
+
+        assert_eq!(args.state_file, Some("live.state".into()));
+        assert_eq!(args.settings, [("sample-rate".to_string(), "48000".to_string()), ("midi-port".to_string(), "Keys".to_string())]);
+        assert!(args.show_settings);
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert!(parse(&["--volume", "11"]).is_err());
+        assert!(parse(&["--buffer-size"]).is_err());
+        assert!(parse(&["one.state", "two.state"]).is_err());
+    }
+}
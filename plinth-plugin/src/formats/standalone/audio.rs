@@ -3,9 +3,10 @@ use std::sync::{Arc, mpsc::Receiver};
 use cpal::{FromSample, Sample};
 use plinth_core::{ buffers::buffer::Buffer, signals::{ signal::{Signal, SignalMut}, signal_base::SignalBase } };
 
+use super::input::InputReceiver;
 use super::parameters::StandaloneParameterEventMap;
 use super::plugin::StandalonePlugin;
-use crate::{Event, Processor};
+use crate::{rt_check, Event, Processor};
 
 /// Push events to a event list vec, printing a warning when preallocated memory exceeded.
 trait EventListPush {
@@ -35,6 +36,8 @@ pub struct AudioState<P: StandalonePlugin> {
     pub channels: usize,
     pub midi_receiver: Receiver<Event>,
     pub parameter_event_map: Arc<StandaloneParameterEventMap>,
+    input: Option<InputReceiver>,
+    aux_buffer: Option<Buffer>,
     pending_events: Vec<Event>,
 }
 
@@ -44,13 +47,20 @@ impl<P: StandalonePlugin> AudioState<P> {
         channels: usize,
         midi_receiver: Receiver<Event>,
         parameter_event_map: Arc<StandaloneParameterEventMap>,
+        input: Option<InputReceiver>,
     ) -> Self {
+        // The aux bus is fed from the input device too
+        let aux_buffer = (P::HAS_AUX_INPUT && input.is_some())
+            .then(|| Buffer::new(channels, P::MAX_BLOCK_SIZE));
+
         Self {
             processor,
             buffer: Buffer::new(channels, P::MAX_BLOCK_SIZE),
             channels,
             midi_receiver,
             parameter_event_map,
+            input,
+            aux_buffer,
             pending_events: Vec::with_capacity(P::EVENT_QUEUE_LEN),
         }
     }
@@ -79,27 +89,38 @@ impl<P: StandalonePlugin> AudioState<P> {
             "Buffer must be preallocated to avoid allocation on the audio thread"
         );
 
+        if let Some(input) = self.input.as_mut() {
+            input.start_block(frame_count);
+        }
+
         let mut frame_offset = 0;
         while frame_offset < frame_count {
             let chunk_size = (frame_count - frame_offset).min(P::MAX_BLOCK_SIZE);
 
-            // Truncate or extend buffer to fit the chunk
+            // Truncate or extend buffers to fit the chunk
             if self.buffer.len() != chunk_size {
                 self.buffer.resize(chunk_size);
             }
+            if let Some(aux_buffer) = self.aux_buffer.as_mut().filter(|aux_buffer| aux_buffer.len() != chunk_size) {
+                aux_buffer.resize(chunk_size);
+            }
 
-            // Deinterleave chunk from CPAL buffer
-            for frame in 0..chunk_size {
-                for ch in 0..self.channels {
-                    self.buffer.channel_mut(ch)[frame] =
-                        f32::from_sample(data[(frame_offset + frame) * self.channels + ch]);
+            if let Some(input) = self.input.as_mut() {
+                input.read(&mut self.buffer, self.aux_buffer.as_mut());
+            } else {
+                // Deinterleave chunk from CPAL buffer
+                for frame in 0..chunk_size {
+                    for ch in 0..self.channels {
+                        self.buffer.channel_mut(ch)[frame] =
+                            f32::from_sample(data[(frame_offset + frame) * self.channels + ch]);
+                    }
                 }
             }
 
             // Process and drain all events on first run, assuming they have no time tags
-            let aux: Option<&Buffer> = None;
-            self.processor
-                .process(&mut self.buffer, aux, None, self.pending_events.drain(..));
+            let aux = self.aux_buffer.as_ref();
+            rt_check::realtime(|| self.processor
+                .process(&mut self.buffer, aux, None, self.pending_events.drain(..)));
 
             // Reinterleave chunk back into CPAL buffer
             for frame in 0..chunk_size {
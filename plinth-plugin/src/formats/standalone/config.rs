@@ -1,8 +1,10 @@
 use cpal::traits::{DeviceTrait, HostTrait};
 use midir::MidiInput;
 
+const PREFERRED_SAMPLE_FORMAT: cpal::SampleFormat = cpal::SampleFormat::F32;
+
 /// Available audio backends for [`StandalonePlugin`].
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum AudioDeviceDriver {
     #[default]
     Default,
@@ -19,6 +21,40 @@ pub enum AudioDeviceDriver {
 }
 
 impl AudioDeviceDriver {
+    /// Name used in the settings file and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            AudioDeviceDriver::Default => "default",
+            #[cfg(target_os = "windows")]
+            AudioDeviceDriver::Asio => "asio",
+            #[cfg(target_os = "windows")]
+            AudioDeviceDriver::Wasapi => "wasapi",
+            #[cfg(target_os = "linux")]
+            AudioDeviceDriver::Alsa => "alsa",
+            #[cfg(target_os = "macos")]
+            AudioDeviceDriver::CoreAudio => "coreaudio",
+            #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+            AudioDeviceDriver::Jack => "jack",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(AudioDeviceDriver::Default),
+            #[cfg(target_os = "windows")]
+            "asio" => Some(AudioDeviceDriver::Asio),
+            #[cfg(target_os = "windows")]
+            "wasapi" => Some(AudioDeviceDriver::Wasapi),
+            #[cfg(target_os = "linux")]
+            "alsa" => Some(AudioDeviceDriver::Alsa),
+            #[cfg(target_os = "macos")]
+            "coreaudio" => Some(AudioDeviceDriver::CoreAudio),
+            #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+            "jack" => Some(AudioDeviceDriver::Jack),
+            _ => None,
+        }
+    }
+
     fn open(self) -> Result<cpal::Host, Box<dyn std::error::Error>> {
         match self {
             AudioDeviceDriver::Default => Ok(cpal::default_host()),
@@ -37,7 +73,7 @@ impl AudioDeviceDriver {
 }
 
 /// Audio output configuration for [`StandalonePlugin`].
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct AudioOutputConfig {
     /// Audio host/driver to use. Defaults to `cpal::default_host`.
     pub driver: AudioDeviceDriver,
@@ -52,7 +88,6 @@ pub struct AudioOutputConfig {
 impl AudioOutputConfig {
     const PREFERRED_SAMPLE_RATE: cpal::SampleRate = 44100;
     const PREFERRED_CHANNELS: cpal::ChannelCount = 2;
-    const PREFERRED_SAMPLE_FORMAT: cpal::SampleFormat = cpal::SampleFormat::F32;
 
     /// Returns all audio drivers available on this platform.
     pub fn available_drivers() -> Vec<AudioDeviceDriver> {
@@ -86,21 +121,7 @@ impl AudioOutputConfig {
         driver: AudioDeviceDriver,
     ) -> Result<Vec<(cpal::DeviceId, String)>, Box<dyn std::error::Error>> {
         let host = driver.open()?;
-        let mut devices = Vec::new();
-        for device in host.output_devices()? {
-            match (device.id(), device.description()) {
-                (Ok(id), Ok(description)) => {
-                    devices.push((id, description.to_string()));
-                }
-                (Ok(id), Err(_)) => {
-                    devices.push((id.clone(), id.to_string()));
-                }
-                (Err(err), _) => {
-                    log::warn!("Failed to query audio device id {err}")
-                }
-            }
-        }
-        Ok(devices)
+        Ok(describe_devices(host.output_devices()?))
     }
 
     pub fn open_host(&self) -> Result<cpal::Host, Box<dyn std::error::Error>> {
@@ -128,26 +149,10 @@ impl AudioOutputConfig {
         device: &cpal::Device,
     ) -> Result<cpal::SupportedStreamConfig, Box<dyn std::error::Error>> {
         let target_rate = self.sample_rate.unwrap_or(Self::PREFERRED_SAMPLE_RATE);
-        let mut configs = device.supported_output_configs()?.collect::<Vec<_>>();
-        configs.sort_by(|a, b| b.cmp_default_heuristics(a));
-        let supports_rate = |s: &cpal::SupportedStreamConfigRange| {
-            (s.min_sample_rate()..=s.max_sample_rate()).contains(&target_rate)
-        };
-        let best_match = configs
-            .iter()
-            .find(|s| {
-                supports_rate(s)
-                    && s.channels() == Self::PREFERRED_CHANNELS
-                    && s.sample_format() == Self::PREFERRED_SAMPLE_FORMAT
-            })
-            .or_else(|| {
-                configs
-                    .iter()
-                    .find(|s| supports_rate(s) && s.channels() == Self::PREFERRED_CHANNELS)
-            })
-            .or_else(|| configs.iter().find(|s| supports_rate(s)));
-        match best_match {
-            Some(s) => Ok(s.with_sample_rate(target_rate)),
+        let configs = device.supported_output_configs()?.collect();
+
+        match select_config(configs, target_rate, Self::PREFERRED_CHANNELS) {
+            Some(config) => Ok(config),
             None => {
                 log::warn!("No matching audio device config found, using device default");
                 Ok(device.default_output_config()?)
@@ -156,8 +161,129 @@ impl AudioOutputConfig {
     }
 }
 
+/// Audio input configuration for [`StandalonePlugin`].
+///
+/// [`run_standalone`](super::run_standalone) opens the default input for plugins with `HAS_AUDIO_INPUT` set.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AudioInputConfig {
+    /// Audio host/driver to use. Defaults to `cpal::default_host`.
+    pub driver: AudioDeviceDriver,
+    /// Id of the input device to open. `None` selects the driver's default device.
+    pub device_id: Option<cpal::DeviceId>,
+    /// Device channels feeding the main input, in order. `None` uses the first channels of the device.
+    /// A single channel feeds all channels of the main input.
+    pub channels: Option<Vec<usize>>,
+    /// Device channels feeding the aux input of plugins with `HAS_AUX_INPUT`. `None` uses the channels
+    /// following the main input's, if the device has any.
+    pub aux_channels: Option<Vec<usize>>,
+}
+
+impl AudioInputConfig {
+    /// Returns `(id, name)`s of all input devices available for the given driver.
+    pub fn available_devices(
+        driver: AudioDeviceDriver,
+    ) -> Result<Vec<(cpal::DeviceId, String)>, Box<dyn std::error::Error>> {
+        let host = driver.open()?;
+        Ok(describe_devices(host.input_devices()?))
+    }
+
+    pub fn open_host(&self) -> Result<cpal::Host, Box<dyn std::error::Error>> {
+        self.driver.open()
+    }
+
+    pub fn open_device(
+        &self,
+        host: &mut cpal::Host,
+    ) -> Result<cpal::Device, Box<dyn std::error::Error>> {
+        if let Some(device_id) = &self.device_id {
+            log::info!("Opening CPAL input device '{}'...", device_id);
+            host.input_devices()?
+                .find(|d| d.id().ok().as_ref() == Some(device_id))
+                .ok_or_else(|| "Specified audio input device not found".into())
+        } else {
+            log::info!("Opening CPAL default input device...");
+            host.default_input_device()
+                .ok_or_else(|| "No audio input device available".into())
+        }
+    }
+
+    /// Returns the largest number of channels the configured device can be opened with
+    pub fn channel_count(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut host = self.open_host()?;
+        let device = self.open_device(&mut host)?;
+        let channels = device.supported_input_configs()?
+            .map(|config| config.channels())
+            .max()
+            .unwrap_or(0);
+
+        Ok(channels as usize)
+    }
+
+    /// Selects a config with the output's sample rate if the device supports it, so no resampling is needed.
+    pub fn select_stream_config(
+        &self,
+        device: &cpal::Device,
+        sample_rate: cpal::SampleRate,
+    ) -> Result<cpal::SupportedStreamConfig, Box<dyn std::error::Error>> {
+        let configs: Vec<_> = device.supported_input_configs()?.collect();
+        let max_channels = configs.iter().map(|config| config.channels()).max().unwrap_or(0);
+
+        match select_config(configs, sample_rate, max_channels) {
+            Some(config) => Ok(config),
+            None => {
+                log::warn!("Audio input device doesn't support {sample_rate} Hz, using device default");
+                Ok(device.default_input_config()?)
+            }
+        }
+    }
+}
+
+fn describe_devices(devices: impl Iterator<Item = cpal::Device>) -> Vec<(cpal::DeviceId, String)> {
+    let mut result = Vec::new();
+    for device in devices {
+        match (device.id(), device.description()) {
+            (Ok(id), Ok(description)) => {
+                result.push((id, description.to_string()));
+            }
+            (Ok(id), Err(_)) => {
+                result.push((id.clone(), id.to_string()));
+            }
+            (Err(err), _) => {
+                log::warn!("Failed to query audio device id {err}")
+            }
+        }
+    }
+    result
+}
+
+/// Picks the best config supporting `sample_rate`, preferring `channels` and f32 samples
+fn select_config(
+    mut configs: Vec<cpal::SupportedStreamConfigRange>,
+    sample_rate: cpal::SampleRate,
+    channels: cpal::ChannelCount,
+) -> Option<cpal::SupportedStreamConfig> {
+    configs.sort_by(|a, b| b.cmp_default_heuristics(a));
+    let supports_rate = |s: &cpal::SupportedStreamConfigRange| {
+        (s.min_sample_rate()..=s.max_sample_rate()).contains(&sample_rate)
+    };
+    configs
+        .iter()
+        .find(|s| {
+            supports_rate(s)
+                && s.channels() == channels
+                && s.sample_format() == PREFERRED_SAMPLE_FORMAT
+        })
+        .or_else(|| {
+            configs
+                .iter()
+                .find(|s| supports_rate(s) && s.channels() == channels)
+        })
+        .or_else(|| configs.iter().find(|s| supports_rate(s)))
+        .map(|s| s.with_sample_rate(sample_rate))
+}
+
 /// MIDI input configuration for [`StandalonePlugin`].
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct MidiInputConfig {
     /// Names of MIDI input ports to connect to. `None` connects to all available ports.
     pub port_names: Option<Vec<String>>,
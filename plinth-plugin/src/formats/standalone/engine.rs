@@ -0,0 +1,153 @@
+use std::sync::{Arc, mpsc::{self, Sender}};
+
+use cpal::{BufferSize, FromSample, I24, SizedSample, Stream, StreamConfig, traits::{DeviceTrait, StreamTrait}};
+use midir::MidiInputConnection;
+
+use super::{audio::AudioState, input, midi, parameters::StandaloneParameterEventMap, plugin::StandalonePlugin, settings::StandaloneSettings};
+use crate::{Event, MidiLearn, ProcessMode, Processor, ProcessorConfig};
+
+/// Audio and MIDI streams running a processor, which are started again with a new processor when the settings change
+pub struct Engine {
+    audio_stream: Stream,
+    input_stream: Option<Stream>,
+    midi_connections: Vec<MidiInputConnection<()>>,
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.audio_stream.pause();
+        if let Some(input_stream) = self.input_stream.as_ref() {
+            let _ = input_stream.pause();
+        }
+        self.midi_connections.clear();
+    }
+}
+
+impl Engine {
+    /// Opens the devices in the settings and starts processing, the plugin runs without audio input if that fails
+    pub fn start<P: StandalonePlugin + 'static>(
+        plugin: &mut P,
+        settings: &StandaloneSettings,
+        parameter_event_map: Arc<StandaloneParameterEventMap>,
+        to_plugin_sender: Sender<Event>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let audio_config = &settings.audio;
+
+        // Open audio device
+        let mut audio_host = audio_config.open_host()?;
+        let audio_device = audio_config.open_device(&mut audio_host)?;
+        let audio_stream_config = audio_config.select_stream_config(&audio_device)?;
+
+        // Open MIDI connections if plugin accepts note inputs, has MIDI CC assignments or MIDI learn
+        let (midi_sender, midi_receiver) = mpsc::channel::<Event>();
+        let midi_cc_assignments = plugin.midi_cc_assignments();
+        let learned_controllers = plugin.midi_learn().map(MidiLearn::learned_controllers);
+
+        let midi_connections = if P::HAS_NOTE_INPUT || !midi_cc_assignments.is_empty() || learned_controllers.is_some() {
+            midi::connect_inputs(&settings.midi, &midi_cc_assignments, learned_controllers, midi_sender, to_plugin_sender)
+        } else {
+            vec![]
+        };
+
+        // Create processor
+        // NB: CPAL unfortunately has no getter for the real applied block size, so we need to ensure that the processor never gets called with more frames
+        let processor_config = ProcessorConfig {
+            sample_rate: audio_stream_config.sample_rate() as f64,
+            min_block_size: 1,
+            max_block_size: P::MAX_BLOCK_SIZE,
+            process_mode: ProcessMode::Realtime,
+            thread_pool: Default::default(),
+        };
+        let mut processor = plugin.create_processor(processor_config);
+        processor.reset();
+
+        // Open audio input, the plugin still runs without it if that fails
+        let channels = audio_stream_config.channels() as usize;
+        let aux_channels = if P::HAS_AUX_INPUT { channels } else { 0 };
+
+        let (input_stream, input_receiver) = match settings.input.as_ref()
+            .map(|config| input::open_input(config, audio_stream_config.sample_rate(), P::MAX_BLOCK_SIZE, channels, aux_channels))
+        {
+            Some(Ok((stream, receiver))) => (Some(stream), Some(receiver)),
+            Some(Err(err)) => {
+                log::error!("Failed to open audio input: {err}");
+                (None, None)
+            }
+            None => (None, None),
+        };
+
+        // Create audio state
+        let audio_state = AudioState::<P>::new(
+            processor,
+            channels,
+            midi_receiver,
+            parameter_event_map,
+            input_receiver,
+        );
+
+        // Create and start the CPAL stream
+        fn run_audio_stream<P, T>(
+            device: &cpal::Device,
+            config: cpal::StreamConfig,
+            mut audio_state: AudioState<P>,
+        ) -> Result<Stream, Box<dyn std::error::Error>>
+        where
+            P: StandalonePlugin + 'static,
+            T: SizedSample + FromSample<f32>,
+            f32: FromSample<T>,
+        {
+            let channels = config.channels as usize;
+
+            let stream = device.build_output_stream(
+                &config,
+                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                    audio_state.process(data, channels);
+                },
+                |err| {
+                    log::error!("An audio stream error occurred: {err}");
+                },
+                None,
+            )?;
+            stream.play()?;
+
+            Ok(stream)
+        }
+
+        let stream_format = audio_stream_config.sample_format();
+        let stream_config = StreamConfig {
+            channels: audio_stream_config.channels(),
+            sample_rate: audio_stream_config.sample_rate(),
+            buffer_size: audio_config
+                .buffer_size
+                .map(BufferSize::Fixed)
+                .unwrap_or(BufferSize::Default),
+        };
+
+        let audio_stream = match stream_format {
+            cpal::SampleFormat::I8 => run_audio_stream::<P, i8>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::I16 => run_audio_stream::<P, i16>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::I24 => run_audio_stream::<P, I24>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::I32 => run_audio_stream::<P, i32>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::I64 => run_audio_stream::<P, i64>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::U8 => run_audio_stream::<P, u8>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::U16 => run_audio_stream::<P, u16>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::U32 => run_audio_stream::<P, u32>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::U64 => run_audio_stream::<P, u64>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::F32 => run_audio_stream::<P, f32>(&audio_device, stream_config, audio_state),
+            cpal::SampleFormat::F64 => run_audio_stream::<P, f64>(&audio_device, stream_config, audio_state),
+            sample_format => return Err(format!("Unsupported sample format '{sample_format}'").into()),
+        }?;
+
+        log::info!(
+            "Started audio at {} Hz with {} channels",
+            audio_stream_config.sample_rate(),
+            audio_stream_config.channels(),
+        );
+
+        Ok(Self {
+            audio_stream,
+            input_stream,
+            midi_connections,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::{Arc, mpsc::Sender};
+use std::{cell::Cell, rc::Rc, sync::{Arc, mpsc::Sender}};
 
 use crate::{Event, Host, ParameterId, ParameterValue};
 
@@ -7,16 +7,19 @@ use super::parameters::StandaloneParameterEventMap;
 pub struct StandaloneHost {
     parameter_event_map: Arc<StandaloneParameterEventMap>,
     to_plugin_sender: Sender<Event>,
+    settings_requested: Rc<Cell<bool>>,
 }
 
 impl StandaloneHost {
     pub fn new(
         parameter_event_map: Arc<StandaloneParameterEventMap>,
         to_plugin_sender: Sender<Event>,
+        settings_requested: Rc<Cell<bool>>,
     ) -> Self {
         Self {
             parameter_event_map,
             to_plugin_sender,
+            settings_requested,
         }
     }
 }
@@ -47,4 +50,14 @@ impl Host for StandaloneHost {
     fn reload_parameters(&self) {}
 
     fn mark_state_dirty(&self) {}
+
+    fn open_settings(&self) -> bool {
+        if !cfg!(feature = "standalone-settings") {
+            return false;
+        }
+
+        // The runner opens the window on the next frame, it can't be reached from here
+        self.settings_requested.set(true);
+        true
+    }
 }
@@ -0,0 +1,386 @@
+use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+
+use cpal::{FromSample, I24, Sample, SizedSample, Stream, StreamConfig, traits::{DeviceTrait, StreamTrait}};
+use plinth_core::signals::signal::SignalMut;
+use rtrb::{Consumer, Producer, RingBuffer};
+
+use super::config::AudioInputConfig;
+
+/// Size of the ring between the input and output callbacks, in blocks of the processor's maximum size
+const RING_BLOCKS: usize = 8;
+
+/// Opens the input stream and returns it with the receiving end for the output callback
+///
+/// `main_channels` and `aux_channels` are the channel counts of the processor's buffers, pass 0 for no aux input.
+pub fn open_input(
+    config: &AudioInputConfig,
+    sample_rate: cpal::SampleRate,
+    max_block_size: usize,
+    main_channels: usize,
+    aux_channels: usize,
+) -> Result<(Stream, InputReceiver), Box<dyn std::error::Error>> {
+    let mut host = config.open_host()?;
+    let device = config.open_device(&mut host)?;
+    let stream_config = config.select_stream_config(&device, sample_rate)?;
+    let device_channels = stream_config.channels() as usize;
+
+    let main = select_channels(config.channels.as_deref(), 0, main_channels, device_channels);
+    let aux = if aux_channels > 0 {
+        select_channels(config.aux_channels.as_deref(), main.len(), aux_channels, device_channels)
+    } else {
+        Vec::new()
+    };
+
+    if main.is_empty() {
+        return Err("Audio input device has no usable channels".into());
+    }
+
+    if stream_config.sample_rate() != sample_rate {
+        log::info!("Resampling audio input from {} Hz to {sample_rate} Hz", stream_config.sample_rate());
+    }
+
+    let ring_channels: Vec<_> = main.iter().chain(aux.iter()).copied().collect();
+    let (producer, consumer) = RingBuffer::new(ring_channels.len() * max_block_size * RING_BLOCKS);
+    let input_block_size = Arc::new(AtomicUsize::new(0));
+
+    let capture = InputCapture {
+        producer,
+        resampler: Resampler::new(stream_config.sample_rate(), sample_rate, ring_channels.len()),
+        frame: vec![0.0; ring_channels.len()],
+        channels: ring_channels,
+        device_channels,
+        block_size: input_block_size.clone(),
+    };
+
+    let receiver = InputReceiver {
+        consumer,
+        main_channels: main.len(),
+        aux_channels: aux.len(),
+        input_block_size,
+        output_block_size: 0,
+        primed: false,
+    };
+
+    fn run_input_stream<T>(
+        device: &cpal::Device,
+        config: StreamConfig,
+        mut capture: InputCapture,
+    ) -> Result<Stream, Box<dyn std::error::Error>>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let stream = device.build_input_stream(
+            &config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                capture.capture(data);
+            },
+            |err| {
+                log::error!("An audio input stream error occurred: {err}");
+            },
+            None,
+        )?;
+        stream.play()?;
+
+        Ok(stream)
+    }
+
+    let stream_format = stream_config.sample_format();
+    let stream_config = stream_config.config();
+
+    let stream = match stream_format {
+        cpal::SampleFormat::I8 => run_input_stream::<i8>(&device, stream_config, capture),
+        cpal::SampleFormat::I16 => run_input_stream::<i16>(&device, stream_config, capture),
+        cpal::SampleFormat::I24 => run_input_stream::<I24>(&device, stream_config, capture),
+        cpal::SampleFormat::I32 => run_input_stream::<i32>(&device, stream_config, capture),
+        cpal::SampleFormat::I64 => run_input_stream::<i64>(&device, stream_config, capture),
+        cpal::SampleFormat::U8 => run_input_stream::<u8>(&device, stream_config, capture),
+        cpal::SampleFormat::U16 => run_input_stream::<u16>(&device, stream_config, capture),
+        cpal::SampleFormat::U32 => run_input_stream::<u32>(&device, stream_config, capture),
+        cpal::SampleFormat::U64 => run_input_stream::<u64>(&device, stream_config, capture),
+        cpal::SampleFormat::F32 => run_input_stream::<f32>(&device, stream_config, capture),
+        cpal::SampleFormat::F64 => run_input_stream::<f64>(&device, stream_config, capture),
+        sample_format => return Err(format!("Unsupported input sample format '{sample_format}'").into()),
+    }?;
+
+    Ok((stream, receiver))
+}
+
+/// Uses the configured channels that exist on the device, or by default the `count` channels from `first_default` on
+fn select_channels(configured: Option<&[usize]>, first_default: usize, count: usize, device_channels: usize) -> Vec<usize> {
+    match configured {
+        Some(channels) => {
+            if let Some(channel) = channels.iter().find(|&&channel| channel >= device_channels) {
+                log::warn!("Audio input device has no channel {channel}, it has {device_channels}");
+            }
+
+            channels.iter()
+                .copied()
+                .filter(|&channel| channel < device_channels)
+                .collect()
+        },
+
+        None => (first_default..usize::min(first_default + count, device_channels)).collect(),
+    }
+}
+
+/// Writes input frames from the input stream's callback to the ring
+struct InputCapture {
+    producer: Producer<f32>,
+    resampler: Resampler,
+    frame: Vec<f32>,
+    // Device channel for each channel in the ring
+    channels: Vec<usize>,
+    device_channels: usize,
+    // Largest callback seen, in output frames
+    block_size: Arc<AtomicUsize>,
+}
+
+impl InputCapture {
+    fn capture<T>(&mut self, data: &[T])
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let producer = &mut self.producer;
+        let mut frames_written = 0;
+
+        for device_frame in data.chunks_exact(self.device_channels) {
+            for (sample, &channel) in self.frame.iter_mut().zip(self.channels.iter()) {
+                *sample = f32::from_sample(device_frame[channel]);
+            }
+
+            self.resampler.process(&self.frame, |frame| {
+                // Drop frames that don't fit instead of waiting, the output side catches up by skipping
+                if producer.slots() >= frame.len() {
+                    for &sample in frame {
+                        let _ = producer.push(sample);
+                    }
+                }
+
+                frames_written += 1;
+            });
+        }
+
+        self.block_size.fetch_max(frames_written, Ordering::Relaxed);
+    }
+}
+
+/// Reads input frames in the output stream's callback
+///
+/// Reading starts once a block from both devices is buffered, so a callback on either side doesn't
+/// run out of input. If the input falls behind, the output gets silence until the ring fills up again,
+/// and if it gets ahead, frames are skipped to keep the latency from growing.
+pub struct InputReceiver {
+    consumer: Consumer<f32>,
+    main_channels: usize,
+    aux_channels: usize,
+    input_block_size: Arc<AtomicUsize>,
+    // Largest output callback seen
+    output_block_size: usize,
+    primed: bool,
+}
+
+impl InputReceiver {
+    /// Called at the start of each output callback, before reading
+    pub fn start_block(&mut self, frames: usize) {
+        self.output_block_size = usize::max(self.output_block_size, frames);
+    }
+
+    /// Fills the main and aux buffers with input, a single input channel feeds all main channels
+    pub fn read(&mut self, main: &mut impl SignalMut, mut aux: Option<&mut impl SignalMut>) {
+        main.fill(0.0);
+        if let Some(aux) = aux.as_mut() {
+            aux.fill(0.0);
+        }
+
+        let frames = main.len();
+        let ring_channels = self.main_channels + self.aux_channels;
+        let latency = self.input_block_size.load(Ordering::Relaxed) + self.output_block_size;
+        let available = self.consumer.slots() / ring_channels;
+
+        if !self.primed {
+            if available < latency + frames {
+                return;
+            }
+
+            self.primed = true;
+        }
+
+        if available < frames {
+            self.primed = false;
+            return;
+        }
+
+        let excess = available.saturating_sub(2 * latency + frames);
+        if excess > 0 && let Ok(chunk) = self.consumer.read_chunk(excess * ring_channels) {
+            chunk.commit_all();
+        }
+
+        let Ok(chunk) = self.consumer.read_chunk(frames * ring_channels) else {
+            return;
+        };
+
+        let mut samples = chunk.into_iter();
+        for frame in 0..frames {
+            for channel in 0..self.main_channels {
+                let sample = samples.next().unwrap_or_default();
+
+                if self.main_channels == 1 {
+                    for main_channel in 0..main.channels() {
+                        main.channel_mut(main_channel)[frame] = sample;
+                    }
+                } else if channel < main.channels() {
+                    main.channel_mut(channel)[frame] = sample;
+                }
+            }
+
+            for channel in 0..self.aux_channels {
+                let sample = samples.next().unwrap_or_default();
+
+                if let Some(aux) = aux.as_mut().filter(|aux| channel < aux.channels()) {
+                    aux.channel_mut(channel)[frame] = sample;
+                }
+            }
+        }
+    }
+}
+
+/// Linear interpolation between the input and output sample rates
+struct Resampler {
+    // Input frames per output frame
+    step: f64,
+    // Position of the next output frame between the previous and the current input frame
+    position: f64,
+    previous: Vec<f32>,
+    interpolated: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(input_rate: cpal::SampleRate, output_rate: cpal::SampleRate, channels: usize) -> Self {
+        Self {
+            step: input_rate as f64 / output_rate as f64,
+            position: 0.0,
+            previous: vec![0.0; channels],
+            interpolated: vec![0.0; channels],
+        }
+    }
+
+    /// Passes the output frames up to `frame` to `output`
+    fn process(&mut self, frame: &[f32], mut output: impl FnMut(&[f32])) {
+        if self.step == 1.0 {
+            output(frame);
+            return;
+        }
+
+        while self.position < 1.0 {
+            let position = self.position as f32;
+            for ((sample, previous), current) in self.interpolated.iter_mut().zip(self.previous.iter()).zip(frame.iter()) {
+                *sample = previous + (current - previous) * position;
+            }
+
+            output(&self.interpolated);
+            self.position += self.step;
+        }
+
+        self.position -= 1.0;
+        self.previous.copy_from_slice(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, atomic::AtomicUsize};
+
+    use plinth_core::{buffers::buffer::Buffer, signals::signal::Signal};
+    use rtrb::RingBuffer;
+
+    use super::{InputCapture, InputReceiver, Resampler, RING_BLOCKS};
+
+    #[test]
+    fn resampler_doubles_rate() {
+        let mut resampler = Resampler::new(24000, 48000, 1);
+        let mut output = Vec::new();
+
+        for sample in [1.0, 2.0, 3.0] {
+            resampler.process(&[sample], |frame| output.push(frame[0]));
+        }
+
+        assert_eq!(output, [0.0, 0.5, 1.0, 1.5, 2.0, 2.5]);
+    }
+
+    #[test]
+    fn receiver_waits_for_latency() {
+        let (mut producer, consumer) = RingBuffer::new(64);
+        let mut receiver = InputReceiver {
+            consumer,
+            main_channels: 1,
+            aux_channels: 0,
+            input_block_size: Arc::new(AtomicUsize::new(4)),
+            output_block_size: 4,
+            primed: false,
+        };
+
+        let mut buffer = Buffer::new(2, 4);
+        let aux: Option<&mut Buffer> = None;
+
+        for sample in 0..8 {
+            producer.push(sample as f32).unwrap();
+        }
+        receiver.read(&mut buffer, aux);
+        assert_eq!(buffer.channel(0), [0.0; 4]);
+
+        for sample in 8..12 {
+            producer.push(sample as f32).unwrap();
+        }
+        let aux: Option<&mut Buffer> = None;
+        receiver.read(&mut buffer, aux);
+        assert_eq!(buffer.channel(0), [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(buffer.channel(1), [0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn receiver_recovers_from_overflow() {
+        const BLOCK_SIZE: usize = 4;
+
+        let (producer, consumer) = RingBuffer::new(BLOCK_SIZE * RING_BLOCKS);
+        let mut capture = InputCapture {
+            producer,
+            resampler: Resampler::new(48000, 48000, 1),
+            frame: vec![0.0],
+            channels: vec![0],
+            device_channels: 1,
+            block_size: Arc::new(AtomicUsize::new(0)),
+        };
+        let mut receiver = InputReceiver {
+            consumer,
+            main_channels: 1,
+            aux_channels: 0,
+            input_block_size: Arc::new(AtomicUsize::new(BLOCK_SIZE)),
+            output_block_size: BLOCK_SIZE,
+            primed: false,
+        };
+
+        // The output stalled, so the input overflows the ring and the frames that don't fit are dropped
+        let input: Vec<f32> = (0..40).map(|sample| sample as f32).collect();
+        capture.capture(&input);
+
+        // Frames beyond twice the latency are skipped
+        let mut buffer = Buffer::new(1, BLOCK_SIZE);
+        let aux: Option<&mut Buffer> = None;
+        receiver.read(&mut buffer, aux);
+        assert_eq!(buffer.channel(0), [12.0, 13.0, 14.0, 15.0]);
+
+        for _ in 0..4 {
+            let aux: Option<&mut Buffer> = None;
+            receiver.read(&mut buffer, aux);
+        }
+        assert_eq!(buffer.channel(0), [28.0, 29.0, 30.0, 31.0]);
+
+        // Out of input, so there's silence until the latency is buffered again
+        let aux: Option<&mut Buffer> = None;
+        receiver.read(&mut buffer, aux);
+        assert_eq!(buffer.channel(0), [0.0; 4]);
+        assert!(!receiver.primed);
+    }
+}
@@ -1,13 +1,16 @@
-use std::sync::mpsc::Sender;
+use std::sync::{mpsc::Sender, Arc};
 
 use midir::{MidiInput, MidiInputConnection};
 
 use super::config::MidiInputConfig;
-use crate::Event;
+use crate::{midi::{parse_midi, LearnedControllers}, Event, MidiCcAssignment};
 
 pub fn connect_inputs(
     config: &MidiInputConfig,
+    midi_cc_assignments: &[MidiCcAssignment],
+    learned_controllers: Option<Arc<LearnedControllers>>,
     sender: Sender<Event>,
+    to_plugin_sender: Sender<Event>,
 ) -> Vec<MidiInputConnection<()>> {
     let midi_in = match MidiInput::new("plinth-standalone") {
         Ok(m) => m,
@@ -41,11 +44,20 @@ pub fn connect_inputs(
         };
 
         let sender = sender.clone();
+        let to_plugin_sender = to_plugin_sender.clone();
+        let midi_cc_assignments = midi_cc_assignments.to_vec();
+        let learned_controllers = learned_controllers.clone();
+
         match midi_in.connect(
             port,
             "plinth-midi-input",
             move |_timestamp, data, _| {
-                if let Some(event) = parse_midi(data) {
+                if let Some(event) = parse_midi(data, 0, &midi_cc_assignments, learned_controllers.as_deref()) {
+                    // Control changes also need to reach the plugin, for parameter changes and MIDI learn
+                    if let Event::ParameterValue { .. } | Event::ControlChange { .. } = event {
+                        let _ = to_plugin_sender.send(event.clone());
+                    }
+
                     let _ = sender.send(event);
                 }
             },
@@ -61,49 +73,3 @@ pub fn connect_inputs(
 
     connections
 }
-
-fn parse_midi(data: &[u8]) -> Option<Event> {
-    if data.len() < 2 {
-        return None;
-    }
-
-    let status = data[0] & 0xF0;
-    let channel = (data[0] & 0x0F) as i16;
-    let key = data[1] as i16;
-    let velocity = if data.len() >= 3 {
-        data[2] as f64 / 127.0
-    } else {
-        0.0
-    };
-
-    match status {
-        0x90 if data.len() >= 3 && data[2] > 0 => Some(Event::NoteOn {
-            sample_offset: 0,
-            channel,
-            key,
-            note: -1,
-            velocity,
-        }),
-        0x80 | 0x90 => Some(Event::NoteOff {
-            sample_offset: 0,
-            channel,
-            key,
-            note: -1,
-            velocity,
-        }),
-        0xE0 if data.len() >= 3 => {
-            let lsb = data[1] as i16;
-            let msb = data[2] as i16;
-            let bend = (msb << 7 | lsb) - 8192;
-            let semitones = bend as f64 / 8192.0 * 2.0;
-            Some(Event::PitchBend {
-                sample_offset: 0,
-                channel,
-                key: -1,
-                note: -1,
-                semitones,
-            })
-        }
-        _ => None,
-    }
-}
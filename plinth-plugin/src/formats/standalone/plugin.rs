@@ -1,6 +1,8 @@
 use crate::Plugin;
 
 pub trait StandalonePlugin: Plugin {
+    /// Opens the default audio input device in [`run_standalone`](super::run_standalone), for effect plugins
+    const HAS_AUDIO_INPUT: bool = false;
     const EVENT_QUEUE_LEN: usize = 1024;
     const MAX_BLOCK_SIZE: usize = 4096;
 }
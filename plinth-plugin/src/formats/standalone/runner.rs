@@ -1,39 +1,281 @@
-use std::{rc::Rc, sync::{Arc, mpsc}, time::{Duration, Instant}};
+use std::{cell::Cell, path::{Path, PathBuf}, rc::Rc, sync::{Arc, mpsc}, time::{Duration, Instant}};
 
-use cpal::{BufferSize, FromSample, I24, SizedSample, Stream, StreamConfig, traits::{DeviceTrait, StreamTrait}};
-use midir::MidiInputConnection;
 use raw_window_handle::HasWindowHandle;
-use winit::{application::ApplicationHandler, event::WindowEvent, event_loop::{ActiveEventLoop, ControlFlow, EventLoop}, window::{Window, WindowAttributes, WindowId}};
+use winit::{application::ApplicationHandler, dpi::{LogicalSize, PhysicalSize, Size}, event::{ElementState, KeyEvent, Modifiers, WindowEvent}, event_loop::{ActiveEventLoop, ControlFlow, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowAttributes, WindowId}};
 
-use super::{parameters::StandaloneParameterEventMap, audio::AudioState, config::{AudioOutputConfig, MidiInputConfig}, host::StandaloneHost, midi, plugin::StandalonePlugin};
+use super::{args::Args, config::{AudioInputConfig, AudioOutputConfig, MidiInputConfig}, engine::Engine, host::StandaloneHost, parameters::StandaloneParameterEventMap, plugin::StandalonePlugin, settings::StandaloneSettings, state};
+#[cfg(feature = "standalone-settings")]
+use super::settings_window::{SettingsAction, SettingsInputs, SettingsWindow};
 
-use crate::{Editor, Event, Host, HostInfo, ProcessMode, Processor, ProcessorConfig, formats::PluginFormat};
+use crate::{bundle, Editor, Event, Host, HostInfo, StateContext, formats::PluginFormat, logging};
 
+// Without the settings window, the engine is never restarted and settings aren't saved, so what's only used
+// for that isn't read
+#[cfg_attr(not(feature = "standalone-settings"), allow(dead_code))]
 struct StandaloneRunner<P: StandalonePlugin> {
     plugin: P,
     editor: P::Editor,
+    to_plugin_sender: mpsc::Sender<Event>,
     to_plugin_receiver: mpsc::Receiver<Event>,
-    title: &'static str,
+    parameter_event_map: Arc<StandaloneParameterEventMap>,
+    // State file that was opened or saved last, saving without choosing a file saves to it
+    state_file: Option<PathBuf>,
+    modifiers: Modifiers,
     size: (f64, f64),
     window: Option<Window>,
     last_frame: Instant,
-    audio_stream: Stream,
-    midi_connections: Vec<MidiInputConnection<()>>,
+    engine: Option<Engine>,
+    settings: StandaloneSettings,
+    // Where settings and the session state are kept, unless the app was run with a config and nothing is kept
+    settings_path: Option<PathBuf>,
+    session_state_path: Option<PathBuf>,
+    // Settings from the settings file at launch, and with the command line applied. Applied settings are
+    // saved as the former with what changed from the latter, so command-line settings aren't saved.
+    saved_settings: StandaloneSettings,
+    launch_settings: StandaloneSettings,
+    // Why the engine couldn't be started with the current settings
+    settings_error: Option<String>,
+    #[cfg(feature = "standalone-settings")]
+    settings_window: Option<SettingsWindow>,
+    settings_requested: Rc<Cell<bool>>,
+}
+
+/// Why the standalone app couldn't run
+#[derive(Debug, thiserror::Error)]
+pub enum StandaloneError {
+    #[error("{}", .0.join("\n"))]
+    InvalidSettings(Vec<String>),
+    #[error("Event loop error: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
 }
 
 impl<P: StandalonePlugin> Drop for StandaloneRunner<P> {
     fn drop(&mut self) {
-        let _ = self.audio_stream.pause();
-        self.midi_connections.clear();
+        // Stop processing before the plugin goes away
+        self.engine = None;
+    }
+}
+
+impl<P: StandalonePlugin + 'static> StandaloneRunner<P> {
+    fn title(&self) -> String {
+        match self.state_file.as_ref().and_then(|path| path.file_name()) {
+            Some(file_name) => format!("{} - {}", P::NAME, file_name.display()),
+            None => P::NAME.to_string(),
+        }
+    }
+
+    fn process_plugin_events(&mut self) {
+        while let Ok(event) = self.to_plugin_receiver.try_recv() {
+            // Learned controllers are mapped on the MIDI thread, so control changes only end up here
+            // if they're unassigned or about to be learned
+            if self.plugin.midi_learn().is_some_and(|midi_learn| midi_learn.process_event(&event)) {
+                continue;
+            }
+
+            self.plugin.process_event(&event);
+        }
+    }
+
+    fn open_state_file(&mut self, path: PathBuf) {
+        // Changes from the editor that are still queued would be applied on top of the loaded state
+        self.process_plugin_events();
+
+        if !load_state_file(&mut self.plugin, &path, StateContext::Preset) {
+            return;
+        }
+
+        self.set_state_file(path);
+    }
+
+    fn set_state_file(&mut self, path: PathBuf) {
+        self.state_file = Some(path);
+
+        if let Some(window) = self.window.as_ref() {
+            window.set_title(&self.title());
+        }
+
+        #[cfg(feature = "standalone-settings")]
+        if let Some(settings_window) = self.settings_window.as_mut() {
+            settings_window.state_file_changed(self.state_file.as_ref());
+        }
+    }
+
+    /// Saves to the current state file, or lets the user choose one
+    fn save_state(&mut self, event_loop: &ActiveEventLoop) {
+        match self.state_file.clone() {
+            Some(path) => self.save_state_as(path),
+            None => self.choose_state_file(event_loop),
+        }
+    }
+
+    #[cfg(feature = "standalone-settings")]
+    fn choose_state_file(&mut self, event_loop: &ActiveEventLoop) {
+        self.open_settings(event_loop);
+    }
+
+    #[cfg(not(feature = "standalone-settings"))]
+    fn choose_state_file(&mut self, _event_loop: &ActiveEventLoop) {
+        log::warn!("Choosing a state file to save to needs the standalone-settings feature, a new state file can be given on the command line instead");
+    }
+
+    fn save_state_as(&mut self, path: PathBuf) {
+        self.process_plugin_events();
+
+        if let Err(err) = state::save_state_file(&self.plugin, &path, StateContext::Preset) {
+            let error = format!("Failed to save state to {}: {err:?}", path.display());
+            log::error!("{error}");
+
+            #[cfg(feature = "standalone-settings")]
+            if let Some(settings_window) = self.settings_window.as_mut() {
+                settings_window.save_failed(error);
+            }
+
+            return;
+        }
+
+        self.set_state_file(path);
+    }
+
+    /// Saves the session state, state files are only written when the user saves them
+    fn save_session_state(&mut self) {
+        self.process_plugin_events();
+
+        if let Some(path) = self.session_state_path.as_ref() {
+            save_state_file(&self.plugin, path, StateContext::Project);
+        }
+    }
+
+    /// Ctrl+S (Cmd+S on macOS) saves the state, with Shift it's saved to a new file
+    fn key_event(&mut self, event_loop: &ActiveEventLoop, event: &KeyEvent) {
+        let modifiers = self.modifiers.state();
+        let command = if cfg!(target_os = "macos") { modifiers.super_key() } else { modifiers.control_key() };
+
+        if event.state != ElementState::Pressed || event.repeat || !command || event.physical_key != PhysicalKey::Code(KeyCode::KeyS) {
+            return;
+        }
+
+        if modifiers.shift_key() {
+            self.choose_state_file(event_loop);
+        } else {
+            self.save_state(event_loop);
+        }
+    }
+
+    #[cfg(feature = "standalone-settings")]
+    fn open_settings(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(settings_window) = self.settings_window.as_ref() {
+            settings_window.focus();
+            return;
+        }
+
+        let inputs = SettingsInputs {
+            midi: P::HAS_NOTE_INPUT || !self.plugin.midi_cc_assignments().is_empty() || self.plugin.midi_learn().is_some(),
+            aux_audio: P::HAS_AUX_INPUT,
+        };
+
+        match SettingsWindow::open(event_loop, P::NAME, self.settings.clone(), inputs, self.state_file.clone(), self.settings_error.clone()) {
+            Ok(settings_window) => self.settings_window = Some(settings_window),
+            Err(err) => log::error!("Failed to open settings window, settings can be given on the command line instead: {err}"),
+        }
+    }
+
+    #[cfg(not(feature = "standalone-settings"))]
+    fn open_settings(&mut self, _event_loop: &ActiveEventLoop) {
+        log::warn!("The settings window needs the standalone-settings feature, settings can be given on the command line instead");
+    }
+
+    /// Restarts audio and MIDI with a new processor, the settings are saved if that works
+    #[cfg(feature = "standalone-settings")]
+    fn apply_settings(&mut self, settings: StandaloneSettings) {
+        // The old processor is dropped first, so there's only ever one
+        self.engine = None;
+
+        match Engine::start(&mut self.plugin, &settings, self.parameter_event_map.clone(), self.to_plugin_sender.clone()) {
+            Ok(engine) => {
+                self.engine = Some(engine);
+                self.settings_error = None;
+
+                let mut saved_settings = self.saved_settings.clone();
+                saved_settings.apply_changes(&self.launch_settings, &settings);
+
+                if let Some(path) = self.settings_path.as_ref()
+                    && let Err(err) = saved_settings.save(path)
+                {
+                    log::error!("Failed to save settings to {}: {err}", path.display());
+                }
+            }
+            Err(err) => {
+                log::error!("Failed to start audio: {err}");
+                self.settings_error = Some(format!("Failed to start audio: {err}"));
+            }
+        }
+
+        self.settings = settings;
+
+        if let Some(settings_window) = self.settings_window.as_mut() {
+            settings_window.applied(self.settings.clone(), self.settings_error.clone());
+        }
+    }
+
+    #[cfg(feature = "standalone-settings")]
+    fn settings_window_event(&mut self, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            self.settings_window = None;
+            return;
+        }
+
+        match self.settings_window.as_mut().and_then(|settings_window| settings_window.window_event(&event)) {
+            Some(SettingsAction::Apply(settings)) => self.apply_settings(settings),
+            Some(SettingsAction::SaveState(path)) => self.save_state_as(path),
+            None => {},
+        }
+    }
+
+    /// Converts an editor size to a window size; on macOS editor sizes are logical, elsewhere physical
+    fn window_size(size: (f64, f64)) -> Size {
+        if cfg!(target_os = "macos") {
+            LogicalSize::new(size.0, size.1).into()
+        } else {
+            PhysicalSize::new(size.0, size.1).into()
+        }
+    }
+
+    fn resized(&mut self, physical_size: PhysicalSize<u32>) {
+        let Some(window) = self.window.as_ref() else {
+            return;
+        };
+
+        if !self.editor.can_resize() {
+            return;
+        }
+
+        let size = if cfg!(target_os = "macos") {
+            let logical_size = physical_size.to_logical::<f64>(window.scale_factor());
+            (logical_size.width, logical_size.height)
+        } else {
+            (physical_size.width as f64, physical_size.height as f64)
+        };
+
+        let Some(supported_size) = self.editor.check_window_size(size) else {
+            return;
+        };
+
+        // Snap the window to the supported size, allowing for rounding to whole pixels
+        if (supported_size.0 - size.0).abs() >= 1.0 || (supported_size.1 - size.1).abs() >= 1.0 {
+            let _ = window.request_inner_size(Self::window_size(supported_size));
+        }
+
+        self.editor.set_window_size(supported_size.0, supported_size.1);
+        self.size = supported_size;
     }
 }
 
-impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
+impl<P: StandalonePlugin + 'static> ApplicationHandler for StandaloneRunner<P> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         // Create new window
         let attrs = WindowAttributes::default()
-            .with_title(self.title)
-            .with_inner_size(winit::dpi::LogicalSize::new(self.size.0, self.size.1))
+            .with_title(self.title())
+            .with_inner_size(LogicalSize::new(self.size.0, self.size.1))
             .with_resizable(self.editor.can_resize());
 
         let window = match event_loop.create_window(attrs) {
@@ -52,6 +294,17 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
         }
         self.size = self.editor.window_size();
 
+        if self.editor.can_resize() {
+            let constraints = self.editor.resize_constraints();
+
+            window.set_min_inner_size(constraints.min_size.map(Self::window_size));
+            window.set_max_inner_size(constraints.max_size.map(Self::window_size));
+
+            if constraints.aspect_ratio.is_none() {
+                window.set_resize_increments(constraints.increments.map(Self::window_size));
+            }
+        }
+
         // Attach editor to the window
         let handle = window
             .window_handle()
@@ -62,12 +315,27 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
     }
 
     fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        #[cfg(feature = "standalone-settings")]
+        self.settings_window = None;
         self.editor.close();
         self.window = None;
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        #[cfg(feature = "standalone-settings")]
+        if self.settings_window.as_ref().is_some_and(|settings_window| settings_window.id() == id) {
+            self.settings_window_event(event);
+            return;
+        }
+
+        #[cfg(not(feature = "standalone-settings"))]
+        let _ = id;
+
         if let WindowEvent::CloseRequested = event {
+            // Save before closing the editor, so its state is still there
+            self.save_session_state();
+            #[cfg(feature = "standalone-settings")]
+            self.settings_window = None;
             self.editor.close();
             event_loop.exit();
         } else if let WindowEvent::ScaleFactorChanged {
@@ -80,6 +348,14 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
                 // see `resumed`impl
                 self.editor.set_scale(scale_factor);
             }
+        } else if let WindowEvent::Resized(physical_size) = event {
+            self.resized(physical_size);
+        } else if let WindowEvent::DroppedFile(path) = event {
+            self.open_state_file(path);
+        } else if let WindowEvent::ModifiersChanged(modifiers) = event {
+            self.modifiers = modifiers;
+        } else if let WindowEvent::KeyboardInput { event, .. } = event {
+            self.key_event(event_loop, &event);
         }
     }
 
@@ -91,11 +367,19 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
         let now = Instant::now();
         let frame_interval = Duration::from_millis(16);
 
+        if self.settings_requested.take() {
+            self.open_settings(event_loop);
+        }
+
         if now >= self.last_frame + frame_interval {
-            while let Ok(event) = self.to_plugin_receiver.try_recv() {
-                self.plugin.process_event(&event);
-            }
+            self.process_plugin_events();
             self.editor.on_frame();
+
+            #[cfg(feature = "standalone-settings")]
+            if let Some(settings_window) = self.settings_window.as_ref() {
+                settings_window.on_frame();
+            }
+
             self.last_frame = now;
         }
 
@@ -103,8 +387,13 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
     }
 }
 
-/// Runs the given plugin as a standalone application using the default audio output device and all available
-/// MIDI input ports (if the plugin has `HAS_NOTE_INPUT` set).
+/// Runs the given plugin as a standalone application using the default audio output device, the default audio
+/// input device (if the plugin has `HAS_AUDIO_INPUT` set) and all available MIDI input ports (if the plugin has
+/// `HAS_NOTE_INPUT` set or assigns MIDI CCs to parameters).
+///
+/// This is meant to be all of an app's `main`: it takes the command line, see `--help`, and exits with an
+/// error if the app can't run. These can be changed in the app's settings window. Plugin state is restored
+/// on launch and saved on exit, see [`run_standalone_from_args`].
 ///
 /// # Example
 ///
@@ -116,11 +405,46 @@ impl<P: StandalonePlugin> ApplicationHandler for StandaloneRunner<P> {
 /// }
 /// ```
 pub fn run_standalone<P: StandalonePlugin + 'static>() {
-    run_standalone_with_config::<P>(AudioOutputConfig::default(), MidiInputConfig::default());
+    let mut args = std::env::args_os();
+    let program_name = args.next()
+        .and_then(|program| Path::new(&program).file_stem().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| P::NAME.to_string());
+
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{}", Args::usage(&program_name));
+            std::process::exit(2);
+        }
+    };
+
+    if args.show_help {
+        print!("{}", Args::usage(&program_name));
+        return;
+    }
+
+    let input_config = P::HAS_AUDIO_INPUT.then(AudioInputConfig::default);
+
+    match run_standalone_from_args::<P>(AudioOutputConfig::default(), input_config, MidiInputConfig::default(), args) {
+        Ok(()) => {},
+        Err(err @ StandaloneError::InvalidSettings(_)) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        },
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        },
+    }
 }
 
 /// Runs the given plugin as a standalone application with explicit audio and MIDI configuration.
 ///
+/// The default audio input device is opened if the plugin has `HAS_AUDIO_INPUT` set, see
+/// [`run_standalone_with_input_config`] to choose the input. The command line isn't used, and nothing is kept
+/// between launches: the configs aren't replaced by saved settings, and plugin state isn't restored or saved.
+/// See [`run_standalone_from_args`] for that.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -149,131 +473,209 @@ pub fn run_standalone_with_config<P: StandalonePlugin + 'static>(
     audio_config: AudioOutputConfig,
     midi_config: MidiInputConfig,
 ) {
+    let input_config = P::HAS_AUDIO_INPUT.then(AudioInputConfig::default);
+    run_standalone_with_input_config::<P>(audio_config, input_config, midi_config);
+}
+
+/// Runs the given plugin as a standalone application with explicit audio input, audio output and MIDI configuration.
+///
+/// Audio input is only opened with an input config. It feeds the main input, and the aux input
+/// if the plugin has `HAS_AUX_INPUT` set. The command line isn't used, and nothing is kept between launches.
+/// Settings can still be changed in the settings window, but aren't saved. See [`run_standalone_from_args`]
+/// for how settings and state are kept.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn main() {
+///     // Use the first two channels of the default input device
+///     let input_config = AudioInputConfig {
+///         channels: Some(vec![0, 1]),
+///         ..Default::default()
+///     };
+///
+///     run_standalone_with_input_config::<MyPlugin>(AudioOutputConfig::default(), Some(input_config), MidiInputConfig::default());
+/// }
+/// ```
+pub fn run_standalone_with_input_config<P: StandalonePlugin + 'static>(
+    audio_config: AudioOutputConfig,
+    input_config: Option<AudioInputConfig>,
+    midi_config: MidiInputConfig,
+) {
+    run::<P>(audio_config, input_config, midi_config, Args::default(), false)
+        .expect("Failed to run standalone app");
+}
+
+/// Runs the given plugin as a standalone application with explicit configuration and command-line arguments
+/// parsed with [`Args::parse`], for apps that handle the command line themselves. `show_help` is left to them.
+///
+/// The configs are defaults for the settings window, which restarts audio and MIDI with a new processor when
+/// settings are applied and saves them in the user's config directory. Saved settings are used instead of the
+/// configs on later launches, and settings in `args`, like `--sample-rate 48000`, override both for one run
+/// without being saved. Editors can open the window with [`Host::open_settings`], as can `--settings`, and it
+/// opens by itself when audio fails to start. The window needs the `standalone-settings` feature.
+///
+/// Plugin and editor state are kept in the user's config directory between launches. A state file given on
+/// the command line, or dropped onto the window, is loaded instead and becomes the current file. A file given
+/// on the command line that doesn't exist yet becomes the current file without loading anything. Ctrl+S
+/// (Cmd+S on macOS) saves to the current file, and the settings window, which Ctrl+Shift+S opens, saves to
+/// any file. State files are only written when saved like this, and can be copied between machines.
+///
+/// Returns when the window is closed.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// fn main() -> ExitCode {
+///     let args = match Args::parse(std::env::args_os().skip(1)) {
+///         Ok(args) => args,
+///         Err(err) => {
+///             eprintln!("{err}\n\n{}", Args::usage("my-plugin"));
+///             return ExitCode::from(2);
+///         }
+///     };
+///
+///     match run_standalone_from_args::<MyPlugin>(AudioOutputConfig::default(), None, MidiInputConfig::default(), args) {
+///         Ok(()) => ExitCode::SUCCESS,
+///         Err(err) => {
+///             eprintln!("{err}");
+///             ExitCode::FAILURE
+///         }
+///     }
+/// }
+/// ```
+pub fn run_standalone_from_args<P: StandalonePlugin + 'static>(
+    audio_config: AudioOutputConfig,
+    input_config: Option<AudioInputConfig>,
+    midi_config: MidiInputConfig,
+    args: Args,
+) -> Result<(), StandaloneError> {
+    run::<P>(audio_config, input_config, midi_config, args, true)
+}
+
+/// Runs the app, settings and the session state are only loaded and saved with `persist`
+fn run<P: StandalonePlugin + 'static>(
+    audio_config: AudioOutputConfig,
+    input_config: Option<AudioInputConfig>,
+    midi_config: MidiInputConfig,
+    args: Args,
+    persist: bool,
+) -> Result<(), StandaloneError> {
+    let _log_registration = P::LOG_LEVEL
+        .and_then(|level| logging::register_file_sink(level, P::VENDOR, P::NAME).ok());
+
+    // Saved settings replace the ones given in code, and the command line replaces both
+    let mut saved_settings = StandaloneSettings {
+        audio: audio_config,
+        input: input_config,
+        midi: midi_config,
+    };
+
+    let settings_path = persist.then(StandaloneSettings::path::<P>).flatten();
+    let session_state_path = persist.then(state::session_state_path::<P>).flatten();
+
+    if let Some(path) = settings_path.as_ref().filter(|path| path.exists())
+        && let Err(err) = saved_settings.load(path)
+    {
+        log::error!("Failed to load settings from {}: {err}", path.display());
+    }
+
+    let mut settings = saved_settings.clone();
+    let errors = settings.apply(args.settings.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+    if !errors.is_empty() {
+        return Err(StandaloneError::InvalidSettings(errors));
+    }
+
     let host_info = HostInfo {
         name: Some("Standalone".to_string()),
         format: PluginFormat::Standalone,
+        bundle_path: std::env::current_exe().ok().map(|path| bundle::bundle_path(&path, "app")),
     };
 
     let mut plugin = P::new(host_info);
 
+    // Restore state before creating the processor, so it starts with the right parameter values.
+    // A file that doesn't exist yet becomes the current file, so saving creates it. A file that fails
+    // to load isn't kept as the current file, so saving doesn't overwrite it.
+    let mut state_file_loaded = false;
+    let state_file = args.state_file.filter(|path| {
+        if !path.exists() {
+            return true;
+        }
+
+        state_file_loaded = load_state_file(&mut plugin, path, StateContext::Preset);
+        state_file_loaded
+    });
+
+    if !state_file_loaded && let Some(path) = session_state_path.as_ref().filter(|path| path.exists()) {
+        load_state_file(&mut plugin, path, StateContext::Project);
+    }
+
     // Parameter event map (shared between host and audio thread)
     let parameter_event_map =
         plugin.with_parameters(|params| Arc::new(StandaloneParameterEventMap::new(params)));
 
     // Channels
-    let (midi_sender, midi_receiver) = mpsc::channel::<Event>();
     let (to_plugin_sender, to_plugin_receiver) = mpsc::channel::<Event>();
 
-    // Open MIDI connections if plugin accepts note inputs
-    let midi_connections = if P::HAS_NOTE_INPUT {
-        midi::connect_inputs(&midi_config, midi_sender)
-    } else {
-        vec![]
-    };
-
-    // Open audio device
-    let mut audio_host = audio_config
-        .open_host()
-        .expect("Failed to open audio driver");
-    let audio_device = audio_config
-        .open_device(&mut audio_host)
-        .expect("Failed to open audio device");
-    let audio_stream_config = audio_config
-        .select_stream_config(&audio_device)
-        .expect("Failed to select audio stream config");
-
-    // Create processor
-    // NB: CPAL unfortunately has no getter for the real applied block size, so we need to ensure that the processor never gets called with more frames
-    let processor_config = ProcessorConfig {
-        sample_rate: audio_stream_config.sample_rate() as f64,
-        min_block_size: 1,
-        max_block_size: P::MAX_BLOCK_SIZE,
-        process_mode: ProcessMode::Realtime,
-    };
-    let mut processor = plugin.create_processor(processor_config);
-    processor.reset();
-
-    // Create audio state
-    let audio_state = AudioState::<P>::new(
-        processor,
-        audio_stream_config.channels() as usize,
-        midi_receiver,
-        parameter_event_map.clone(),
-    );
-
-    // Create and start the CPAL stream
-    fn run_audio_stream<P, T>(
-        device: &cpal::Device,
-        config: cpal::StreamConfig,
-        mut audio_state: AudioState<P>,
-    ) -> Result<Stream, Box<dyn std::error::Error>>
-    where
-        P: StandalonePlugin + 'static,
-        T: SizedSample + FromSample<f32>,
-        f32: FromSample<T>,
-    {
-        let channels = config.channels as usize;
-
-        let stream = device.build_output_stream(
-            &config,
-            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                audio_state.process(data, channels);
-            },
-            |err| {
-                log::error!("An audio stream error occurred: {err}");
-            },
-            None,
-        )?;
-        stream.play()?;
-
-        Ok(stream)
-    }
-
-    let stream_format = audio_stream_config.sample_format();
-    let stream_config = StreamConfig {
-        channels: audio_stream_config.channels(),
-        sample_rate: audio_stream_config.sample_rate(),
-        buffer_size: audio_config
-            .buffer_size
-            .map(BufferSize::Fixed)
-            .unwrap_or(BufferSize::Default),
+    // The app keeps running without audio if the settings don't work, and opens the settings window instead
+    let (engine, settings_error) = match Engine::start(&mut plugin, &settings, parameter_event_map.clone(), to_plugin_sender.clone()) {
+        Ok(engine) => (Some(engine), None),
+        Err(err) => {
+            log::error!("Failed to start audio: {err}");
+            (None, Some(format!("Failed to start audio: {err}")))
+        }
     };
 
-    let audio_stream = match stream_format {
-        cpal::SampleFormat::I8 => run_audio_stream::<P, i8>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::I16 => run_audio_stream::<P, i16>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::I24 => run_audio_stream::<P, I24>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::I32 => run_audio_stream::<P, i32>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::I64 => run_audio_stream::<P, i64>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::U8 => run_audio_stream::<P, u8>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::U16 => run_audio_stream::<P, u16>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::U32 => run_audio_stream::<P, u32>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::U64 => run_audio_stream::<P, u64>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::F32 => run_audio_stream::<P, f32>(&audio_device, stream_config, audio_state),
-        cpal::SampleFormat::F64 => run_audio_stream::<P, f64>(&audio_device, stream_config, audio_state),
-        sample_format => panic!("Unsupported sample format '{sample_format}'"),
-    }
-    .expect("Failed to build audio output stream");
+    let settings_requested = Rc::new(Cell::new(args.show_settings || settings_error.is_some()));
 
     // Create host and editor
-    let host = Rc::new(StandaloneHost::new(parameter_event_map, to_plugin_sender));
+    let host = Rc::new(StandaloneHost::new(parameter_event_map.clone(), to_plugin_sender.clone(), settings_requested.clone()));
     let editor = plugin.create_editor(host as Rc<dyn Host>);
 
     // Create winit event loop
-    let event_loop = EventLoop::new().expect("Failed to create event loop");
+    let event_loop = EventLoop::new()?;
 
     // Run winit event loop (blocks until window is closed)
     let mut runner = StandaloneRunner {
         plugin,
         editor,
+        to_plugin_sender,
         to_plugin_receiver,
-        title: P::NAME,
+        parameter_event_map,
+        state_file,
+        modifiers: Modifiers::default(),
         size: P::Editor::DEFAULT_SIZE,
         window: None,
         last_frame: Instant::now(),
-        audio_stream,
-        midi_connections,
+        engine,
+        launch_settings: settings.clone(),
+        settings,
+        settings_path,
+        session_state_path,
+        saved_settings,
+        settings_error,
+        #[cfg(feature = "standalone-settings")]
+        settings_window: None,
+        settings_requested,
     };
 
-    event_loop.run_app(&mut runner).expect("Event loop error");
+    event_loop.run_app(&mut runner)?;
+    Ok(())
+}
+
+fn save_state_file<P: StandalonePlugin>(plugin: &P, path: &Path, context: StateContext) {
+    if let Err(err) = state::save_state_file(plugin, path, context) {
+        log::error!("Failed to save state to {}: {err:?}", path.display());
+    }
+}
+
+fn load_state_file<P: StandalonePlugin>(plugin: &mut P, path: &Path, context: StateContext) -> bool {
+    match state::load_state_file(plugin, path, context) {
+        Ok(()) => true,
+        Err(err) => {
+            log::error!("Failed to load state from {}: {err:?}", path.display());
+            false
+        }
+    }
 }
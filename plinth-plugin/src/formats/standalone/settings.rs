@@ -0,0 +1,271 @@
+use std::{fs, io, path::{Path, PathBuf}, str::FromStr};
+
+use super::config::{AudioDeviceDriver, AudioInputConfig, AudioOutputConfig, MidiInputConfig};
+use super::state;
+use crate::Plugin;
+
+/// Keys and descriptions of the settings, which are the same in the settings file and on the command line
+pub const SETTINGS_KEYS: &[(&str, &str)] = &[
+    ("driver", "Audio driver name, or default"),
+    ("output-device", "Audio output device id, or default"),
+    ("input-device", "Audio input device id, or default"),
+    ("input-channels", "Input device channels feeding the main input, from 0, like 0,1, or default"),
+    ("aux-input-channels", "Input device channels feeding the aux input, from 0, like 2,3, or default"),
+    ("sample-rate", "Sample rate in Hz, or default"),
+    ("buffer-size", "Buffer size in frames, or default"),
+    ("midi-port", "MIDI input port name, all or none, repeat for more ports"),
+];
+
+const DEFAULT: &str = "default";
+const ALL_PORTS: &str = "all";
+const NO_PORTS: &str = "none";
+
+/// Audio and MIDI settings of the standalone app
+///
+/// The input uses the same driver as the output. `input` is `None` when the app doesn't open an audio input.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StandaloneSettings {
+    pub audio: AudioOutputConfig,
+    pub input: Option<AudioInputConfig>,
+    pub midi: MidiInputConfig,
+}
+
+impl StandaloneSettings {
+    /// Where the settings are saved
+    pub fn path<P: Plugin>() -> Option<PathBuf> {
+        state::plugin_config_directory::<P>().map(|directory| directory.join("settings"))
+    }
+
+    /// Applies settings from a saved file, missing settings are left as they are
+    pub fn load(&mut self, path: &Path) -> io::Result<()> {
+        let settings = fs::read_to_string(path)?;
+
+        let pairs = settings.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| line.split_once('='));
+
+        for err in self.apply(pairs) {
+            log::warn!("Ignoring setting in {}: {err}", path.display());
+        }
+
+        Ok(())
+    }
+
+    /// Applies `(key, value)` pairs and returns the errors for the ones that couldn't be applied
+    ///
+    /// MIDI ports given here replace the current ones instead of adding to them.
+    pub fn apply<'a>(&mut self, pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut midi_ports_replaced = false;
+
+        for (key, value) in pairs {
+            let (key, value) = (key.trim(), value.trim());
+
+            let result = match key {
+                "driver" => AudioDeviceDriver::from_name(value)
+                    .ok_or_else(|| format!("Unknown audio driver '{value}'"))
+                    .map(|driver| {
+                        self.audio.driver = driver;
+                        if let Some(input) = self.input.as_mut() {
+                            input.driver = driver;
+                        }
+                    }),
+
+                "output-device" => parse_or_default(value).map(|device_id| self.audio.device_id = device_id),
+
+                "input-device" => parse_or_default(value).map(|device_id| {
+                    if let Some(input) = self.input.as_mut() {
+                        input.device_id = device_id;
+                    }
+                }),
+
+                "input-channels" => parse_channels(value).map(|channels| {
+                    if let Some(input) = self.input.as_mut() {
+                        input.channels = channels;
+                    }
+                }),
+
+                "aux-input-channels" => parse_channels(value).map(|channels| {
+                    if let Some(input) = self.input.as_mut() {
+                        input.aux_channels = channels;
+                    }
+                }),
+
+                "sample-rate" => parse_or_default(value).map(|sample_rate| self.audio.sample_rate = sample_rate),
+                "buffer-size" => parse_or_default(value).map(|buffer_size| self.audio.buffer_size = buffer_size),
+
+                "midi-port" => {
+                    if !midi_ports_replaced {
+                        self.midi.port_names = None;
+                        midi_ports_replaced = true;
+                    }
+
+                    match value {
+                        ALL_PORTS => self.midi.port_names = None,
+                        NO_PORTS => self.midi.port_names = Some(Vec::new()),
+                        port_name => self.midi.port_names.get_or_insert_default().push(port_name.to_string()),
+                    }
+
+                    Ok(())
+                },
+
+                _ => Err(format!("Unknown setting '{key}'")),
+            };
+
+            if let Err(err) = result {
+                errors.push(err);
+            }
+        }
+
+        errors
+    }
+}
+
+// Settings are only changed and saved in the settings window
+#[cfg_attr(not(feature = "standalone-settings"), allow(dead_code))]
+impl StandaloneSettings {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut settings = String::new();
+        for (key, value) in self.to_pairs() {
+            settings.push_str(&format!("{key}={value}\n"));
+        }
+
+        state::replace_file(path, settings.as_bytes())
+    }
+
+    /// Applies the settings that differ between `from` and `to`
+    ///
+    /// This keeps settings that were given on the command line out of the saved ones, unless they're changed.
+    pub fn apply_changes(&mut self, from: &StandaloneSettings, to: &StandaloneSettings) {
+        let from_pairs = from.to_pairs();
+        let to_pairs = to.to_pairs();
+
+        let values = |pairs: &[(&'static str, String)], key: &str| -> Vec<String> {
+            pairs.iter()
+                .filter(|(pair_key, _)| *pair_key == key)
+                .map(|(_, value)| value.clone())
+                .collect()
+        };
+
+        let changed = to_pairs.iter()
+            .filter(|(key, _)| values(&from_pairs, key) != values(&to_pairs, key))
+            .map(|(key, value)| (*key, value.as_str()));
+
+        for err in self.apply(changed) {
+            log::warn!("Ignoring changed setting: {err}");
+        }
+    }
+
+    fn to_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            ("driver", self.audio.driver.name().to_string()),
+            ("output-device", display_or_default(self.audio.device_id.as_ref())),
+        ];
+
+        if let Some(input) = self.input.as_ref() {
+            pairs.push(("input-device", display_or_default(input.device_id.as_ref())));
+            pairs.push(("input-channels", display_channels(input.channels.as_deref())));
+            pairs.push(("aux-input-channels", display_channels(input.aux_channels.as_deref())));
+        }
+
+        pairs.push(("sample-rate", display_or_default(self.audio.sample_rate.as_ref())));
+        pairs.push(("buffer-size", display_or_default(self.audio.buffer_size.as_ref())));
+
+        match self.midi.port_names.as_deref() {
+            None => pairs.push(("midi-port", ALL_PORTS.to_string())),
+            Some([]) => pairs.push(("midi-port", NO_PORTS.to_string())),
+            Some(port_names) => pairs.extend(port_names.iter().map(|port_name| ("midi-port", port_name.clone()))),
+        }
+
+        pairs
+    }
+}
+
+fn parse_or_default<T: FromStr>(value: &str) -> Result<Option<T>, String> {
+    if value == DEFAULT {
+        return Ok(None);
+    }
+
+    value.parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid value '{value}'"))
+}
+
+fn display_or_default(value: Option<&impl ToString>) -> String {
+    value.map_or_else(|| DEFAULT.to_string(), ToString::to_string)
+}
+
+fn parse_channels(value: &str) -> Result<Option<Vec<usize>>, String> {
+    if value == DEFAULT {
+        return Ok(None);
+    }
+
+    value.split(',')
+        .map(|channel| channel.trim().parse())
+        .collect::<Result<_, _>>()
+        .map(Some)
+        .map_err(|_| format!("Invalid channels '{value}'"))
+}
+
+fn display_channels(channels: Option<&[usize]>) -> String {
+    match channels {
+        Some(channels) => channels.iter().map(ToString::to_string).collect::<Vec<_>>().join(","),
+        None => DEFAULT.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StandaloneSettings;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = StandaloneSettings {
+            input: Some(Default::default()),
+            ..Default::default()
+        };
+
+        let errors = settings.apply([("sample-rate", "48000"), ("input-channels", "2, 3"), ("midi-port", "Keys"), ("midi-port", "Pads")]);
+        assert!(errors.is_empty());
+
+        let pairs = settings.to_pairs();
+        let mut loaded = StandaloneSettings {
+            input: Some(Default::default()),
+            ..Default::default()
+        };
+        assert!(loaded.apply(pairs.iter().map(|(key, value)| (*key, value.as_str()))).is_empty());
+
+        assert_eq!(loaded, settings);
+        assert_eq!(loaded.audio.sample_rate, Some(48000));
+        assert_eq!(loaded.midi.port_names, Some(vec!["Keys".to_string(), "Pads".to_string()]));
+        assert_eq!(loaded.input.unwrap().channels, Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn only_changed_settings_are_applied() {
+        let saved = StandaloneSettings::default();
+
+        // Given on the command line
+        let mut launched = saved.clone();
+        assert!(launched.apply([("sample-rate", "96000")]).is_empty());
+
+        let mut changed = launched.clone();
+        assert!(changed.apply([("buffer-size", "256")]).is_empty());
+
+        let mut to_save = saved.clone();
+        to_save.apply_changes(&launched, &changed);
+
+        assert_eq!(to_save.audio.sample_rate, None);
+        assert_eq!(to_save.audio.buffer_size, Some(256));
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let mut settings = StandaloneSettings::default();
+
+        let errors = settings.apply([("buffer-size", "lots"), ("volume", "11"), ("buffer-size", "256")]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(settings.audio.buffer_size, Some(256));
+    }
+}
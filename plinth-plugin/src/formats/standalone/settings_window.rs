@@ -0,0 +1,511 @@
+use std::{num::NonZeroU32, path::PathBuf, sync::Arc};
+
+use cpal::DeviceId;
+use egui::{Button, CentralPanel, ComboBox, Grid, ScrollArea, Ui};
+use egui_glow::{EguiGlow, glow};
+use glutin::{config::ConfigTemplateBuilder, context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext, PossiblyCurrentGlContext}, display::{Display, DisplayApiPreference, GlDisplay}, surface::{GlSurface, Surface, SurfaceAttributesBuilder, WindowSurface}};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
+use winit::{dpi::LogicalSize, event::WindowEvent, event_loop::ActiveEventLoop, window::{Window, WindowAttributes, WindowId}};
+
+use super::config::{AudioDeviceDriver, AudioInputConfig, AudioOutputConfig, MidiInputConfig};
+use super::settings::StandaloneSettings;
+
+const SAMPLE_RATES: &[u32] = &[44100, 48000, 88200, 96000, 176400, 192000];
+const BUFFER_SIZES: &[u32] = &[32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+/// What the user asked for in the settings window
+pub enum SettingsAction {
+    Apply(StandaloneSettings),
+    /// Save the plugin state to a file, which becomes the current one
+    SaveState(PathBuf),
+}
+
+/// Window for choosing the audio and MIDI settings and saving state files, drawn with egui
+pub struct SettingsWindow {
+    // The GL objects need to be dropped before the window
+    egui_glow: EguiGlow,
+    gl_surface: Surface<WindowSurface>,
+    gl_context: PossiblyCurrentContext,
+    window: Window,
+    ui: SettingsUi,
+}
+
+impl Drop for SettingsWindow {
+    fn drop(&mut self) {
+        // The painter frees its GL objects, which needs the context to be current
+        let _ = self.gl_context.make_current(&self.gl_surface);
+        self.egui_glow.destroy();
+    }
+}
+
+impl SettingsWindow {
+    /// Opens the window for editing `settings`, showing `error` if applying them failed
+    ///
+    /// Fails if there's no way to draw to the window with GL.
+    pub fn open(
+        event_loop: &ActiveEventLoop,
+        title: &str,
+        settings: StandaloneSettings,
+        inputs: SettingsInputs,
+        state_file: Option<PathBuf>,
+        error: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let attributes = WindowAttributes::default()
+            .with_title(format!("{title} Settings"))
+            .with_inner_size(LogicalSize::new(480.0, 480.0));
+
+        let window = event_loop.create_window(attributes)?;
+        let raw_window_handle = window.window_handle()?.as_raw();
+
+        let display = unsafe { Display::new(event_loop.display_handle()?.as_raw(), display_api_preference(raw_window_handle))? };
+        let template = ConfigTemplateBuilder::new()
+            .compatible_with_native_window(raw_window_handle)
+            .build();
+        let gl_config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or("Failed to find a GL config")?;
+
+        // Fall back to GLES if there's no desktop GL
+        let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
+        let fallback_context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(None))
+            .build(Some(raw_window_handle));
+
+        let gl_context = unsafe {
+            display.create_context(&gl_config, &context_attributes)
+                .or_else(|_| display.create_context(&gl_config, &fallback_context_attributes))?
+        };
+
+        let size = window.inner_size();
+        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new()
+            .build(raw_window_handle, non_zero(size.width), non_zero(size.height));
+        let gl_surface = unsafe { display.create_window_surface(&gl_config, &surface_attributes)? };
+        let gl_context = gl_context.make_current(&gl_surface)?;
+
+        let gl = unsafe { glow::Context::from_loader_function_cstr(|name| display.get_proc_address(name)) };
+        let egui_glow = EguiGlow::new(event_loop, Arc::new(gl), None, None, true);
+
+        Ok(Self {
+            egui_glow,
+            gl_surface,
+            gl_context,
+            window,
+            ui: SettingsUi {
+                choices: Choices::scan(&settings, inputs.midi),
+                applied: settings.clone(),
+                settings,
+                inputs,
+                state_path: state_path(state_file.as_ref()),
+                error,
+            },
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn focus(&self) {
+        self.window.focus_window();
+    }
+
+    /// Returns what the user asked for, if anything
+    pub fn window_event(&mut self, event: &WindowEvent) -> Option<SettingsAction> {
+        if let WindowEvent::RedrawRequested = event {
+            return self.redraw();
+        }
+
+        if let WindowEvent::Resized(size) = event {
+            self.gl_surface.resize(&self.gl_context, non_zero(size.width), non_zero(size.height));
+        }
+
+        if self.egui_glow.on_window_event(&self.window, event).repaint {
+            self.window.request_redraw();
+        }
+
+        None
+    }
+
+    /// Updates the window after the runner has tried to apply settings
+    pub fn applied(&mut self, settings: StandaloneSettings, error: Option<String>) {
+        self.ui.applied = settings;
+        self.ui.error = error;
+        self.window.request_redraw();
+    }
+
+    /// Updates the window after a state file was opened or saved
+    pub fn state_file_changed(&mut self, state_file: Option<&PathBuf>) {
+        self.ui.state_path = state_path(state_file);
+        self.ui.error = None;
+        self.window.request_redraw();
+    }
+
+    pub fn save_failed(&mut self, error: String) {
+        self.ui.error = Some(error);
+        self.window.request_redraw();
+    }
+
+    /// Called every frame, for redrawing when egui has something animating
+    pub fn on_frame(&self) {
+        if self.egui_glow.egui_ctx.has_requested_repaint() {
+            self.window.request_redraw();
+        }
+    }
+
+    fn redraw(&mut self) -> Option<SettingsAction> {
+        // The editor might use GL in the same thread
+        if let Err(err) = self.gl_context.make_current(&self.gl_surface) {
+            log::error!("Failed to draw settings window: {err}");
+            return None;
+        }
+
+        let mut action = None;
+        self.egui_glow.run(&self.window, |ctx| action = self.ui.show(ctx));
+
+        self.egui_glow.painter.clear(self.window.inner_size().into(), [0.0, 0.0, 0.0, 1.0]);
+        self.egui_glow.paint(&self.window);
+
+        if let Err(err) = self.gl_surface.swap_buffers(&self.gl_context) {
+            log::error!("Failed to draw settings window: {err}");
+        }
+
+        action
+    }
+}
+
+/// Which inputs the plugin has settings for
+#[derive(Clone, Copy)]
+pub struct SettingsInputs {
+    pub midi: bool,
+    pub aux_audio: bool,
+}
+
+/// Devices and ports to choose from, scanned when the window opens and when the driver changes
+struct Choices {
+    drivers: Vec<AudioDeviceDriver>,
+    output_devices: Vec<(DeviceId, String)>,
+    input_devices: Vec<(DeviceId, String)>,
+    // Channels of the chosen input device
+    input_channels: usize,
+    midi_ports: Vec<String>,
+}
+
+impl Choices {
+    fn scan(settings: &StandaloneSettings, has_midi_input: bool) -> Self {
+        let driver = settings.audio.driver;
+
+        let output_devices = AudioOutputConfig::available_devices(driver).unwrap_or_else(|err| {
+            log::warn!("Failed to list audio output devices: {err}");
+            Vec::new()
+        });
+
+        let input_devices = if settings.input.is_some() {
+            AudioInputConfig::available_devices(driver).unwrap_or_else(|err| {
+                log::warn!("Failed to list audio input devices: {err}");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
+        let input_channels = settings.input.as_ref().map_or(0, channel_count);
+
+        let midi_ports = if has_midi_input {
+            MidiInputConfig::available_ports().unwrap_or_else(|err| {
+                log::warn!("Failed to list MIDI input ports: {err}");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            drivers: AudioOutputConfig::available_drivers(),
+            output_devices,
+            input_devices,
+            input_channels,
+            midi_ports,
+        }
+    }
+}
+
+fn channel_count(input: &AudioInputConfig) -> usize {
+    input.channel_count().unwrap_or_else(|err| {
+        log::warn!("Failed to get the audio input device's channels: {err}");
+        0
+    })
+}
+
+struct SettingsUi {
+    settings: StandaloneSettings,
+    // Settings the app is running with
+    applied: StandaloneSettings,
+    choices: Choices,
+    inputs: SettingsInputs,
+    // Path typed in for saving the state, starts out as the current state file
+    state_path: String,
+    error: Option<String>,
+}
+
+impl SettingsUi {
+    fn show(&mut self, ctx: &egui::Context) -> Option<SettingsAction> {
+        let mut action = None;
+
+        CentralPanel::default().show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                Grid::new("audio").num_columns(2).spacing([16.0, 8.0]).show(ui, |ui| {
+                    self.show_audio_settings(ui);
+                });
+
+                if self.inputs.midi {
+                    ui.separator();
+                    self.show_midi_settings(ui);
+                }
+
+                ui.separator();
+
+                if let Some(error) = self.error.as_ref() {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Rescan").clicked() {
+                        self.choices = Choices::scan(&self.settings, self.inputs.midi);
+                    }
+
+                    if ui.add_enabled(self.settings != self.applied, Button::new("Apply")).clicked() {
+                        action = Some(SettingsAction::Apply(self.settings.clone()));
+                    }
+                });
+
+                ui.separator();
+
+                if let Some(path) = self.show_state_file(ui) {
+                    action = Some(SettingsAction::SaveState(path));
+                }
+            });
+        });
+
+        action
+    }
+
+    /// Returns the path to save the state to when the user saves it
+    fn show_state_file(&mut self, ui: &mut Ui) -> Option<PathBuf> {
+        ui.label("State file");
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.state_path);
+
+            let path = self.state_path.trim();
+            let save = ui.add_enabled(!path.is_empty(), Button::new("Save")).clicked();
+            save.then(|| PathBuf::from(path))
+        }).inner
+    }
+
+    fn show_audio_settings(&mut self, ui: &mut Ui) {
+        let audio = &mut self.settings.audio;
+        let driver = audio.driver;
+
+        ui.label("Audio driver");
+        ComboBox::from_id_salt("driver")
+            .selected_text(driver_label(driver))
+            .show_ui(ui, |ui| {
+                for &choice in &self.choices.drivers {
+                    ui.selectable_value(&mut audio.driver, choice, driver_label(choice));
+                }
+            });
+        ui.end_row();
+
+        // Device ids belong to a driver
+        if audio.driver != driver {
+            audio.device_id = None;
+            if let Some(input) = self.settings.input.as_mut() {
+                input.driver = self.settings.audio.driver;
+                input.device_id = None;
+                input.channels = None;
+                input.aux_channels = None;
+            }
+
+            self.choices = Choices::scan(&self.settings, self.inputs.midi);
+        }
+
+        ui.label("Output device");
+        device_combo(ui, "output-device", &mut self.settings.audio.device_id, &self.choices.output_devices);
+        ui.end_row();
+
+        if let Some(input) = self.settings.input.as_mut() {
+            let device_id = input.device_id.clone();
+
+            ui.label("Input device");
+            device_combo(ui, "input-device", &mut input.device_id, &self.choices.input_devices);
+            ui.end_row();
+
+            // Channels belong to a device
+            if input.device_id != device_id {
+                input.channels = None;
+                input.aux_channels = None;
+                self.choices.input_channels = channel_count(input);
+            }
+        }
+
+        if let Some(input) = self.settings.input.as_mut() {
+            let first_channels = (0..usize::min(self.choices.input_channels, 2)).collect();
+
+            ui.label("Input channels");
+            channels_selection(ui, &mut input.channels, self.choices.input_channels, first_channels);
+            ui.end_row();
+
+            if self.inputs.aux_audio {
+                ui.label("Aux input channels");
+                channels_selection(ui, &mut input.aux_channels, self.choices.input_channels, Vec::new());
+                ui.end_row();
+            }
+        }
+
+        ui.label("Sample rate");
+        option_combo(ui, "sample-rate", &mut self.settings.audio.sample_rate, SAMPLE_RATES, |sample_rate| format!("{sample_rate} Hz"));
+        ui.end_row();
+
+        ui.label("Buffer size");
+        option_combo(ui, "buffer-size", &mut self.settings.audio.buffer_size, BUFFER_SIZES, |buffer_size| format!("{buffer_size} samples"));
+        ui.end_row();
+    }
+
+    fn show_midi_settings(&mut self, ui: &mut Ui) {
+        ui.label("MIDI inputs");
+
+        let mut all_ports = self.settings.midi.port_names.is_none();
+        if ui.checkbox(&mut all_ports, "All ports").changed() {
+            self.settings.midi.port_names = (!all_ports).then(|| self.choices.midi_ports.clone());
+        }
+
+        let Some(port_names) = self.settings.midi.port_names.as_mut() else {
+            return;
+        };
+
+        // Chosen ports that aren't connected are kept, so a device can be plugged in later
+        let missing_ports: Vec<_> = port_names.iter()
+            .filter(|port_name| !self.choices.midi_ports.contains(port_name))
+            .cloned()
+            .collect();
+
+        for port_name in self.choices.midi_ports.iter().chain(missing_ports.iter()) {
+            let mut enabled = port_names.contains(port_name);
+            let label = if missing_ports.contains(port_name) {
+                format!("{port_name} (not connected)")
+            } else {
+                port_name.clone()
+            };
+
+            if ui.checkbox(&mut enabled, label).changed() {
+                if enabled {
+                    port_names.push(port_name.clone());
+                } else {
+                    port_names.retain(|name| name != port_name);
+                }
+            }
+        }
+    }
+}
+
+fn state_path(state_file: Option<&PathBuf>) -> String {
+    state_file.map(|path| path.display().to_string()).unwrap_or_default()
+}
+
+fn driver_label(driver: AudioDeviceDriver) -> &'static str {
+    match driver.name() {
+        "default" => "Default",
+        "asio" => "ASIO",
+        "wasapi" => "WASAPI",
+        "alsa" => "ALSA",
+        "coreaudio" => "Core Audio",
+        "jack" => "JACK",
+        name => name,
+    }
+}
+
+fn device_combo(ui: &mut Ui, id_salt: &str, device_id: &mut Option<DeviceId>, devices: &[(DeviceId, String)]) {
+    let selected_text = match device_id.as_ref() {
+        Some(id) => devices.iter()
+            .find(|(device_id, _)| device_id == id)
+            .map_or_else(|| format!("{id} (not connected)"), |(_, name)| name.clone()),
+        None => "Default".to_string(),
+    };
+
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(device_id, None, "Default");
+            for (id, name) in devices {
+                ui.selectable_value(device_id, Some(id.clone()), name);
+            }
+        });
+}
+
+/// Either the default channels or the chosen ones, `first_channels` are chosen when leaving the default
+fn channels_selection(ui: &mut Ui, channels: &mut Option<Vec<usize>>, channel_count: usize, first_channels: Vec<usize>) {
+    ui.vertical(|ui| {
+        let mut default = channels.is_none();
+        if ui.checkbox(&mut default, "Default").changed() {
+            *channels = (!default).then_some(first_channels);
+        }
+
+        let Some(channels) = channels.as_mut() else {
+            return;
+        };
+
+        // Chosen channels the device doesn't have are kept, like MIDI ports that aren't connected
+        let shown_channels = channels.iter().map(|&channel| channel + 1).fold(channel_count, usize::max);
+
+        ui.horizontal_wrapped(|ui| {
+            for channel in 0..shown_channels {
+                let mut enabled = channels.contains(&channel);
+                let label = if channel < channel_count {
+                    (channel + 1).to_string()
+                } else {
+                    format!("{} (not available)", channel + 1)
+                };
+
+                if ui.checkbox(&mut enabled, label).changed() {
+                    if enabled {
+                        channels.push(channel);
+                        channels.sort_unstable();
+                    } else {
+                        channels.retain(|&chosen| chosen != channel);
+                    }
+                }
+            }
+        });
+    });
+}
+
+fn option_combo(ui: &mut Ui, id_salt: &str, value: &mut Option<u32>, choices: &[u32], label: impl Fn(u32) -> String) {
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(value.map_or_else(|| "Default".to_string(), &label))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Default");
+            for &choice in choices {
+                ui.selectable_value(value, Some(choice), label(choice));
+            }
+        });
+}
+
+/// Native GL first like other apps, with EGL as the fallback where there is one
+#[cfg(target_os = "windows")]
+fn display_api_preference(window: RawWindowHandle) -> DisplayApiPreference {
+    DisplayApiPreference::WglThenEgl(Some(window))
+}
+
+#[cfg(target_os = "macos")]
+fn display_api_preference(_window: RawWindowHandle) -> DisplayApiPreference {
+    DisplayApiPreference::Cgl
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn display_api_preference(_window: RawWindowHandle) -> DisplayApiPreference {
+    DisplayApiPreference::GlxThenEgl(Box::new(winit::platform::x11::register_xlib_error_hook))
+}
+
+fn non_zero(size: u32) -> NonZeroU32 {
+    NonZeroU32::new(size).unwrap_or(NonZeroU32::MIN)
+}
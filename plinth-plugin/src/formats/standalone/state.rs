@@ -0,0 +1,94 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::{Error, Plugin, StateContext};
+use crate::state::{load_plugin_state, save_plugin_state};
+
+/// Where the standalone app keeps the plugin's state between launches
+pub fn session_state_path<P: Plugin>() -> Option<PathBuf> {
+    plugin_config_directory::<P>().map(|directory| directory.join("state"))
+}
+
+/// The plugin's directory in the user's config directory
+pub fn plugin_config_directory<P: Plugin>() -> Option<PathBuf> {
+    config_directory().map(|directory| directory.join(P::VENDOR).join(P::NAME))
+}
+
+/// Saves plugin and editor state to a file
+pub fn save_state_file<P: Plugin>(plugin: &P, path: &Path, context: StateContext) -> Result<(), Error> {
+    // Same layout as CLAP state
+    let mut state = Vec::new();
+    save_plugin_state(plugin, &mut state, context)?;
+
+    replace_file(path, &state)?;
+    Ok(())
+}
+
+/// Loads state saved with [`save_state_file`]
+pub fn load_state_file<P: Plugin>(plugin: &mut P, path: &Path, context: StateContext) -> Result<(), Error> {
+    let state = fs::read(path)?;
+    load_plugin_state(plugin, &mut state.as_slice(), context)
+}
+
+/// Writes a file, replacing an existing one only once the new contents are written
+pub fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path)
+}
+
+fn config_directory() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::home_dir().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        // XDG says relative paths are invalid and should be ignored
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::home_dir().map(|home| home.join(".config")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{formats::PluginFormat, testing::test_plugin::{TestPlugin, GAIN}, Event, FloatParameter, HostInfo, Parameters, Plugin, StateContext};
+
+    use super::{load_state_file, save_state_file};
+
+    fn plugin() -> TestPlugin {
+        TestPlugin::new(HostInfo {
+            name: None,
+            format: PluginFormat::Standalone,
+            bundle_path: None,
+        })
+    }
+
+    #[test]
+    fn state_file_round_trip() {
+        let directory = std::env::temp_dir().join(format!("plinth-state-test-{}", std::process::id()));
+        let path = directory.join("nested").join("test.state");
+
+        let mut plugin = plugin();
+        plugin.process_event(&Event::ParameterValue { sample_offset: 0, id: GAIN, value: 0.25 });
+        save_state_file(&plugin, &path, StateContext::Preset).unwrap();
+
+        // The file is written in place of the temporary one
+        assert!(path.exists());
+        assert!(!path.with_extension("state.tmp").exists());
+
+        let mut loaded = plugin();
+        load_state_file(&mut loaded, &path, StateContext::Preset).unwrap();
+        assert_eq!(loaded.with_parameters(|parameters| parameters.value::<FloatParameter>(GAIN)), 0.25);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
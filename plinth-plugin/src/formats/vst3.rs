@@ -5,10 +5,13 @@ mod factory;
 mod host;
 mod key_codes;
 mod macros;
+#[cfg(feature = "moduleinfo")]
+mod module_info;
 mod parameters;
 mod plugin;
 mod stream;
 mod subcategories;
+mod track_info;
 mod transport;
 mod view;
 
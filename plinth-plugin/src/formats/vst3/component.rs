@@ -1,16 +1,20 @@
 use std::any::TypeId;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ffi::CStr;
 use std::iter::zip;
+use std::path::PathBuf;
 use std::ptr::null_mut;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use atomic_refcell::AtomicRefCell;
 use plinth_core::signals::ptr_signal::{PtrSignal, PtrSignalMut};
 use plinth_core::signals::signal::SignalMut;
 use vst3::Steinberg::Vst::ControllerNumbers_::kPitchBend;
-use vst3::Steinberg::Vst::{CtrlNumber, IMidiMapping, IMidiMappingTrait};
+use vst3::Steinberg::Vst::ChannelContext::{IInfoListener, IInfoListenerTrait};
+use vst3::Steinberg::Vst::IAutomationState_::AutomationStates_;
+use vst3::Steinberg::Vst::{CtrlNumber, IAttributeList, IAutomationState, IAutomationStateTrait, IComponentHandlerTrait, IMidiLearn, IMidiLearnTrait, IMidiMapping, IMidiMappingTrait, IParameterFunctionName, IParameterFunctionNameTrait};
+use vst3::Steinberg::Vst::RestartFlags_::kMidiCCAssignmentChanged;
 use vst3::{ComPtr, ComRef};
 use vst3::Steinberg::{int16, int32, kInvalidArgument, kNoInterface, kResultFalse, kResultOk, kResultTrue, tresult, uint32, FIDString, FUnknown, IBStream, IPlugView, IPluginBaseTrait, TBool, TUID};
 use vst3::Steinberg::Vst::{kInfiniteTail, kNoParentUnitId, kNoProgramListId, kNoTail, BusDirection, BusDirections_, BusInfo, BusInfo_::BusFlags_, BusTypes_, CString, IAudioProcessor, IAudioProcessorTrait, IComponent, IComponentHandler, IComponentTrait, IEditController, IEditController2, IEditController2Trait, IEditControllerTrait, IHostApplication, IHostApplicationTrait, IProcessContextRequirements, IProcessContextRequirementsTrait, IProcessContextRequirements_, IUnitInfo, IUnitInfoTrait, IoMode, IoModes_, KnobMode, MediaType, MediaTypes_, ParamID, ParamValue, ParameterInfo_, ProcessData, ProcessSetup, ProgramListID, ProgramListInfo, RoutingInfo, SpeakerArr, SpeakerArrangement, String128, SymbolicSampleSizes_, TChar, UnitID, UnitInfo, ViewType::kEditor};
@@ -18,13 +22,17 @@ use widestring::U16CStr;
 
 use crate::formats::PluginFormat;
 use crate::host::HostInfo;
+use crate::logging::{self, LogRegistration};
+use crate::midi;
+use crate::rt_check;
+use crate::state;
 use crate::vst3::parameters::parameter_change_to_event;
-use crate::{ParameterId, Parameters, ProcessMode, ProcessState, Processor};
+use crate::{AutomationState, MidiCcAssignment, ParameterId, ParameterRole, Parameters, ProcessMode, ProcessState, Processor, TrackInfo};
 use crate::editor::NoEditor;
 use crate::parameters::{group::{self, ParameterGroupRef}, has_duplicates, info::ParameterInfo};
 use crate::processor::ProcessorConfig;
 use crate::string::{char16_to_string, copy_str_to_char16};
-use crate::vst3::{event::EventIterator, parameters::ParameterChangeIterator};
+use crate::vst3::{event::{EventIterator, MergedEvents}, parameters::ParameterChangeIterator, track_info::convert_track_info};
 
 use super::{plugin::Vst3Plugin, stream::Stream, view::View};
 
@@ -50,30 +58,45 @@ pub struct PluginComponent<P: Vst3Plugin> {
     plugin: Rc<RefCell<Option<P>>>,
 
     parameter_info: RefCell<Vec<ParameterInfo>>,
+    // Sorted, including the pitch bend parameters
+    parameter_ids: RefCell<Vec<ParameterId>>,
     parameter_groups: RefCell<Vec<ParameterGroupRef>>,
     pitch_bend_parameter_ids: RefCell<[ParameterId; 16]>,
+    midi_cc_assignments: RefCell<Vec<MidiCcAssignment>>,
 
     processor_config: RefCell<ProcessorConfig>,
     processing: AtomicBool,
     tail_length: AtomicU32,
     component_handler: Rc<RefCell<Option<ComPtr<IComponentHandler>>>>,
+    track_info: Rc<RefCell<Option<TrackInfo>>>,
+    automation_state: Rc<Cell<AutomationState>>,
+    editor: RefCell<Weak<RefCell<Option<P::Editor>>>>,
+    log_registration: RefCell<Option<LogRegistration>>,
+    bundle_path: Option<PathBuf>,
 
     audio_thread_state: AudioThreadState<P>,
 }
 
 impl<P: Vst3Plugin + 'static> PluginComponent<P> {
-    pub fn new() -> Self {
+    pub fn new(bundle_path: Option<PathBuf>) -> Self {
         Self {
             plugin: Default::default(),
 
             parameter_info: Default::default(),
+            parameter_ids: Default::default(),
             parameter_groups: Default::default(),
             pitch_bend_parameter_ids: Default::default(),
+            midi_cc_assignments: Default::default(),
 
             processor_config: Default::default(),
             processing: AtomicBool::new(false),
             tail_length: AtomicU32::new(0),
             component_handler: Default::default(),
+            track_info: Default::default(),
+            automation_state: Default::default(),
+            editor: Default::default(),
+            log_registration: Default::default(),
+            bundle_path,
 
             audio_thread_state: Default::default(),
         }
@@ -91,7 +114,7 @@ impl<P: Vst3Plugin + 'static> PluginComponent<P> {
 }
 
 impl<P: Vst3Plugin> vst3::Class for PluginComponent<P> {
-    type Interfaces = (IAudioProcessor, IComponent, IComponent, IEditController, IEditController2, IMidiMapping, IProcessContextRequirements, IUnitInfo);
+    type Interfaces = (IAudioProcessor, IAutomationState, IComponent, IComponent, IEditController, IEditController2, IInfoListener, IMidiLearn, IMidiMapping, IParameterFunctionName, IProcessContextRequirements, IUnitInfo);
 }
 
 impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
@@ -102,6 +125,12 @@ impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
             return kResultOk;
         }
 
+        // VST3 has no host logging interface
+        // Logging is best effort, another global logger might already be installed
+        if let Some(level) = P::LOG_LEVEL {
+            *self.log_registration.borrow_mut() = logging::register_file_sink(level, P::VENDOR, P::NAME).ok();
+        }
+
         // Get plugin name if available
         let mut host_name = None;
 
@@ -117,6 +146,7 @@ impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
         let host_info = HostInfo {
             name: host_name,
             format: PluginFormat::Vst3,
+            bundle_path: self.bundle_path.clone(),
         };
 
         let mut plugin = P::new(host_info);
@@ -145,6 +175,13 @@ impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
             group::from_parameters(parameters)
         });
 
+        let midi_cc_assignments = plugin.midi_cc_assignments();
+        assert!(
+            midi_cc_assignments.iter().all(|assignment| parameter_infos.iter().any(|info| info.id() == assignment.parameter_id)),
+            "MIDI CCs can only be assigned to existing parameters"
+        );
+        *self.midi_cc_assignments.borrow_mut() = midi_cc_assignments;
+
         // Create parameters for MIDI pitch bend messages
         plugin.with_parameters(|parameters| {
             let mut parameter_id = 1;
@@ -165,6 +202,10 @@ impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
             }
         });
 
+        let mut parameter_ids: Vec<_> = parameter_infos.iter().map(|info| info.id()).collect();
+        parameter_ids.sort_unstable();
+        *self.parameter_ids.borrow_mut() = parameter_ids;
+
         *self.plugin.borrow_mut() = Some(plugin);
 
         kResultOk
@@ -176,6 +217,7 @@ impl<P: Vst3Plugin> IPluginBaseTrait for PluginComponent<P> {
         *self.plugin.borrow_mut() = None;
         self.parameter_info.borrow_mut().clear();
         self.parameter_groups.borrow_mut().clear();
+        *self.log_registration.borrow_mut() = None;
 
         kResultOk
     }
@@ -269,9 +311,10 @@ impl<P: Vst3Plugin> IAudioProcessorTrait for PluginComponent<P> {
     unsafe fn process(&self, data: *mut ProcessData) -> tresult {
         let data = unsafe { &mut *data };
 
-        let parameter_change_iterator = ParameterChangeIterator::new(data.inputParameterChanges, *self.pitch_bend_parameter_ids.borrow());
+        let parameter_ids = self.parameter_ids.borrow();
+        let parameter_change_iterator = ParameterChangeIterator::new(data.inputParameterChanges, &parameter_ids, *self.pitch_bend_parameter_ids.borrow());
         let event_iterator = EventIterator::new(data.inputEvents);
-        let all_events = event_iterator.chain(parameter_change_iterator);
+        let all_events = MergedEvents::new(event_iterator, parameter_change_iterator);
 
         let mut processor = self.audio_thread_state.processor.borrow_mut();
         let Some(processor) = processor.as_mut() else {
@@ -282,7 +325,7 @@ impl<P: Vst3Plugin> IAudioProcessorTrait for PluginComponent<P> {
 
         // Empty input: this is a parameter dump
         if data.inputs.is_null() || data.outputs.is_null() || data.numInputs == 0 || data.numSamples == 0 {
-            processor.process_events(all_events);
+            rt_check::realtime(|| processor.process_events(all_events));
             return kResultOk;
         }
 
@@ -322,7 +365,7 @@ impl<P: Vst3Plugin> IAudioProcessorTrait for PluginComponent<P> {
             Some(unsafe { &*data.processContext }.into())
         };
 
-        let process_state = processor.process(&mut main_output, aux_input.as_ref(), transport, all_events);
+        let process_state = rt_check::realtime(|| processor.process(&mut main_output, aux_input.as_ref(), transport, all_events));
 
         let tail_length = match process_state {
             ProcessState::Error => {
@@ -466,7 +509,9 @@ impl<P: Vst3Plugin> IComponentTrait for PluginComponent<P> {
             return kResultFalse;
         };
 
-        match plugin.load_state(&mut stream) {
+        let context = stream.state_context();
+
+        match plugin.load_state(&mut stream, context) {
             Ok(_) => kResultOk,
             Err(_) => kResultFalse, // TODO: Extract actual error code
         }
@@ -483,7 +528,9 @@ impl<P: Vst3Plugin> IComponentTrait for PluginComponent<P> {
             return kResultFalse;
         };
 
-        match plugin.save_state(&mut stream) {
+        let context = stream.state_context();
+
+        match plugin.save_state(&mut stream, context) {
             Ok(_) => kResultOk,
             Err(_) => kResultFalse, // TODO: Extract actual error code
         }
@@ -491,19 +538,58 @@ impl<P: Vst3Plugin> IComponentTrait for PluginComponent<P> {
 }
 
 impl<P: Vst3Plugin + 'static> IEditControllerTrait for PluginComponent<P> {
-    unsafe fn setComponentState(&self, _state: *mut IBStream) -> tresult {
+    unsafe fn setComponentState(&self, state: *mut IBStream) -> tresult {
         log::trace!("IEditController::setComponentState");
+
+        // The component and controller are the same object, so IComponent::setState has already loaded
+        // the parameter values, and the host reads them back with getParamNormalized()
+        if state.is_null() || self.plugin.borrow().is_none() {
+            return kResultFalse;
+        }
+
         kResultOk
     }
 
-    unsafe fn setState(&self, _state: *mut IBStream) -> tresult {
+    unsafe fn setState(&self, state: *mut IBStream) -> tresult {
         log::trace!("IEditController::setState");
+
+        let mut plugin = self.plugin.borrow_mut();
+        let Some(plugin) = plugin.as_mut() else {
+            return kResultFalse;
+        };
+
+        let Some(mut stream) = Stream::new(state) else {
+            return kResultFalse;
+        };
+
+        if state::load_editor_state(plugin, &mut stream).is_err() {
+            return kResultFalse;
+        }
+
+        // Learned controllers may have changed
+        if plugin.midi_learn().is_some() && let Some(handler) = self.component_handler.borrow_mut().as_mut() {
+            unsafe { handler.restartComponent(kMidiCCAssignmentChanged as _) };
+        }
+
         kResultOk
     }
 
-    unsafe fn getState(&self, _state: *mut IBStream) -> tresult {
+    unsafe fn getState(&self, state: *mut IBStream) -> tresult {
         log::trace!("IEditController::getState");
-        kResultOk
+
+        let plugin = self.plugin.borrow();
+        let Some(plugin) = plugin.as_ref() else {
+            return kResultFalse;
+        };
+
+        let Some(mut stream) = Stream::new(state) else {
+            return kResultFalse;
+        };
+
+        match state::save_editor_state(plugin, &mut stream) {
+            Ok(_) => kResultOk,
+            Err(_) => kResultFalse,
+        }
     }
 
     unsafe fn getParameterCount(&self) -> int32 {
@@ -527,19 +613,34 @@ impl<P: Vst3Plugin + 'static> IEditControllerTrait for PluginComponent<P> {
 
         vst3_info.id = parameter_info.id();
         copy_str_to_char16(parameter_info.name(), &mut vst3_info.title);
-        // TODO: info.shortTitle
+        copy_str_to_char16(parameter_info.short_name(), &mut vst3_info.shortTitle);
+        copy_str_to_char16(parameter_info.unit(), &mut vst3_info.units);
         vst3_info.stepCount = parameter_info.steps() as _;
         vst3_info.defaultNormalizedValue = parameter_info.default_normalized_value();
         vst3_info.unitId = self.parameter_group_id(parameter_info);
 
+        // On some platforms, these casts are needed
         #[allow(clippy::unnecessary_cast)]
-        if parameter_info.is_bypass() {
-            vst3_info.flags = ParameterInfo_::ParameterFlags_::kIsBypass as i32;
-            vst3_info.flags |= ParameterInfo_::ParameterFlags_::kCanAutomate as i32;
-        } else if !parameter_info.visible() {
-            vst3_info.flags = ParameterInfo_::ParameterFlags_::kIsHidden as i32;
-        } else {
-            vst3_info.flags = ParameterInfo_::ParameterFlags_::kCanAutomate as i32;
+        {
+            vst3_info.flags = 0;
+
+            if parameter_info.is_bypass() {
+                vst3_info.flags |= ParameterInfo_::ParameterFlags_::kIsBypass as i32;
+            }
+
+            if parameter_info.is_read_only() {
+                vst3_info.flags |= ParameterInfo_::ParameterFlags_::kIsReadOnly as i32;
+            } else if parameter_info.visible() || parameter_info.is_bypass() {
+                vst3_info.flags |= ParameterInfo_::ParameterFlags_::kCanAutomate as i32;
+            }
+
+            if !parameter_info.visible() {
+                vst3_info.flags |= ParameterInfo_::ParameterFlags_::kIsHidden as i32;
+            }
+
+            if parameter_info.is_list() {
+                vst3_info.flags |= ParameterInfo_::ParameterFlags_::kIsList as i32;
+            }
         }
 
         kResultOk
@@ -558,7 +659,8 @@ impl<P: Vst3Plugin + 'static> IEditControllerTrait for PluginComponent<P> {
                 return kInvalidArgument;
             };
 
-            let formatted = parameter.normalized_to_string(value_normalized);
+            // Hosts may ask for values slightly out of range, format them like the value they'll be set to
+            let formatted = parameter.normalized_to_string(value_normalized.clamp(0.0, 1.0));
             copy_str_to_char16(&formatted, unsafe { &mut *string });
 
             kResultOk
@@ -621,6 +723,10 @@ impl<P: Vst3Plugin + 'static> IEditControllerTrait for PluginComponent<P> {
     }
 
     unsafe fn setParamNormalized(&self, id: ParamID, value: ParamValue) -> tresult {
+        if self.parameter_ids.borrow().binary_search(&id).is_err() || !value.is_finite() {
+            return kInvalidArgument;
+        }
+
         let mut plugin = self.plugin.borrow_mut();
         let Some(plugin) = plugin.as_mut() else {
             return kResultFalse;
@@ -666,8 +772,12 @@ impl<P: Vst3Plugin + 'static> IEditControllerTrait for PluginComponent<P> {
         let view = View::<P>::new(
             self.plugin.clone(),
             self.component_handler.clone(),
+            self.track_info.clone(),
+            self.automation_state.clone(),
         );
 
+        *self.editor.borrow_mut() = Rc::downgrade(view.editor());
+
         view.to_com_ptr::<IPlugView>().unwrap().into_raw()
     }
 }
@@ -689,6 +799,57 @@ impl<P: Vst3Plugin> IEditController2Trait for PluginComponent<P> {
     }
 }
 
+impl<P: Vst3Plugin> IAutomationStateTrait for PluginComponent<P> {
+    unsafe fn setAutomationState(&self, state: int32) -> tresult {
+        log::trace!("IAutomationState::setAutomationState: {state}");
+
+        #[allow(clippy::unnecessary_cast)]
+        let automation_state = if state & AutomationStates_::kWriteState as int32 != 0 {
+            AutomationState::Recording
+        } else if state & AutomationStates_::kReadState as int32 != 0 {
+            AutomationState::Playing
+        } else {
+            AutomationState::None
+        };
+
+        self.automation_state.set(automation_state);
+
+        kResultOk
+    }
+}
+
+impl<P: Vst3Plugin> IInfoListenerTrait for PluginComponent<P> {
+    unsafe fn setChannelContextInfos(&self, list: *mut IAttributeList) -> tresult {
+        log::trace!("IInfoListener::setChannelContextInfos");
+
+        let Some(list) = (unsafe { ComRef::from_raw(list) }) else {
+            return kInvalidArgument;
+        };
+
+        let track_info = convert_track_info(list);
+
+        {
+            let mut plugin = self.plugin.borrow_mut();
+            let Some(plugin) = plugin.as_mut() else {
+                return kResultFalse;
+            };
+
+            plugin.track_info_changed(&track_info);
+        }
+
+        *self.track_info.borrow_mut() = Some(track_info.clone());
+
+        if let Some(editor) = self.editor.borrow().upgrade()
+            && let Ok(editor) = editor.try_borrow()
+            && let Some(editor) = editor.as_ref()
+        {
+            editor.track_info_changed(&track_info);
+        }
+
+        kResultOk
+    }
+}
+
 impl<P: Vst3Plugin> IMidiMappingTrait for PluginComponent<P> {
     unsafe fn getMidiControllerAssignment(
         &self,
@@ -700,19 +861,99 @@ impl<P: Vst3Plugin> IMidiMappingTrait for PluginComponent<P> {
         if bus_index != 0 {
             return kResultFalse;
         }
-        if midi_controller_number != kPitchBend as i16 {
-            return kResultFalse;
-        }
         if !(0..16).contains(&channel) {
             return kInvalidArgument;
         }
 
-        unsafe { *id = self.pitch_bend_parameter_ids.borrow()[channel as usize] as _ };
+        let parameter_id = if midi_controller_number == kPitchBend as i16 {
+            self.pitch_bend_parameter_ids.borrow()[channel as usize]
+        } else if let Some(parameter_id) = self.plugin.borrow()
+            .as_ref()
+            .and_then(|plugin| plugin.midi_learn()?.assigned_parameter(channel, midi_controller_number))
+        {
+            // Learned controllers override the default assignments
+            parameter_id
+        } else if let Some(parameter_id) = midi::assigned_parameter(&self.midi_cc_assignments.borrow(), channel, midi_controller_number) {
+            parameter_id
+        } else {
+            return kResultFalse;
+        };
+
+        unsafe { *id = parameter_id as _ };
 
         kResultTrue
     }
 }
 
+impl<P: Vst3Plugin> IMidiLearnTrait for PluginComponent<P> {
+    unsafe fn onLiveMIDIControllerInput(&self, bus_index: int32, channel: int16, midi_cc: CtrlNumber) -> tresult {
+        log::trace!("IMidiLearn::onLiveMIDIControllerInput");
+
+        if bus_index != 0 {
+            return kResultFalse;
+        }
+
+        let learned = self.plugin.borrow()
+            .as_ref()
+            .and_then(|plugin| plugin.midi_learn())
+            .is_some_and(|midi_learn| midi_learn.learn(channel, midi_cc));
+
+        if !learned {
+            return kResultFalse;
+        }
+
+        // The host has to query the assignments again to route the learned controller to its parameter
+        if let Some(handler) = self.component_handler.borrow_mut().as_mut() {
+            unsafe { handler.restartComponent(kMidiCCAssignmentChanged as _) };
+        }
+
+        kResultOk
+    }
+}
+
+impl<P: Vst3Plugin + 'static> IParameterFunctionNameTrait for PluginComponent<P> {
+    unsafe fn getParameterIDFromFunctionName(&self, unit_id: UnitID, function_name: FIDString, param_id: *mut ParamID) -> tresult {
+        log::trace!("IParameterFunctionName::getParameterIDFromFunctionName");
+
+        if function_name.is_null() {
+            return kInvalidArgument;
+        }
+
+        let function_name = unsafe { CStr::from_ptr(function_name) };
+        let Some(role) = parameter_role_from_function_name(function_name) else {
+            return kResultFalse;
+        };
+
+        let parameter_info = self.parameter_info.borrow();
+        let parameter_info = parameter_info.iter()
+            .filter(|info| info.role() == Some(role))
+            .find(|info| unit_id == ROOT_UNIT_ID || self.parameter_group_id(info) == unit_id);
+
+        let Some(parameter_info) = parameter_info else {
+            return kResultFalse;
+        };
+
+        unsafe { *param_id = parameter_info.id() };
+
+        kResultOk
+    }
+}
+
+// From ivstparameterfunctionname.h
+fn parameter_role_from_function_name(function_name: &CStr) -> Option<ParameterRole> {
+    if function_name == c"DryWetMix" {
+        Some(ParameterRole::DryWetMix)
+    } else if function_name == c"LowLatency" {
+        Some(ParameterRole::LowLatency)
+    } else if function_name == c"Randomize" {
+        Some(ParameterRole::Randomize)
+    } else if function_name == c"Comp:GainReduction" {
+        Some(ParameterRole::GainReduction)
+    } else {
+        None
+    }
+}
+
 impl<P: Vst3Plugin> IProcessContextRequirementsTrait for PluginComponent<P> {
     unsafe fn getProcessContextRequirements(&self) -> uint32 {
         log::trace!("IProcessContextRequirements::getProcessContextRequirements");
@@ -1,4 +1,4 @@
-use std::mem;
+use std::{iter::Peekable, mem};
 
 use vst3::{ComRef, Steinberg::{kResultOk, Vst::{self, IEventList, IEventListTrait}}};
 
@@ -24,40 +24,139 @@ impl Iterator for EventIterator<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         let event_list = self.event_list?;
 
-        if self.index >= unsafe { event_list.getEventCount() } as usize {
-            return None;
+        loop {
+            if self.index >= unsafe { event_list.getEventCount() }.max(0) as usize {
+                return None;
+            }
+
+            let mut event: vst3::Steinberg::Vst::Event = unsafe { mem::zeroed() };
+            let result = unsafe { event_list.getEvent(self.index as _, &mut event) };
+            if result != kResultOk {
+                return None;
+            }
+
+            self.index += 1;
+
+            // Negative offsets from buggy hosts are moved to the start of the block
+            let sample_offset = event.sampleOffset.max(0) as _;
+
+            match event.r#type as _ {
+                Vst::Event_::EventTypes_::kNoteOnEvent => unsafe {
+                    return Some(Event::NoteOn {
+                        sample_offset,
+                        channel: event.__field0.noteOn.channel,
+                        key: event.__field0.noteOn.pitch,
+                        note: event.__field0.noteOn.noteId,
+                        velocity: event.__field0.noteOn.velocity as _,
+                    });
+                },
+
+                Vst::Event_::EventTypes_::kNoteOffEvent => unsafe {
+                    return Some(Event::NoteOff {
+                        sample_offset,
+                        channel: event.__field0.noteOff.channel,
+                        key: event.__field0.noteOff.pitch,
+                        note: event.__field0.noteOff.noteId,
+                        velocity: event.__field0.noteOff.velocity as _,
+                    });
+                },
+
+                // Skip event types we don't handle instead of ending the iteration
+                _ => {},
+            }
         }
+    }
+}
 
-        let mut event: vst3::Steinberg::Vst::Event = unsafe { mem::zeroed() };
-        let result = unsafe { event_list.getEvent(self.index as _, &mut event) };
-        if result != kResultOk {
-            return None;
+/// Merges note events and parameter changes, which VST3 delivers separately, by sample offset
+///
+/// Parameter changes go first at equal offsets, so a note sees the parameter values of its own sample.
+pub struct MergedEvents<N: Iterator<Item = Event>, P: Iterator<Item = Event>> {
+    notes: Peekable<N>,
+    parameters: Peekable<P>,
+}
+
+impl<N: Iterator<Item = Event>, P: Iterator<Item = Event>> MergedEvents<N, P> {
+    pub fn new(notes: N, parameters: P) -> Self {
+        Self {
+            notes: notes.peekable(),
+            parameters: parameters.peekable(),
         }
+    }
+}
+
+impl<N: Iterator<Item = Event>, P: Iterator<Item = Event>> Iterator for MergedEvents<N, P> {
+    type Item = Event;
 
-        self.index += 1;
-
-        match event.r#type as _ {
-            Vst::Event_::EventTypes_::kNoteOnEvent => unsafe {
-                Some(Event::NoteOn {
-                    sample_offset: event.sampleOffset as _,
-                    channel: event.__field0.noteOn.channel,
-                    key: event.__field0.noteOn.pitch,
-                    note: event.__field0.noteOn.noteId,
-                    velocity: event.__field0.noteOn.velocity as _,
-                })
-            },
-
-            Vst::Event_::EventTypes_::kNoteOffEvent => unsafe {
-                Some(Event::NoteOff {
-                    sample_offset: event.sampleOffset as _,
-                    channel: event.__field0.noteOff.channel,
-                    key: event.__field0.noteOff.pitch,
-                    note: event.__field0.noteOn.noteId,
-                    velocity: event.__field0.noteOff.velocity as _,
-                })
-            },
-
-            _ => None
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.notes.peek(), self.parameters.peek()) {
+            (Some(note), Some(parameter)) if note.sample_offset() < parameter.sample_offset() => self.notes.next(),
+            (_, Some(_)) => self.parameters.next(),
+            (_, None) => self.notes.next(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use vst3::{ComWrapper, Steinberg::Vst::{Event_::EventTypes_, IEventList, NoteOffEvent}};
+
+    use crate::{testing::vst3::EventList, Event};
+
+    use super::{EventIterator, MergedEvents};
+
+    #[test]
+    fn unknown_events_are_skipped() {
+        let event_list = ComWrapper::new(EventList::default());
+
+        let mut unknown_event: vst3::Steinberg::Vst::Event = unsafe { std::mem::zeroed() };
+        unknown_event.r#type = EventTypes_::kDataEvent as _;
+
+        let mut note_off: vst3::Steinberg::Vst::Event = unsafe { std::mem::zeroed() };
+        note_off.sampleOffset = 8;
+        note_off.r#type = EventTypes_::kNoteOffEvent as _;
+        note_off.__field0.noteOff = NoteOffEvent {
+            channel: 1,
+            pitch: 60,
+            velocity: 0.5,
+            noteId: 42,
+            tuning: 0.0,
+        };
+
+        event_list.events.borrow_mut().extend([unknown_event, note_off]);
+
+        let raw = event_list.to_com_ptr::<IEventList>().unwrap();
+        let events: Vec<_> = EventIterator::new(raw.as_ptr()).collect();
+
+        // The note off has to come through after the unknown event, with its own note id
+        assert!(matches!(
+            events[..],
+            [Event::NoteOff { sample_offset: 8, channel: 1, key: 60, note: 42, .. }]
+        ));
+    }
+
+    #[test]
+    fn events_are_merged_by_sample_offset() {
+        let notes = [
+            Event::NoteOn { sample_offset: 0, channel: 0, key: 60, note: 1, velocity: 1.0 },
+            Event::NoteOff { sample_offset: 10, channel: 0, key: 60, note: 1, velocity: 0.0 },
+        ];
+        let parameters = [
+            Event::ParameterValue { sample_offset: 5, id: 1, value: 0.5 },
+            Event::ParameterValue { sample_offset: 10, id: 1, value: 0.25 },
+        ];
+
+        let events: Vec<_> = MergedEvents::new(notes.into_iter(), parameters.into_iter()).collect();
+
+        // At equal offsets the parameter change comes before the note
+        assert!(matches!(
+            events[..],
+            [
+                Event::NoteOn { sample_offset: 0, .. },
+                Event::ParameterValue { sample_offset: 5, .. },
+                Event::ParameterValue { sample_offset: 10, .. },
+                Event::NoteOff { sample_offset: 10, .. },
+            ]
+        ));
+    }
+}
@@ -1,50 +1,116 @@
-use std::{ffi::{c_void, CStr}, marker::PhantomData};
+use std::ffi::{c_void, CStr};
+use std::path::PathBuf;
 
 use vst3::{ComWrapper, Steinberg::{int32, kInvalidArgument, kResultOk, tresult, FIDString, FUnknown, IPluginFactory, IPluginFactory2, IPluginFactory2Trait, IPluginFactory3, IPluginFactory3Trait, IPluginFactoryTrait, PClassInfo, PClassInfo2, PClassInfoW, PClassInfo_::ClassCardinality_::kManyInstances, PFactoryInfo, PFactoryInfo_, Vst::SDKVersionString, TUID}};
 
+use crate::bundle;
 use crate::string::{copy_str_to_char16, copy_str_to_char8, copy_u128_to_char8};
 
 use super::{plugin::Vst3Plugin, component::PluginComponent};
 
-pub struct Factory<P: Vst3Plugin> {
-    _phantom_plugin: PhantomData<P>,
+type CreateInstanceFn = unsafe fn(iid: FIDString, obj: *mut *mut c_void, bundle_path: Option<PathBuf>) -> tresult;
+
+pub(super) struct FactoryClass {
+    pub(super) class_id: u128,
+    pub(super) name: &'static str,
+    pub(super) vendor: &'static str,
+    pub(super) version: &'static str,
+    pub(super) subcategories: String,
+    create_instance: CreateInstanceFn,
 }
 
-impl<P: Vst3Plugin + 'static> Factory<P> {
-    // This is a bit special
-    #[allow(clippy::new_ret_no_self)]
-    pub fn new() -> *mut IPluginFactory {
-        let factory = Self {
-            _phantom_plugin: PhantomData,
-        };
+/// Plugin factory exposing one or more plugin classes
+///
+/// Factory information (vendor, URL and email) is taken from the first plugin.
+#[derive(Default)]
+pub struct Factory {
+    pub(super) vendor: &'static str,
+    pub(super) url: Option<&'static str>,
+    pub(super) email: Option<&'static str>,
+
+    pub(super) classes: Vec<FactoryClass>,
+
+    // Resolved once when the factory is handed to the host, instead of for every instance
+    bundle_path: Option<PathBuf>,
+}
+
+impl Factory {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        ComWrapper::new(factory)
+    pub fn with_plugin<P: Vst3Plugin + 'static>(mut self) -> Self {
+        if self.classes.is_empty() {
+            self.vendor = P::VENDOR;
+            self.url = P::URL;
+            self.email = P::EMAIL;
+        }
+
+        assert!(
+            self.classes.iter().all(|class| class.class_id != P::CLASS_ID),
+            "Duplicate VST3 class id for plugin {}", P::NAME,
+        );
+
+        self.classes.push(FactoryClass {
+            class_id: P::CLASS_ID,
+            name: P::NAME,
+            vendor: P::VENDOR,
+            version: P::VERSION,
+            subcategories: P::SUBCATEGORIES
+                .iter()
+                .map(|subcategory| subcategory.to_str())
+                .collect::<Vec<_>>()
+                .join("|"),
+            create_instance: create_instance::<P>,
+        });
+
+        self
+    }
+
+    pub fn into_raw(mut self) -> *mut IPluginFactory {
+        self.bundle_path = bundle::current_bundle_path("vst3");
+
+        ComWrapper::new(self)
             .to_com_ptr::<IPluginFactory>()
             .unwrap()
             .into_raw() as _
     }
+
+    fn class(&self, index: int32) -> Option<&FactoryClass> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.classes.get(index))
+    }
 }
 
-impl<P: Vst3Plugin> vst3::Class for Factory<P> {
+unsafe fn create_instance<P: Vst3Plugin + 'static>(iid: FIDString, obj: *mut *mut c_void, bundle_path: Option<PathBuf>) -> tresult {
+    let instance = ComWrapper::new(PluginComponent::<P>::new(bundle_path));
+    let unknown = instance.as_com_ref::<FUnknown>().unwrap();
+    let ptr = unknown.as_ptr();
+
+    unsafe { ((*(*ptr).vtbl).queryInterface)(ptr, iid as *const TUID, obj) }
+}
+
+impl vst3::Class for Factory {
     type Interfaces = (IPluginFactory, IPluginFactory2, IPluginFactory3);
 }
 
 #[allow(non_snake_case)]
-impl<P: Vst3Plugin + 'static> IPluginFactoryTrait for Factory<P> {
+impl IPluginFactoryTrait for Factory {
     unsafe fn getFactoryInfo(&self, info: *mut PFactoryInfo) -> tresult {
         let mut local_info: PFactoryInfo = unsafe { std::mem::zeroed() };
 
         local_info.flags = PFactoryInfo_::FactoryFlags_::kUnicode as _;
 
-        copy_str_to_char8(P::VENDOR, &mut local_info.vendor);
+        copy_str_to_char8(self.vendor, &mut local_info.vendor);
 
-        if let Some(url) = P::URL {
+        if let Some(url) = self.url {
             copy_str_to_char8(url, &mut local_info.url);
         } else {
             local_info.url.fill(0);
         }
 
-        if let Some(email) = P::EMAIL {
+        if let Some(email) = self.email {
             copy_str_to_char8(email, &mut local_info.email);
         } else {
             local_info.email.fill(0);
@@ -57,19 +123,19 @@ impl<P: Vst3Plugin + 'static> IPluginFactoryTrait for Factory<P> {
     }
 
     unsafe fn countClasses(&self) -> int32 {
-        1
+        self.classes.len() as _
     }
 
     unsafe fn getClassInfo(&self, index: int32, info: *mut PClassInfo) -> tresult {
-        if index != 0 {
+        let Some(class) = self.class(index) else {
             return kInvalidArgument;
-        }
+        };
 
         let mut local_info: PClassInfo = unsafe { std::mem::zeroed() };
         local_info.cardinality = kManyInstances as _;
 
-        copy_u128_to_char8(&P::CLASS_ID, &mut local_info.cid);
-        copy_str_to_char8(P::NAME, &mut local_info.name);
+        copy_u128_to_char8(&class.class_id, &mut local_info.cid);
+        copy_str_to_char8(class.name, &mut local_info.name);
 
         copy_str_to_char8("Audio Module Class", &mut local_info.category);
 
@@ -88,42 +154,31 @@ impl<P: Vst3Plugin + 'static> IPluginFactoryTrait for Factory<P> {
         let bytes_array: [u8; 16] = std::array::from_fn(|i| bytes[i] as u8);
         let cid = u128::from_be_bytes(bytes_array);
 
-        if cid == P::CLASS_ID {
-            let instance = ComWrapper::new(PluginComponent::<P>::new());
-            let unknown = instance.as_com_ref::<FUnknown>().unwrap();
-            let ptr = unknown.as_ptr();
-
-            unsafe { ((*(*ptr).vtbl).queryInterface)(ptr, iid as *const TUID, obj) }
-        } else {
-            kInvalidArgument
+        match self.classes.iter().find(|class| class.class_id == cid) {
+            Some(class) => unsafe { (class.create_instance)(iid, obj, self.bundle_path.clone()) },
+            None => kInvalidArgument,
         }
     }
 }
 
 #[allow(non_snake_case)]
-impl<P: Vst3Plugin + 'static> IPluginFactory2Trait for Factory<P> {
+impl IPluginFactory2Trait for Factory {
     unsafe fn getClassInfo2(&self, index: int32, info: *mut PClassInfo2) -> tresult {
-        if index != 0 {
+        let Some(class) = self.class(index) else {
             return kInvalidArgument;
-        }
+        };
 
         let mut local_info: PClassInfo2 = unsafe { std::mem::zeroed() };
         local_info.cardinality = kManyInstances as _;
 
-        copy_u128_to_char8(&P::CLASS_ID, &mut local_info.cid);
-        copy_str_to_char8(P::NAME, &mut local_info.name);
-        copy_str_to_char8(P::VENDOR, &mut local_info.vendor);
-        copy_str_to_char8(P::VERSION, &mut local_info.version);
+        copy_u128_to_char8(&class.class_id, &mut local_info.cid);
+        copy_str_to_char8(class.name, &mut local_info.name);
+        copy_str_to_char8(class.vendor, &mut local_info.vendor);
+        copy_str_to_char8(class.version, &mut local_info.version);
 
         copy_str_to_char8("Audio Module Class", &mut local_info.category);
         copy_str_to_char8(unsafe { CStr::from_ptr(SDKVersionString).to_str().unwrap() }, &mut local_info.sdkVersion);
-
-        let subcategory_string = P::SUBCATEGORIES
-            .iter()
-            .map(|subcategory| subcategory.to_str())
-            .collect::<Vec<_>>()
-            .join("|");
-        copy_str_to_char8(&subcategory_string, &mut local_info.subCategories);
+        copy_str_to_char8(&class.subcategories, &mut local_info.subCategories);
 
         // We have to do a workaround like this for FL Studio which is giving us unaligned addresses
         unsafe { std::ptr::write_unaligned(info, local_info) };
@@ -133,29 +188,23 @@ impl<P: Vst3Plugin + 'static> IPluginFactory2Trait for Factory<P> {
 }
 
 #[allow(non_snake_case)]
-impl<P: Vst3Plugin + 'static> IPluginFactory3Trait for Factory<P> {
+impl IPluginFactory3Trait for Factory {
     unsafe fn getClassInfoUnicode(&self, index: int32, info: *mut PClassInfoW) -> tresult {
-        if index != 0 {
+        let Some(class) = self.class(index) else {
             return kInvalidArgument;
-        }
+        };
 
         let mut local_info: PClassInfoW = unsafe { std::mem::zeroed() };
         local_info.cardinality = kManyInstances as _;
 
-        copy_u128_to_char8(&P::CLASS_ID, &mut local_info.cid);
-        copy_str_to_char16(P::NAME, &mut local_info.name);
-        copy_str_to_char16(P::VENDOR, &mut local_info.vendor);
-        copy_str_to_char16(P::VERSION, &mut local_info.version);
+        copy_u128_to_char8(&class.class_id, &mut local_info.cid);
+        copy_str_to_char16(class.name, &mut local_info.name);
+        copy_str_to_char16(class.vendor, &mut local_info.vendor);
+        copy_str_to_char16(class.version, &mut local_info.version);
 
         copy_str_to_char8("Audio Module Class", &mut local_info.category);
         copy_str_to_char16(unsafe { CStr::from_ptr(SDKVersionString).to_str().unwrap() }, &mut local_info.sdkVersion);
-
-        let subcategory_string = P::SUBCATEGORIES
-            .iter()
-            .map(|subcategory| subcategory.to_str())
-            .collect::<Vec<_>>()
-            .join("|");
-        copy_str_to_char8(&subcategory_string, &mut local_info.subCategories);
+        copy_str_to_char8(&class.subcategories, &mut local_info.subCategories);
 
         // We have to do a workaround like this for FL Studio which is giving us unaligned addresses
         unsafe { std::ptr::write_unaligned(info, local_info) };
@@ -1,8 +1,8 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::{Cell, RefCell}, rc::Rc};
 
 use vst3::{ComPtr, Steinberg::{kResultOk, IPlugFrameTrait, IPlugView, ViewRect, Vst::{IComponentHandler, IComponentHandler2, IComponentHandler2Trait, IComponentHandlerTrait, RestartFlags_::kParamValuesChanged}}};
 
-use crate::{host::Host, parameters::ParameterValue, ParameterId, Parameters, Plugin};
+use crate::{host::Host, parameters::ParameterValue, AutomationState, ParameterId, ParameterIndication, Parameters, Plugin, TrackInfo};
 
 use super::view::ViewContext;
 
@@ -11,6 +11,8 @@ pub struct Vst3Host<P: Plugin> {
     component_handler: Rc<RefCell<Option<ComPtr<IComponentHandler>>>>,
     plug_view: ComPtr<IPlugView>,
     view_context: Rc<RefCell<ViewContext>>,
+    track_info: Rc<RefCell<Option<TrackInfo>>>,
+    automation_state: Rc<Cell<AutomationState>>,
 }
 
 impl<P: Plugin> Vst3Host<P> {
@@ -19,12 +21,16 @@ impl<P: Plugin> Vst3Host<P> {
         handler: Rc<RefCell<Option<ComPtr<IComponentHandler>>>>,
         plug_view: ComPtr<IPlugView>,
         view_context: Rc<RefCell<ViewContext>>,
+        track_info: Rc<RefCell<Option<TrackInfo>>>,
+        automation_state: Rc<Cell<AutomationState>>,
     ) -> Self {
         Self {
             plugin,
             component_handler: handler,
             plug_view,
             view_context,
+            track_info,
+            automation_state,
         }
     }
 }
@@ -94,4 +100,15 @@ impl<P: Plugin> Host for Vst3Host<P> {
             unsafe { handler2.setDirty(1) };
         }
     }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.track_info.borrow().clone()
+    }
+
+    fn parameter_indication(&self, _id: ParameterId) -> ParameterIndication {
+        ParameterIndication {
+            automation: self.automation_state.get(),
+            ..Default::default()
+        }
+    }
 }
@@ -1,9 +1,9 @@
 #[macro_export]
 macro_rules! export_vst3 {
-    ($plugin:ty) => {
+    (@entry_points $factory:expr) => {
         #[unsafe(no_mangle)]
         pub extern "system" fn GetPluginFactory() -> *mut ::std::ffi::c_void {
-            ::plinth_plugin::vst3::Factory::<$plugin>::new() as _
+            $factory.into_raw() as _
         }
         
         #[cfg(target_os="windows")]
@@ -42,4 +42,14 @@ macro_rules! export_vst3 {
             true
         }                
     };
+
+    // A function returning the factory, for crates that also need the factory elsewhere,
+    // such as to generate moduleinfo.json
+    (factory = $factory:path $(,)?) => {
+        ::plinth_plugin::export_vst3!(@entry_points $factory());
+    };
+
+    ($($plugin:ty),+ $(,)?) => {
+        ::plinth_plugin::export_vst3!(@entry_points ::plinth_plugin::vst3::Factory::new()$(.with_plugin::<$plugin>())+);
+    };
 }
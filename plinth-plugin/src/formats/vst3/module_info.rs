@@ -0,0 +1,85 @@
+use std::{ffi::CStr, path::Path};
+
+use serde_json::json;
+use vst3::Steinberg::{PClassInfo_::ClassCardinality_::kManyInstances, Vst::SDKVersionString};
+
+use super::factory::Factory;
+
+impl Factory {
+    /// Contents of `moduleinfo.json` describing this factory and its classes
+    ///
+    /// Pass the same factory the binary exports, for example the function passed to `export_vst3!(factory = ...)`,
+    /// so that every exported plugin is listed.
+    pub fn module_info(&self) -> String {
+        let sdk_version = unsafe { CStr::from_ptr(SDKVersionString) }.to_string_lossy();
+
+        // The module is named after the first plugin
+        let (name, version) = self.classes
+            .first()
+            .map(|class| (class.name, class.version))
+            .unwrap_or_default();
+
+        let classes: Vec<_> = self.classes.iter()
+            .map(|class| json!({
+                "CID": format!("{:032X}", class.class_id),
+                "Category": "Audio Module Class",
+                "Name": class.name,
+                "Vendor": class.vendor,
+                "Version": class.version,
+                "SDKVersion": sdk_version,
+                "Sub Categories": class.subcategories
+                    .split('|')
+                    .filter(|subcategory| !subcategory.is_empty())
+                    .collect::<Vec<_>>(),
+                "Class Flags": 0,
+                "Cardinality": kManyInstances,
+                "Snapshots": [],
+            }))
+            .collect();
+
+        let module_info = json!({
+            "Name": name,
+            "Version": version,
+            "Factory Info": {
+                "Vendor": self.vendor,
+                "URL": self.url.unwrap_or_default(),
+                "E-Mail": self.email.unwrap_or_default(),
+                "Flags": {
+                    "Unicode": true,
+                    "Classes Discardable": false,
+                    "Component Non Discardable": false,
+                },
+            },
+            "Compatibility": [],
+            "Classes": classes,
+        });
+
+        serde_json::to_string_pretty(&module_info).unwrap() + "\n"
+    }
+
+    /// Writes `moduleinfo.json` to `Contents/Resources` inside the given `.vst3` bundle
+    pub fn write_module_info(&self, bundle_path: &Path) -> std::io::Result<()> {
+        let resources_dir = bundle_path.join("Contents").join("Resources");
+        std::fs::create_dir_all(&resources_dir)?;
+        std::fs::write(resources_dir.join("moduleinfo.json"), self.module_info())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::test_plugin::TestPlugin;
+    use crate::vst3::Factory;
+
+    #[test]
+    fn module_info_lists_factory_classes() {
+        let module_info: serde_json::Value = serde_json::from_str(&Factory::new().with_plugin::<TestPlugin>().module_info()).unwrap();
+
+        assert_eq!(module_info["Name"], "Test");
+        assert_eq!(module_info["Factory Info"]["Vendor"], "plinth");
+
+        let classes = module_info["Classes"].as_array().unwrap();
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0]["CID"], "706C696E746854657374506C7567696E");
+        assert_eq!(classes[0]["Sub Categories"], serde_json::json!(["Fx"]));
+    }
+}
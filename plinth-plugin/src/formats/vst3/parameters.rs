@@ -26,16 +26,19 @@ pub(super) fn parameter_change_to_event(id: ParamID, value: ParamValue, offset:
 
 pub struct ParameterChangeIterator<'a> {
     parameter_changes: Option<ComRef<'a, IParameterChanges>>,
+    parameter_ids: &'a [ParameterId],
     pitch_bend_parameter_ids: [ParameterId; 16],
     offset: usize,
     index: usize,
     finished: bool,
 }
 
-impl ParameterChangeIterator<'_> {
-    pub fn new(parameter_changes: *mut IParameterChanges, pitch_bend_parameter_ids: [ParameterId; 16]) -> Self {
+impl<'a> ParameterChangeIterator<'a> {
+    /// Changes to parameters not in `parameter_ids`, which needs to be sorted, are skipped
+    pub fn new(parameter_changes: *mut IParameterChanges, parameter_ids: &'a [ParameterId], pitch_bend_parameter_ids: [ParameterId; 16]) -> Self {
         Self {
             parameter_changes: unsafe { ComRef::from_raw(parameter_changes) },
+            parameter_ids,
             pitch_bend_parameter_ids,
             offset: 0,
             index: 0,
@@ -54,21 +57,21 @@ impl Iterator for ParameterChangeIterator<'_> {
         let parameter_changes = self.parameter_changes?;
 
         let parameter_count = unsafe { parameter_changes.getParameterCount() };
-        assert!(parameter_count >= 0);
-        if parameter_count == 0 {
+        if parameter_count <= 0 {
             return None;
         }
 
         let current_offset = self.offset;
         let current_index = self.index;
+        let parameter_ids = self.parameter_ids;
         let mut nth = 0;
 
-        let event = (0..unsafe { parameter_changes.getParameterCount() })
-            .flat_map(|parameter_index| {
-                let Some(value_queue) = (unsafe { ComRef::from_raw(parameter_changes.getParameterData(parameter_index)) }) else {
-                    panic!();
-                };
-
+        // Hosts can send unknown ids, missing queues, failing points and out of range values,
+        // so skip those instead of trusting them
+        let event = (0..parameter_count)
+            .filter_map(|parameter_index| unsafe { ComRef::from_raw(parameter_changes.getParameterData(parameter_index)) })
+            .filter(|value_queue| parameter_ids.binary_search(&unsafe { value_queue.getParameterId() }).is_ok())
+            .flat_map(|value_queue| {
                 let id = unsafe { value_queue.getParameterId() };
 
                 (0..unsafe { value_queue.getPointCount() })
@@ -76,30 +79,29 @@ impl Iterator for ParameterChangeIterator<'_> {
                     let mut offset = 0;
                     let mut value = 0.0;
                     let result = unsafe { value_queue.getPoint(point_index, &mut offset, &mut value) };
-                    if result != kResultOk {
-                        panic!();
+                    if result != kResultOk || !value.is_finite() {
+                        return None;
                     }
 
-                    assert!(offset >= 0);
-                    let offset = offset as usize;
-
-                    match offset.cmp(&current_offset) {
-                        cmp::Ordering::Equal => {
-                            if nth >= current_index {
-                                Some((id, offset, value))
-                            } else {
-                                nth += 1;
-                                None
-                            }    
-                        },
-
-                        cmp::Ordering::Greater => Some((id, offset, value)),
-
-                        cmp::Ordering::Less => None,
-                    }
+                    let offset = offset.max(0) as usize;
+                    Some((id, offset, value))
                 })
             })
-            .filter(|(_, offset, _)| *offset >= current_offset)
+            .filter(|&(_, offset, _)| {
+                match offset.cmp(&current_offset) {
+                    cmp::Ordering::Equal => {
+                        if nth >= current_index {
+                            true
+                        } else {
+                            nth += 1;
+                            false
+                        }
+                    },
+
+                    cmp::Ordering::Greater => true,
+                    cmp::Ordering::Less => false,
+                }
+            })
             .min_by_key(|(_, offset, _)| *offset);
 
         let Some(event) = event else {
@@ -121,3 +123,37 @@ impl Iterator for ParameterChangeIterator<'_> {
         Some(event)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use vst3::{ComWrapper, Steinberg::Vst::IParameterChanges};
+
+    use crate::{testing::vst3::ParameterChanges, Event};
+
+    use super::ParameterChangeIterator;
+
+    const PITCH_BEND_PARAMETER_IDS: [u32; 16] = [1000; 16];
+
+    #[test]
+    fn unknown_ids_and_non_finite_values_are_skipped() {
+        let parameter_changes = ComWrapper::new(ParameterChanges::default());
+        parameter_changes.add_point(7, 0, 0.5);
+        parameter_changes.add_point(5, 4, 0.25);
+        parameter_changes.add_point(9, 2, f64::NAN);
+        parameter_changes.add_point(1, 2, f64::INFINITY);
+        parameter_changes.add_point(1, 6, 1.0);
+
+        // Sorted for the binary search
+        let parameter_ids = [1, 5, 9];
+
+        let raw = parameter_changes.to_com_ptr::<IParameterChanges>().unwrap();
+        let events: Vec<_> = ParameterChangeIterator::new(raw.as_ptr(), &parameter_ids, PITCH_BEND_PARAMETER_IDS)
+            .map(|event| match event {
+                Event::ParameterValue { sample_offset, id, value } => (sample_offset, id, value),
+                _ => panic!("Expected a parameter value"),
+            })
+            .collect();
+
+        assert_eq!(events, [(4, 5, 0.25), (6, 1, 1.0)]);
+    }
+}
@@ -1,10 +1,19 @@
+use std::ffi::CStr;
 use std::io::{Read, Write};
 
 use num_traits::FromPrimitive;
-use vst3::{ComRef, Steinberg::{kResultOk, IBStream, IBStreamTrait}};
+use vst3::{ComRef, Steinberg::{kResultOk, tresult, IBStream, IBStreamTrait}};
+use vst3::Steinberg::Vst::{IAttributeListTrait, IStreamAttributes, IStreamAttributesTrait, String128};
+
+use crate::StateContext;
+use crate::string::char16_to_string;
 
 use super::Error;
 
+// From vstpresetkeys.h
+const STATE_TYPE_KEY: &CStr = c"StateType";
+const STATE_TYPE_DEFAULT: &str = "Default";
+
 pub struct Stream<'a> {
     raw: ComRef<'a, IBStream>,
 }
@@ -17,23 +26,48 @@ impl Stream<'_> {
             raw,
         })
     }
+
+    /// Find out why the host is saving or loading state, falling back to project state
+    pub fn state_context(&self) -> StateContext {
+        let Some(stream_attributes) = self.raw.cast::<IStreamAttributes>() else {
+            return StateContext::Project;
+        };
+
+        let Some(attributes) = (unsafe { ComRef::from_raw(stream_attributes.getAttributes()) }) else {
+            return StateContext::Project;
+        };
+
+        let mut state_type: String128 = [0; 128];
+        if unsafe { attributes.getString(STATE_TYPE_KEY.as_ptr(), state_type.as_mut_ptr(), size_of::<String128>() as _) } != kResultOk {
+            return StateContext::Project;
+        }
+
+        // "Default" means the state is stored as a preset
+        match char16_to_string(&state_type) {
+            Some(state_type) if state_type == STATE_TYPE_DEFAULT => StateContext::Preset,
+            _ => StateContext::Project,
+        }
+    }
 }
 
 impl Read for Stream<'_> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let mut total_bytes_read = 0;
         while total_bytes_read < buf.len() {
+            let remaining = &mut buf[total_bytes_read..];
+
             let mut bytes_read = 0;
-            let result = unsafe { self.raw.read(buf.as_mut_ptr() as _, buf.len() as _, &mut bytes_read) };
+            let result = unsafe { self.raw.read(remaining.as_mut_ptr() as _, remaining.len() as _, &mut bytes_read) };
             
             if bytes_read <= 0 {
                 break;
             }
 
             if result == kResultOk {
-                total_bytes_read += bytes_read as usize;
+                // Don't trust the host to stay within the buffer
+                total_bytes_read += usize::min(bytes_read as usize, remaining.len());
             } else {
-                return Err(std::io::Error::other(Error::from_i32(result).unwrap()));
+                return Err(stream_error(result));
             }
         }
 
@@ -46,17 +80,19 @@ impl Write for Stream<'_> {
         let mut total_bytes_written = 0;
         
         while total_bytes_written < buf.len() {
+            let remaining = &buf[total_bytes_written..];
+
             let mut bytes_written = 0;
-            let result = unsafe { self.raw.write(buf.as_ptr() as _, buf.len() as _, &mut bytes_written) };
+            let result = unsafe { self.raw.write(remaining.as_ptr() as _, remaining.len() as _, &mut bytes_written) };
 
             if bytes_written <= 0 {
                 break;
             }
 
             if result == kResultOk {
-                total_bytes_written += bytes_written as usize;
+                total_bytes_written += usize::min(bytes_written as usize, remaining.len());
             } else {
-                return Err(std::io::Error::other(Error::from_i32(result).unwrap()));
+                return Err(stream_error(result));
             }
         }
 
@@ -67,3 +103,50 @@ impl Write for Stream<'_> {
         Ok(())
     }
 }
+
+fn stream_error(result: tresult) -> std::io::Error {
+    match Error::from_i32(result) {
+        Some(error) => std::io::Error::other(error),
+        None => std::io::Error::other(format!("VST3 stream error {result}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use vst3::{ComWrapper, Steinberg::IBStream};
+
+    use crate::testing::vst3::MemoryStream;
+
+    use super::Stream;
+
+    #[test]
+    fn chunked_write_and_read() {
+        let data: Vec<u8> = (0..10).collect();
+
+        let memory_stream = ComWrapper::new(MemoryStream::default().with_max_chunk_size(3));
+        let raw = memory_stream.to_com_ptr::<IBStream>().unwrap();
+
+        // Each chunk has to continue where the previous one ended
+        let mut stream = Stream::new(raw.as_ptr()).unwrap();
+        stream.write_all(&data).unwrap();
+        assert_eq!(*memory_stream.data.borrow(), data);
+
+        let memory_stream = ComWrapper::new(MemoryStream::new(&data).with_max_chunk_size(3));
+        let raw = memory_stream.to_com_ptr::<IBStream>().unwrap();
+
+        let mut stream = Stream::new(raw.as_ptr()).unwrap();
+        let mut read_data = vec![0; 10];
+        stream.read_exact(&mut read_data).unwrap();
+        assert_eq!(read_data, data);
+
+        // Reading past the end stops at the end
+        let memory_stream = ComWrapper::new(MemoryStream::new(&data).with_max_chunk_size(4));
+        let raw = memory_stream.to_com_ptr::<IBStream>().unwrap();
+
+        let mut read_data = Vec::new();
+        Stream::new(raw.as_ptr()).unwrap().read_to_end(&mut read_data).unwrap();
+        assert_eq!(read_data, data);
+    }
+}
@@ -0,0 +1,51 @@
+use std::ffi::CStr;
+
+use vst3::ComRef;
+use vst3::Steinberg::{int64, kResultOk};
+use vst3::Steinberg::Vst::{IAttributeList, IAttributeListTrait, TChar};
+
+use crate::{Color, TrackInfo};
+use crate::string::char16_to_string;
+
+// From ivstchannelcontextinfo.h
+const CHANNEL_NAME_KEY: &CStr = c"channel name";
+const CHANNEL_NAME_LENGTH_KEY: &CStr = c"channel name length";
+const CHANNEL_COLOR_KEY: &CStr = c"channel color";
+
+const DEFAULT_NAME_LENGTH: usize = 128;
+
+/// VST3 reports neither the channel count nor the track type, so the channel count is left unset
+/// and the track is reported as a regular track
+pub fn convert_track_info(list: ComRef<'_, IAttributeList>) -> TrackInfo {
+    let name = get_string(list, CHANNEL_NAME_KEY, CHANNEL_NAME_LENGTH_KEY);
+
+    let mut color: int64 = 0;
+    let color = if unsafe { list.getInt(CHANNEL_COLOR_KEY.as_ptr(), &mut color) } == kResultOk {
+        Some(Color::from_argb(color as u32))
+    } else {
+        None
+    };
+
+    TrackInfo {
+        name,
+        color,
+        ..Default::default()
+    }
+}
+
+fn get_string(list: ComRef<'_, IAttributeList>, key: &CStr, length_key: &CStr) -> Option<String> {
+    let mut length: int64 = 0;
+    let length = if unsafe { list.getInt(length_key.as_ptr(), &mut length) } == kResultOk && length > 0 {
+        length as usize
+    } else {
+        DEFAULT_NAME_LENGTH
+    };
+
+    // +1 for the null terminator
+    let mut string: Vec<TChar> = vec![0; length + 1];
+    if unsafe { list.getString(key.as_ptr(), string.as_mut_ptr(), (string.len() * size_of::<TChar>()) as _) } == kResultOk {
+        char16_to_string(&string)
+    } else {
+        None
+    }
+}
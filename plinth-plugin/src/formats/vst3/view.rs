@@ -1,9 +1,9 @@
-use std::{cell::RefCell, ffi::{c_void, CStr}, rc::Rc};
+use std::{cell::{Cell, RefCell}, ffi::{c_void, CStr}, rc::Rc};
 
 use vst3::{ComPtr, ComRef, ComWrapper};
 use vst3::Steinberg::{char16, int16, kInvalidArgument, kResultFalse, kResultOk, tresult, FIDString, IPlugFrame, IPlugView, IPlugViewContentScaleSupport, IPlugViewContentScaleSupportTrait, IPlugViewContentScaleSupport_::ScaleFactor, IPlugViewTrait, TBool, ViewRect, Vst::IComponentHandler};
 
-use crate::{Editor, vst3::key_codes::{to_key_code, unicode_to_string}};
+use crate::{AutomationState, Editor, TrackInfo, vst3::key_codes::{to_key_code, unicode_to_string}};
 
 use super::{host::Vst3Host, Vst3Plugin};
 
@@ -23,6 +23,8 @@ impl<P: Vst3Plugin + 'static> View<P> {
     pub fn new(
         plugin: Rc<RefCell<Option<P>>>,
         component_handler: Rc<RefCell<Option<ComPtr<IComponentHandler>>>>,
+        track_info: Rc<RefCell<Option<TrackInfo>>>,
+        automation_state: Rc<Cell<AutomationState>>,
     ) -> ComWrapper<Self> {
         let context = ViewContext {
             frame: None,
@@ -44,6 +46,8 @@ impl<P: Vst3Plugin + 'static> View<P> {
             component_handler,
             view.to_com_ptr().unwrap(),
             context,
+            track_info,
+            automation_state,
         ));
 
         let mut plugin = plugin.borrow_mut();
@@ -53,6 +57,10 @@ impl<P: Vst3Plugin + 'static> View<P> {
         view
     }
 
+    pub fn editor(&self) -> &Rc<RefCell<Option<P::Editor>>> {
+        &self.editor
+    }
+
     #[cfg(target_os="linux")]
     fn unregister_timer(&self) {
         use vst3::Steinberg::Linux::IRunLoopTrait;
@@ -243,8 +251,8 @@ impl<P: Vst3Plugin + 'static> IPlugViewTrait for View<P> {
             .check_window_size(((right - left) as _, (bottom - top) as _))
             .unwrap_or(P::Editor::DEFAULT_SIZE);
 
-        rect.right = supported_size.0 as i32 - left;
-        rect.bottom = supported_size.1 as i32 - top;
+        rect.right = left + supported_size.0 as i32;
+        rect.bottom = top + supported_size.1 as i32;
 
         kResultOk
     }
@@ -1,11 +1,23 @@
-use crate::ParameterId;
+use std::path::PathBuf;
+
+use crate::{bundle, ParameterId, TrackInfo};
 use crate::formats::PluginFormat;
-use crate::parameters::ParameterValue;
+use crate::parameters::{indication::ParameterIndication, ParameterValue};
 
 #[derive(Clone)]
 pub struct HostInfo {
     pub name: Option<String>,
     pub format: PluginFormat,
+    /// Path of the plugin bundle (for example `Plugin.vst3`), or of the plugin binary if it isn't in a bundle
+    pub bundle_path: Option<PathBuf>,
+}
+
+impl HostInfo {
+    /// Directory for resources shipped with the plugin: `Contents/Resources` inside a bundle,
+    /// or a directory named after the plugin binary next to it otherwise, for example `Gain` for `Gain.clap`
+    pub fn resources_dir(&self) -> Option<PathBuf> {
+        self.bundle_path.as_deref().and_then(bundle::resources_dir)
+    }
 }
 
 pub trait Host {
@@ -22,4 +34,26 @@ pub trait Host {
     fn reload_parameters(&self);
 
     fn mark_state_dirty(&self);
+
+    /// Call when the user has closed a floating editor window, so that the host knows it's no longer visible
+    fn editor_closed(&self) {}
+
+    /// Latest track information reported by the host, if any
+    fn track_info(&self) -> Option<TrackInfo> {
+        None
+    }
+
+    /// Latest automation/mapping indication reported by the host for a parameter
+    ///
+    /// CLAP hosts report this per parameter. VST3 only has a global automation state,
+    /// so in VST3 every parameter gets the same automation state and no mapping.
+    fn parameter_indication(&self, _id: ParameterId) -> ParameterIndication {
+        ParameterIndication::default()
+    }
+
+    /// Opens the host's own audio and MIDI settings, for a settings button in the standalone app.
+    /// Returns false if the host has none, which is the case in plugin hosts.
+    fn open_settings(&self) -> bool {
+        false
+    }
 }
@@ -1,7 +1,9 @@
-pub use editor::{Editor, NoEditor};
+pub use color::Color;
+pub use editor::{Editor, NoEditor, ResizeConstraints};
 pub use error::Error;
 pub use event::Event;
 pub use host::{Host, HostInfo};
+pub use midi::{MidiCcAssignment, MidiLearn};
 pub use formats::{clap, vst3};
 #[cfg(feature = "standalone")]
 pub use formats::standalone;
@@ -10,12 +12,17 @@ pub use parameters::bool::{BoolParameter, BoolFormatter};
 pub use parameters::enums::{Enum, EnumParameter};
 pub use parameters::float::{FloatParameter, LinearFloatRange, LogFloatRange, PowFloatRange, FloatFormatter, HzFormatter};
 pub use parameters::formatter::ParameterFormatter;
+pub use parameters::indication::{AutomationState, ParameterIndication, ParameterMapping};
+pub use parameters::info::ParameterRole;
 pub use parameters::int::{IntParameter, IntRange, IntFormatter};
 pub use parameters::map::ParameterMap;
 pub use parameters::parameter::Parameter;
 pub use parameters::range::ParameterRange;
 pub use plugin::Plugin;
 pub use processor::{Processor, ProcessorConfig, ProcessState, ProcessMode};
+pub use state::StateContext;
+pub use thread_pool::ThreadPool;
+pub use track_info::{TrackInfo, TrackType};
 pub use transport::Transport;
 
 #[cfg(target_os="macos")]
@@ -28,14 +35,26 @@ pub use plinth_core;
 pub use raw_window_handle;
 pub use xxhash_rust;
 
+mod bundle;
+mod color;
 mod editor;
 pub mod error;
 mod event;
 mod host;
 mod formats;
+mod logging;
+mod midi;
 pub mod parameters;
 mod plugin;
 mod processor;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod rt_check;
+mod state;
 pub mod string;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod thread_pool;
+mod track_info;
 mod transport;
 mod window_handle;
@@ -0,0 +1,345 @@
+use std::fmt::{self, Write as _};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+// Longer messages are truncated
+const MAX_MESSAGE_LEN: usize = 512;
+const QUEUE_LEN: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+pub(crate) trait LogSink: Send + Sync {
+    fn log(&self, level: Level, message: &str);
+    fn flush(&self) {}
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum LogError {
+    #[error("Another global logger has already been installed")]
+    LoggerInstalled,
+    #[error("The log queue was lost by a failed sink thread")]
+    QueueUnavailable,
+    #[error("Failed to create log file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A formatted record, stored inline so that logging doesn't allocate
+struct LogMessage {
+    level: Level,
+    len: usize,
+    text: [u8; MAX_MESSAGE_LEN],
+}
+
+impl LogMessage {
+    fn new(record: &Record) -> Self {
+        let mut message = Self {
+            level: record.level(),
+            len: 0,
+            text: [0; MAX_MESSAGE_LEN],
+        };
+
+        let _ = write!(message, "[{}] {}: {}", record.level(), record.target(), record.args());
+        message
+    }
+
+    fn as_str(&self) -> &str {
+        // write_str() only cuts at char boundaries
+        std::str::from_utf8(&self.text[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for LogMessage {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut len = s.len().min(MAX_MESSAGE_LEN - self.len);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.text[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+
+        Ok(())
+    }
+}
+
+/// Global logger that queues records for the registered sinks
+///
+/// The `log` crate only supports one global logger, so records can't be attributed to a plugin instance.
+/// Because of that, every instance registers its sink, but only the oldest registered sink receives records,
+/// those of every instance. When its instance goes away, the next registered sink takes over. Logging doesn't
+/// lock or allocate, so it can be used on the audio thread; records are dropped if the queue is full.
+struct PlinthLogger {
+    level: AtomicUsize,
+}
+
+impl Log for PlinthLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() as usize <= self.level.load(Ordering::Relaxed)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let _ = queue().sender.try_send(LogMessage::new(record));
+    }
+
+    // Records are written by the sink thread as they arrive
+    fn flush(&self) {}
+}
+
+static LOGGER: PlinthLogger = PlinthLogger {
+    level: AtomicUsize::new(LevelFilter::Off as usize),
+};
+static LOGGER_INSTALLED: OnceLock<bool> = OnceLock::new();
+static NEXT_FILE_ID: AtomicUsize = AtomicUsize::new(0);
+static NEXT_REGISTRATION_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct Queue {
+    sender: SyncSender<LogMessage>,
+    // Owned by the sink thread while a sink is registered
+    receiver: Mutex<Option<Receiver<LogMessage>>>,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_LEN);
+
+        Queue {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    })
+}
+
+struct RegisteredSink {
+    id: usize,
+    level: LevelFilter,
+    sink: Arc<dyn LogSink>,
+}
+
+// In registration order, the first one receives the records
+static SINKS: Mutex<Vec<RegisteredSink>> = Mutex::new(Vec::new());
+
+struct SinkThread {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Receiver<LogMessage>>,
+}
+
+// Locked while sinks are registered or unregistered, before SINKS
+static SINK_THREAD: Mutex<Option<SinkThread>> = Mutex::new(None);
+
+fn unregister(id: usize) {
+    let mut sinks = SINKS.lock().unwrap();
+    sinks.retain(|sink| sink.id != id);
+    update_level(&sinks);
+}
+
+fn update_level(sinks: &[RegisteredSink]) {
+    let level = sinks.iter()
+        .map(|sink| sink.level)
+        .max()
+        .unwrap_or(LevelFilter::Off);
+
+    LOGGER.level.store(level as usize, Ordering::Relaxed);
+    log::set_max_level(level);
+}
+
+// The receiver is polled instead of blocking on it, because waking up a blocked receiver takes a lock.
+// Sinks are only used while SINKS is locked, so a sink can't be used after it has been unregistered.
+fn run_sink(receiver: Receiver<LogMessage>, stop: Arc<AtomicBool>) -> Receiver<LogMessage> {
+    loop {
+        match receiver.try_recv() {
+            Ok(message) => {
+                if let Some(first) = SINKS.lock().unwrap().first() {
+                    first.sink.log(message.level, message.as_str());
+                }
+            }
+            Err(TryRecvError::Empty) if stop.load(Ordering::Acquire) => break,
+            Err(TryRecvError::Empty) => std::thread::sleep(POLL_INTERVAL),
+            Err(TryRecvError::Disconnected) => break,
+        }
+    }
+
+    if let Some(first) = SINKS.lock().unwrap().first() {
+        first.sink.flush();
+    }
+
+    receiver
+}
+
+/// Unregisters the sink when dropped
+///
+/// The last sink is only unregistered after the sink thread has written all queued records to it and stopped.
+pub(crate) struct LogRegistration {
+    id: usize,
+}
+
+impl Drop for LogRegistration {
+    fn drop(&mut self) {
+        let mut sink_thread = SINK_THREAD.lock().unwrap();
+
+        let last = SINKS.lock().unwrap().iter().all(|sink| sink.id == self.id);
+        if !last {
+            // The next sink takes over
+            unregister(self.id);
+            return;
+        }
+
+        // The thread has to be gone before the plugin library can be unloaded
+        if let Some(thread) = sink_thread.take() {
+            thread.stop.store(true, Ordering::Release);
+            if let Ok(receiver) = thread.handle.join() {
+                *queue().receiver.lock().unwrap() = Some(receiver);
+            }
+        }
+
+        unregister(self.id);
+    }
+}
+
+pub(crate) fn register_sink(level: LevelFilter, sink: Arc<dyn LogSink>) -> Result<LogRegistration, LogError> {
+    let installed = *LOGGER_INSTALLED.get_or_init(|| log::set_logger(&LOGGER).is_ok());
+    if !installed {
+        return Err(LogError::LoggerInstalled);
+    }
+
+    let mut sink_thread = SINK_THREAD.lock().unwrap();
+
+    let id = NEXT_REGISTRATION_ID.fetch_add(1, Ordering::Relaxed);
+
+    {
+        let mut sinks = SINKS.lock().unwrap();
+        sinks.push(RegisteredSink { id, level, sink });
+        update_level(&sinks);
+    }
+
+    if sink_thread.is_none() {
+        let Some(receiver) = queue().receiver.lock().unwrap().take() else {
+            unregister(id);
+            return Err(LogError::QueueUnavailable);
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let stop = stop.clone();
+
+            std::thread::Builder::new()
+                .name("plinth-log".into())
+                .spawn(move || run_sink(receiver, stop))
+        };
+
+        match handle {
+            Ok(handle) => *sink_thread = Some(SinkThread { stop, handle }),
+            Err(error) => {
+                unregister(id);
+                return Err(error.into());
+            }
+        }
+    }
+
+    Ok(LogRegistration { id })
+}
+
+/// Writes records to a file
+///
+/// Every instance creates its own file, which receives the records of all instances while it's the oldest
+/// registered sink. Records aren't buffered so the file is complete even if the process crashes.
+pub(crate) struct FileLogSink {
+    file: Mutex<File>,
+}
+
+impl FileLogSink {
+    /// Create a log file in the temporary directory, named after the plugin and the instance
+    pub fn create(vendor: &str, plugin_name: &str) -> std::io::Result<Self> {
+        let directory = log_directory(vendor, plugin_name);
+        fs::create_dir_all(&directory)?;
+
+        let file_id = NEXT_FILE_ID.fetch_add(1, Ordering::Relaxed);
+        let path = directory.join(format!("{}-{file_id}.log", std::process::id()));
+
+        Ok(Self {
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+}
+
+impl LogSink for FileLogSink {
+    fn log(&self, _level: Level, message: &str) {
+        let mut file = self.file.lock().unwrap();
+        let _ = writeln!(file, "{message}");
+    }
+}
+
+fn log_directory(vendor: &str, plugin_name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(vendor)
+        .join(plugin_name)
+}
+
+pub(crate) fn register_file_sink(level: LevelFilter, vendor: &str, plugin_name: &str) -> Result<LogRegistration, LogError> {
+    let sink = FileLogSink::create(vendor, plugin_name)?;
+    register_sink(level, Arc::new(sink))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::sync::{Arc, Mutex};
+
+    use log::{Level, LevelFilter};
+
+    use super::{register_sink, LogMessage, LogSink, MAX_MESSAGE_LEN};
+
+    #[derive(Default)]
+    struct MemorySink {
+        messages: Mutex<Vec<String>>,
+    }
+
+    impl LogSink for MemorySink {
+        fn log(&self, _level: Level, message: &str) {
+            self.messages.lock().unwrap().push(message.to_string());
+        }
+    }
+
+    #[test]
+    fn long_messages_are_truncated_at_char_boundaries() {
+        let mut message = LogMessage {
+            level: Level::Info,
+            len: 0,
+            text: [0; MAX_MESSAGE_LEN],
+        };
+
+        write!(message, "{}", "ä".repeat(MAX_MESSAGE_LEN)).unwrap();
+
+        assert_eq!(message.len, MAX_MESSAGE_LEN);
+        assert_eq!(message.as_str(), "ä".repeat(MAX_MESSAGE_LEN / 2));
+    }
+
+    #[test]
+    fn surviving_instance_keeps_logging() {
+        let second_sink = Arc::new(MemorySink::default());
+
+        let first = register_sink(LevelFilter::Info, Arc::new(MemorySink::default())).unwrap();
+        let second = register_sink(LevelFilter::Info, second_sink.clone()).unwrap();
+
+        drop(first);
+        log::info!("after the first instance is gone");
+
+        // Dropping the last registration writes the queued records
+        drop(second);
+
+        assert!(second_sink.messages.lock().unwrap().iter().any(|message| message.ends_with("after the first instance is gone")));
+    }
+}
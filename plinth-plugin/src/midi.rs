@@ -0,0 +1,418 @@
+use std::{cell::RefCell, io::{Read, Write}, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc}};
+
+use crate::{Error, Event, ParameterId};
+
+/// Default assignment of a MIDI continuous controller to a parameter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MidiCcAssignment {
+    pub controller: u8,
+    /// Channel the assignment applies to, all channels if `None`
+    pub channel: Option<u8>,
+    pub parameter_id: ParameterId,
+}
+
+impl MidiCcAssignment {
+    pub fn new(controller: u8, parameter_id: impl Into<ParameterId>) -> Self {
+        assert!(controller < 128, "Invalid MIDI controller number {controller}");
+
+        Self {
+            controller,
+            channel: None,
+            parameter_id: parameter_id.into(),
+        }
+    }
+
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        assert!(channel < 16, "Invalid MIDI channel {channel}");
+
+        self.channel = Some(channel);
+        self
+    }
+
+    fn applies_to(&self, channel: i16, controller: i16) -> bool {
+        self.controller as i16 == controller && self.channel.is_none_or(|assigned_channel| assigned_channel as i16 == channel)
+    }
+}
+
+/// MIDI learn service binding MIDI CCs to parameters at runtime
+///
+/// The editor puts a parameter into learn mode with [`MidiLearn::start_learning`], and the next
+/// [`Event::ControlChange`] passed to [`MidiLearn::process_event`] is bound to it.
+/// Plugins return the service from [`crate::Plugin::midi_learn`]. The wrappers then route control
+/// changes to it, save and restore the learned assignments with the editor state, and apply learned
+/// assignments before the defaults from [`crate::Plugin::midi_cc_assignments`].
+///
+/// Like the defaults, learned controllers are turned into parameter changes on the audio thread, so they
+/// never reach [`crate::Plugin::process_event`] as control changes. The CLAP wrapper also sends these
+/// changes to the host as parameter output events. VST3 hosts map controllers to parameters themselves
+/// through `IMidiMapping`, so the changes already come from the host.
+#[derive(Default)]
+pub struct MidiLearn {
+    learning: RefCell<Option<ParameterId>>,
+    assignments: RefCell<Vec<MidiCcAssignment>>,
+    learned_controllers: Arc<LearnedControllers>,
+}
+
+impl MidiLearn {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_learning(&self, id: ParameterId) {
+        *self.learning.borrow_mut() = Some(id);
+        self.learned_controllers.learning.store(true, Ordering::Release);
+    }
+
+    pub fn cancel_learning(&self) {
+        *self.learning.borrow_mut() = None;
+        self.learned_controllers.learning.store(false, Ordering::Release);
+    }
+
+    /// Parameter waiting for a control change, if any
+    pub fn learning(&self) -> Option<ParameterId> {
+        *self.learning.borrow()
+    }
+
+    /// Binds a controller to the parameter in learn mode
+    /// Returns true if learn mode was active
+    pub fn learn(&self, channel: i16, controller: i16) -> bool {
+        let (Ok(channel @ 0..16), Ok(controller @ 0..128)) = (u8::try_from(channel), u8::try_from(controller)) else {
+            return false;
+        };
+
+        let Some(id) = self.learning.borrow_mut().take() else {
+            return false;
+        };
+
+        self.learned_controllers.learning.store(false, Ordering::Release);
+
+        // A controller drives a single parameter and a parameter is driven by a single controller
+        {
+            let mut assignments = self.assignments.borrow_mut();
+            assignments.retain(|assignment| assignment.parameter_id != id && !assignment.applies_to(channel as _, controller as _));
+            assignments.push(MidiCcAssignment::new(controller, id).with_channel(channel));
+        }
+
+        self.update_learned_controllers();
+
+        true
+    }
+
+    /// Binds a control change to the parameter in learn mode
+    /// Returns true if the event was used for learning, otherwise it should be passed on to the plugin
+    pub fn process_event(&self, event: &Event) -> bool {
+        let Event::ControlChange { channel, controller, .. } = *event else {
+            return false;
+        };
+
+        self.learn(channel, controller)
+    }
+
+    pub fn assigned_parameter(&self, channel: i16, controller: i16) -> Option<ParameterId> {
+        assigned_parameter(&self.assignments.borrow(), channel, controller)
+    }
+
+    /// Controller bound to a parameter, if any
+    pub fn assignment(&self, id: ParameterId) -> Option<MidiCcAssignment> {
+        self.assignments.borrow()
+            .iter()
+            .find(|assignment| assignment.parameter_id == id)
+            .copied()
+    }
+
+    pub fn forget(&self, id: ParameterId) {
+        self.assignments.borrow_mut().retain(|assignment| assignment.parameter_id != id);
+        self.update_learned_controllers();
+    }
+
+    pub fn assignments(&self) -> Vec<MidiCcAssignment> {
+        self.assignments.borrow().clone()
+    }
+
+    pub fn set_assignments(&self, assignments: impl IntoIterator<Item = MidiCcAssignment>) {
+        *self.assignments.borrow_mut() = assignments.into_iter().collect();
+        self.update_learned_controllers();
+    }
+
+    /// Writes the learned assignments, for example as part of the plugin state
+    pub fn save(&self, writer: &mut impl Write) -> Result<(), Error> {
+        let assignments = self.assignments.borrow();
+
+        writer.write_all(&(assignments.len() as u32).to_le_bytes())?;
+
+        for assignment in assignments.iter() {
+            writer.write_all(&assignment.parameter_id.to_le_bytes())?;
+            writer.write_all(&[assignment.controller, assignment.channel.unwrap_or(ANY_CHANNEL)])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load(&self, reader: &mut impl Read) -> Result<(), Error> {
+        let mut count = [0; 4];
+        reader.read_exact(&mut count)?;
+
+        let mut assignments = Vec::new();
+
+        for _ in 0..u32::from_le_bytes(count) {
+            let mut id = [0; 4];
+            let mut controller_and_channel = [0; 2];
+            reader.read_exact(&mut id)?;
+            reader.read_exact(&mut controller_and_channel)?;
+
+            let [controller, channel] = controller_and_channel;
+            if controller >= 128 || (channel >= 16 && channel != ANY_CHANNEL) {
+                return Err(Error::SerializationError);
+            }
+
+            assignments.push(MidiCcAssignment {
+                controller,
+                channel: (channel != ANY_CHANNEL).then_some(channel),
+                parameter_id: u32::from_le_bytes(id),
+            });
+        }
+
+        *self.assignments.borrow_mut() = assignments;
+        self.update_learned_controllers();
+
+        Ok(())
+    }
+
+    /// Learned assignments for the audio thread, which maps raw MIDI before the plugin sees it
+    pub(crate) fn learned_controllers(&self) -> Arc<LearnedControllers> {
+        self.learned_controllers.clone()
+    }
+
+    fn update_learned_controllers(&self) {
+        let assignments = self.assignments.borrow();
+
+        for (index, parameter) in self.learned_controllers.parameters.iter().enumerate() {
+            let channel = (index / 128) as i16;
+            let controller = (index % 128) as i16;
+
+            let id = assigned_parameter(&assignments, channel, controller).map_or(UNASSIGNED, u64::from);
+            parameter.store(id, Ordering::Relaxed);
+        }
+    }
+}
+
+const UNASSIGNED: u64 = u64::MAX;
+
+/// Lock-free copy of the assignments learned by [`MidiLearn`], one slot per channel and controller
+pub(crate) struct LearnedControllers {
+    learning: AtomicBool,
+    parameters: [AtomicU64; 16 * 128],
+}
+
+impl Default for LearnedControllers {
+    fn default() -> Self {
+        Self {
+            learning: AtomicBool::new(false),
+            parameters: std::array::from_fn(|_| AtomicU64::new(UNASSIGNED)),
+        }
+    }
+}
+
+impl LearnedControllers {
+    /// Control changes have to reach [`MidiLearn`] as is while it's learning
+    pub fn is_learning(&self) -> bool {
+        self.learning.load(Ordering::Acquire)
+    }
+
+    pub fn assigned_parameter(&self, channel: i16, controller: i16) -> Option<ParameterId> {
+        let (Ok(channel @ 0..16), Ok(controller @ 0..128)) = (usize::try_from(channel), usize::try_from(controller)) else {
+            return None;
+        };
+
+        let id = self.parameters[channel * 128 + controller].load(Ordering::Relaxed);
+        (id != UNASSIGNED).then_some(id as ParameterId)
+    }
+}
+
+// Serialized channel for assignments that apply to all channels
+const ANY_CHANNEL: u8 = 0xFF;
+
+/// Finds the parameter assigned to a controller, preferring channel-specific assignments
+pub(crate) fn assigned_parameter(assignments: &[MidiCcAssignment], channel: i16, controller: i16) -> Option<ParameterId> {
+    assignments.iter()
+        .filter(|assignment| assignment.applies_to(channel, controller))
+        .max_by_key(|assignment| assignment.channel.is_some())
+        .map(|assignment| assignment.parameter_id)
+}
+
+/// Converts a raw MIDI 1.0 message to an event
+/// Control changes are converted to parameter changes if they have been assigned to a parameter,
+/// others are passed on as is. Learned assignments take precedence over the default ones.
+pub(crate) fn parse_midi(
+    data: &[u8],
+    sample_offset: usize,
+    assignments: &[MidiCcAssignment],
+    learned_controllers: Option<&LearnedControllers>,
+) -> Option<Event> {
+    if data.len() < 2 {
+        return None;
+    }
+
+    let status = data[0] & 0xF0;
+    let channel = (data[0] & 0x0F) as i16;
+    let key = data[1] as i16;
+    let velocity = if data.len() >= 3 {
+        data[2] as f64 / 127.0
+    } else {
+        0.0
+    };
+
+    match status {
+        0x90 if data.len() >= 3 && data[2] > 0 => Some(Event::NoteOn {
+            sample_offset,
+            channel,
+            key,
+            note: -1,
+            velocity,
+        }),
+        0x80 | 0x90 => Some(Event::NoteOff {
+            sample_offset,
+            channel,
+            key,
+            note: -1,
+            velocity,
+        }),
+        0xB0 if data.len() >= 3 => {
+            let controller = data[1] as i16;
+            let value = data[2] as f64 / 127.0;
+
+            let id = match learned_controllers {
+                Some(learned_controllers) if learned_controllers.is_learning() => None,
+                Some(learned_controllers) => learned_controllers.assigned_parameter(channel, controller)
+                    .or_else(|| assigned_parameter(assignments, channel, controller)),
+                None => assigned_parameter(assignments, channel, controller),
+            };
+
+            match id {
+                Some(id) => Some(Event::ParameterValue {
+                    sample_offset,
+                    id,
+                    value,
+                }),
+                None => Some(Event::ControlChange {
+                    sample_offset,
+                    channel,
+                    controller,
+                    value,
+                }),
+            }
+        }
+        0xE0 if data.len() >= 3 => {
+            let lsb = data[1] as i16;
+            let msb = data[2] as i16;
+            let bend = (msb << 7 | lsb) - 8192;
+            let semitones = bend as f64 / 8192.0 * 2.0;
+            Some(Event::PitchBend {
+                sample_offset,
+                channel,
+                key: -1,
+                note: -1,
+                semitones,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Event;
+
+    use super::{parse_midi, MidiCcAssignment, MidiLearn};
+
+    fn control_change(channel: i16, controller: i16, value: f64) -> Event {
+        Event::ControlChange { sample_offset: 0, channel, controller, value }
+    }
+
+    #[test]
+    fn control_change_to_parameter() {
+        let assignments = [
+            MidiCcAssignment::new(7, 10u32),
+            MidiCcAssignment::new(7, 20u32).with_channel(1),
+        ];
+
+        let Some(Event::ParameterValue { id, value, .. }) = parse_midi(&[0xB0, 7, 127], 0, &assignments, None) else {
+            panic!("Expected a parameter value");
+        };
+        assert_eq!(id, 10);
+        assert_eq!(value, 1.0);
+
+        // Channel-specific assignments take precedence
+        let Some(Event::ParameterValue { id, .. }) = parse_midi(&[0xB1, 7, 0], 0, &assignments, None) else {
+            panic!("Expected a parameter value");
+        };
+        assert_eq!(id, 20);
+
+        // Unassigned controllers are passed on
+        assert!(matches!(
+            parse_midi(&[0xB0, 1, 64], 0, &assignments, None),
+            Some(Event::ControlChange { channel: 0, controller: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn midi_learn() {
+        let midi_learn = MidiLearn::new();
+
+        // Nothing is bound yet
+        assert!(!midi_learn.process_event(&control_change(0, 1, 0.5)));
+
+        midi_learn.start_learning(10);
+        assert!(midi_learn.process_event(&control_change(2, 1, 0.5)));
+        assert_eq!(midi_learn.learning(), None);
+        assert_eq!(midi_learn.assigned_parameter(2, 1), Some(10));
+
+        // Once learned, control changes are left to the wrappers to map
+        assert!(!midi_learn.process_event(&control_change(2, 1, 0.25)));
+        assert_eq!(midi_learn.assigned_parameter(3, 1), None);
+
+        // Learning another controller for the same parameter replaces the binding
+        midi_learn.start_learning(10);
+        midi_learn.learn(0, 74);
+        assert_eq!(midi_learn.assignments(), [MidiCcAssignment::new(74, 10u32).with_channel(0)]);
+
+        let mut state = Vec::new();
+        midi_learn.save(&mut state).unwrap();
+
+        let loaded = MidiLearn::new();
+        loaded.load(&mut state.as_slice()).unwrap();
+        assert_eq!(loaded.assignments(), midi_learn.assignments());
+    }
+
+    #[test]
+    fn learned_assignments_take_precedence() {
+        let assignments = [MidiCcAssignment::new(7, 10u32)];
+        let midi_learn = MidiLearn::new();
+        let learned_controllers = midi_learn.learned_controllers();
+
+        // Control changes are passed on as is while learning, even if they have a default assignment
+        midi_learn.start_learning(20);
+        assert!(matches!(
+            parse_midi(&[0xB0, 7, 127], 0, &assignments, Some(&learned_controllers)),
+            Some(Event::ControlChange { channel: 0, controller: 7, .. })
+        ));
+
+        assert!(midi_learn.process_event(&control_change(0, 7, 1.0)));
+        assert!(matches!(
+            parse_midi(&[0xB0, 7, 127], 0, &assignments, Some(&learned_controllers)),
+            Some(Event::ParameterValue { id: 20, .. })
+        ));
+
+        // The default assignment still applies on other channels
+        assert!(matches!(
+            parse_midi(&[0xB1, 7, 127], 0, &assignments, Some(&learned_controllers)),
+            Some(Event::ParameterValue { id: 10, .. })
+        ));
+
+        midi_learn.forget(20);
+        assert!(matches!(
+            parse_midi(&[0xB0, 7, 127], 0, &assignments, Some(&learned_controllers)),
+            Some(Event::ParameterValue { id: 10, .. })
+        ));
+    }
+}
@@ -3,6 +3,7 @@ pub mod enums;
 pub mod float;
 pub mod formatter;
 pub mod group;
+pub mod indication;
 pub mod info;
 pub mod int;
 pub mod kind;
@@ -4,7 +4,7 @@ use portable_atomic::{AtomicF64, Ordering};
 
 use crate::{error::Error, Parameter, ParameterFormatter, ParameterId, ParameterValue};
 
-use super::{info::ParameterInfo, parameter::ParameterPlain, ModulationChangedCallback};
+use super::{info::{info_builders, ParameterInfo}, parameter::ParameterPlain, ModulationChangedCallback};
 
 const DEFAULT_FALSE_STRING: &str = "False";
 const DEFAULT_TRUE_STRING: &str = "True";
@@ -26,7 +26,8 @@ pub struct BoolParameter {
 impl BoolParameter {
     pub fn new(id: impl Into<ParameterId>, name: impl Into<String>) -> Self {
         let info = ParameterInfo::new(id.into(), name.into())
-            .with_steps(1);
+            .with_steps(1)
+            .as_list();
 
         Self {
             info,
@@ -38,10 +39,7 @@ impl BoolParameter {
         }
     }
 
-    pub fn with_path(mut self, path: String) -> Self {
-        self.info = self.info.with_path(path);
-        self
-    }
+    info_builders!();
 
     pub fn with_default_value(mut self, default_value: bool) -> Self {
         let default_normalized_value = if default_value { 1.0 } else { 0.0 };
@@ -71,16 +69,6 @@ impl BoolParameter {
         self
     }
 
-    pub fn as_output(mut self, output: bool) -> Self {
-        self.info = self.info.as_output(output);
-        self
-    }
-
-    pub fn hidden(mut self) -> Self {
-        self.info = self.info.hidden();
-        self
-    }
-
     pub fn set_value(&self, value: bool) {
         self.value.store(value, Ordering::Release);
 
@@ -4,7 +4,7 @@ use portable_atomic::{AtomicF64, Ordering};
 
 use crate::{error::Error, ParameterId};
 
-use super::{info::ParameterInfo, parameter::{Parameter, ParameterPlain}, range::ParameterRange, ModulationChangedCallback, ParameterValue};
+use super::{info::{info_builders, ParameterInfo}, parameter::{Parameter, ParameterPlain}, range::ParameterRange, ModulationChangedCallback, ParameterValue};
 
 pub type ValueChangedCallback<T> = Arc<dyn Fn(ParameterId, T) + Send + Sync>;
 
@@ -39,6 +39,7 @@ impl<T: Enum> EnumParameter<T> {
         let range = IntRange::new(0, T::COUNT as i64 - 1);
         let info = ParameterInfo::new(id.into(), name.into())
             .with_steps(T::COUNT - 1)
+            .as_list()
             .with_default_normalized_value(range.plain_to_normalized(T::default().to_usize() as i64).unwrap());
 
         Self {
@@ -51,10 +52,7 @@ impl<T: Enum> EnumParameter<T> {
         }
     }
 
-    pub fn with_path(mut self, path: String) -> Self {
-        self.info = self.info.with_path(path);
-        self
-    }
+    info_builders!();
 
     pub fn with_default_value(mut self, default_value: T) -> Self {
         let default_normalized_value = self.range.plain_to_normalized(default_value.to_usize() as i64).unwrap();
@@ -74,16 +72,6 @@ impl<T: Enum> EnumParameter<T> {
         self
     }
 
-    pub fn as_output(mut self, output: bool) -> Self {
-        self.info = self.info.as_output(output);
-        self
-    }
-
-    pub fn hidden(mut self) -> Self {
-        self.info = self.info.hidden();
-        self
-    }
-
     pub fn unmodulated_value(&self) -> T {
         T::from_usize(self.value.load(Ordering::Acquire)).unwrap()
     }
@@ -183,7 +171,7 @@ impl<T: Enum> ParameterPlain for EnumParameter<T> {
     
     fn normalized_to_plain(&self, normalized: ParameterValue) -> T {
         let value = self.range.normalized_to_plain(normalized);
-        let value = value.clamp(0, T::COUNT as i64);
+        let value = value.clamp(0, T::COUNT as i64 - 1);
 
         T::from_usize(value as usize).unwrap()
     }
@@ -232,3 +220,65 @@ impl ParameterRange<i64> for IntRange {
         self.min + i64::min(steps as i64, (normalized * (steps + 1) as f64) as i64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{parameters::parameter::ParameterPlain, Parameter};
+
+    use super::{Enum, EnumParameter};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    enum Waveform {
+        #[default]
+        Sine,
+        Saw,
+        Square,
+    }
+
+    impl Enum for Waveform {
+        const COUNT: usize = 3;
+
+        fn from_hash(hash: u32) -> Option<Self> {
+            Self::from_usize(hash as _)
+        }
+
+        fn from_usize(value: usize) -> Option<Self> {
+            [Self::Sine, Self::Saw, Self::Square].get(value).copied()
+        }
+
+        fn from_string(string: &str) -> Option<Self> {
+            (0..Self::COUNT).filter_map(Self::from_usize).find(|value| Enum::to_string(value) == string)
+        }
+
+        fn hash(&self) -> u32 {
+            self.to_usize() as _
+        }
+
+        fn to_usize(&self) -> usize {
+            *self as _
+        }
+
+        fn to_string(&self) -> String {
+            format!("{self:?}")
+        }
+    }
+
+    #[test]
+    fn enum_parameters_are_lists() {
+        let parameter = EnumParameter::<Waveform>::new(1u32, "Waveform");
+        assert_eq!(parameter.info().steps(), 2);
+        assert!(parameter.info().is_list());
+        assert!(!parameter.info().is_read_only());
+        assert!(parameter.read_only().info().is_read_only());
+    }
+
+    #[test]
+    fn normalized_values_are_clamped_to_the_last_variant() {
+        let parameter = EnumParameter::<Waveform>::new(1u32, "Waveform");
+        assert_eq!(parameter.normalized_to_plain(0.0), Waveform::Sine);
+        assert_eq!(parameter.normalized_to_plain(0.5), Waveform::Saw);
+        assert_eq!(parameter.normalized_to_plain(1.0), Waveform::Square);
+        assert_eq!(parameter.normalized_to_plain(1.5), Waveform::Square);
+        assert_eq!(parameter.normalized_to_plain(-0.5), Waveform::Sine);
+    }
+}
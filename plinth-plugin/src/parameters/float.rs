@@ -5,7 +5,7 @@ use portable_atomic::{AtomicF64, Ordering};
 use crate::error::Error;
 use crate::{Parameter, ParameterId};
 
-use super::{formatter::ParameterFormatter, info::ParameterInfo, parameter::ParameterPlain, range::ParameterRange, ModulationChangedCallback, ParameterValue};
+use super::{formatter::ParameterFormatter, info::{info_builders, ParameterInfo}, parameter::ParameterPlain, range::ParameterRange, ModulationChangedCallback, ParameterValue};
 
 pub const DEFAULT_PRECISION: usize = 2;
 
@@ -39,10 +39,7 @@ impl FloatParameter {
         }
     }
 
-    pub fn with_path(mut self, path: String) -> Self {
-        self.info = self.info.with_path(path);
-        self
-    }
+    info_builders!();
 
     pub fn with_default_value(mut self, value: f64) -> Self {
         let default_normalized_value = self.range.plain_to_normalized(value).unwrap();
@@ -66,16 +63,6 @@ impl FloatParameter {
         self
     }
 
-    pub fn as_output(mut self, output: bool) -> Self {
-        self.info = self.info.as_output(output);
-        self
-    }
-
-    pub fn hidden(mut self) -> Self {
-        self.info = self.info.hidden();
-        self
-    }
-
     pub fn set_value(&self, value: f64) {
         self.value.store(value, Ordering::Release);
 
@@ -154,6 +141,10 @@ impl Parameter for FloatParameter {
     }
 
     fn deserialize_value(&self, value: ParameterValue) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(Error::ParameterRangeError);
+        }
+
         let value = self.range.clamp(value);
         self.set_value(value);
         Ok(())
@@ -0,0 +1,29 @@
+use crate::Color;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutomationState {
+    #[default]
+    None,
+    /// The host has automation for this parameter, but it isn't playing
+    Present,
+    Playing,
+    Recording,
+    /// The host is playing automation, but the user has overridden it
+    Overriding,
+}
+
+/// The parameter is mapped to a host controller
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterMapping {
+    pub color: Option<Color>,
+    pub label: Option<String>,
+    pub description: Option<String>,
+}
+
+/// How the host wants a parameter to be displayed, for example with a colored ring around a knob
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterIndication {
+    pub mapping: Option<ParameterMapping>,
+    pub automation: AutomationState,
+    pub automation_color: Option<Color>,
+}
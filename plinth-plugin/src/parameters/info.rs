@@ -2,15 +2,33 @@ use crate::ParameterId;
 
 use super::ParameterValue;
 
+/// Special function a parameter has in the plugin, lets hosts map them to their own controls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterRole {
+    /// Mix between the unprocessed and processed signal
+    DryWetMix,
+    /// Switches the plugin to a mode with lower latency
+    LowLatency,
+    /// Randomizes the plugin settings when changed
+    Randomize,
+    /// Current gain reduction of a dynamics processor, always read-only
+    GainReduction,
+}
+
 #[derive(Clone)]
 pub struct ParameterInfo {
     id: ParameterId,
     name: String,
+    short_name: Option<String>,
+    unit: String,
+    role: Option<ParameterRole>,
     path: String,
     default_normalized_value: ParameterValue,
     steps: usize,
     is_bypass: bool,
     is_output: bool,
+    is_list: bool,
+    read_only: bool,
     visible: bool,
 }
 
@@ -19,11 +37,16 @@ impl ParameterInfo {
         Self {
             id,
             name,
+            short_name: None,
+            unit: Default::default(),
+            role: None,
             path: Default::default(),
             default_normalized_value: Default::default(),
             steps: 0,
             is_bypass: false,
             is_output: true,
+            is_list: false,
+            read_only: false,
             visible: true,
         }
     }
@@ -33,6 +56,27 @@ impl ParameterInfo {
         self
     }
 
+    /// Name for hosts with little room to display it, for example on control surfaces
+    /// Only used by VST3, CLAP has no short names
+    pub fn with_short_name(mut self, short_name: String) -> Self {
+        self.short_name = Some(short_name);
+        self
+    }
+
+    /// Unit label shown by hosts next to the value, for example "dB"
+    /// Only used by VST3, CLAP hosts show the formatted value so the formatter has to include the unit
+    pub fn with_unit(mut self, unit: String) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Only used by VST3, CLAP has no equivalent flags
+    /// [`ParameterRole::GainReduction`] makes the parameter read-only in all formats.
+    pub fn with_role(mut self, role: ParameterRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
     pub fn with_default_normalized_value(mut self, value: ParameterValue) -> Self {
         self.default_normalized_value = value;
         self
@@ -55,6 +99,18 @@ impl ParameterInfo {
         self
     }
 
+    /// Stepped parameters with a name for each value, shown as a list by hosts
+    pub fn as_list(mut self) -> Self {
+        self.is_list = true;
+        self
+    }
+
+    /// Read-only parameters are only changed by the plugin, for example meters
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
     pub fn hidden(mut self) -> Self {
         self.visible = false;
         self
@@ -68,6 +124,19 @@ impl ParameterInfo {
         &self.name
     }
 
+    /// Short name, or the name if no short name was given
+    pub fn short_name(&self) -> &str {
+        self.short_name.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    pub fn role(&self) -> Option<ParameterRole> {
+        self.role
+    }
+
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -88,7 +157,88 @@ impl ParameterInfo {
         self.is_output
     }
 
+    pub fn is_list(&self) -> bool {
+        self.is_list && self.steps > 0
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.role == Some(ParameterRole::GainReduction)
+    }
+
     pub fn visible(&self) -> bool {
         self.visible
     }
 }
+
+/// Builder methods of the parameter types that only forward to their [`ParameterInfo`]
+///
+/// The methods are documented on [`ParameterInfo`], the forwarders only link there.
+/// Files using the macro have to import [`ParameterInfo`] for the links.
+macro_rules! info_builders {
+    () => {
+        /// See [`ParameterInfo::with_path`]
+        pub fn with_path(mut self, path: String) -> Self {
+            self.info = self.info.with_path(path);
+            self
+        }
+
+        /// See [`ParameterInfo::with_short_name`]
+        pub fn with_short_name(mut self, short_name: impl Into<String>) -> Self {
+            self.info = self.info.with_short_name(short_name.into());
+            self
+        }
+
+        /// See [`ParameterInfo::with_unit`]
+        pub fn with_unit(mut self, unit: impl Into<String>) -> Self {
+            self.info = self.info.with_unit(unit.into());
+            self
+        }
+
+        /// See [`ParameterInfo::with_role`]
+        pub fn with_role(mut self, role: $crate::ParameterRole) -> Self {
+            self.info = self.info.with_role(role);
+            self
+        }
+
+        /// See [`ParameterInfo::as_output`]
+        pub fn as_output(mut self, output: bool) -> Self {
+            self.info = self.info.as_output(output);
+            self
+        }
+
+        /// See [`ParameterInfo::read_only`]
+        pub fn read_only(mut self) -> Self {
+            self.info = self.info.read_only();
+            self
+        }
+
+        /// See [`ParameterInfo::hidden`]
+        pub fn hidden(mut self) -> Self {
+            self.info = self.info.hidden();
+            self
+        }
+    };
+}
+
+pub(crate) use info_builders;
+
+#[cfg(test)]
+mod tests {
+    use super::{ParameterInfo, ParameterRole};
+
+    #[test]
+    fn only_stepped_parameters_are_lists() {
+        assert!(!ParameterInfo::new(1, "Gain".into()).as_list().is_list());
+        assert!(ParameterInfo::new(1, "Mode".into()).with_steps(3).as_list().is_list());
+        assert!(!ParameterInfo::new(1, "Mode".into()).with_steps(3).is_list());
+    }
+
+    #[test]
+    fn read_only_parameters() {
+        assert!(!ParameterInfo::new(1, "Gain".into()).is_read_only());
+        assert!(ParameterInfo::new(1, "Meter".into()).read_only().is_read_only());
+
+        // Gain reduction is always reported by the plugin
+        assert!(ParameterInfo::new(1, "Gain reduction".into()).with_role(ParameterRole::GainReduction).is_read_only());
+    }
+}
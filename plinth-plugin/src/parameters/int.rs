@@ -4,7 +4,7 @@ use portable_atomic::{AtomicF64, Ordering};
 
 use crate::{error::Error, ParameterId};
 
-use super::{formatter::ParameterFormatter, info::ParameterInfo, parameter::{Parameter, ParameterPlain}, range::ParameterRange, ModulationChangedCallback, ParameterValue};
+use super::{formatter::ParameterFormatter, info::{info_builders, ParameterInfo}, parameter::{Parameter, ParameterPlain}, range::ParameterRange, ModulationChangedCallback, ParameterValue};
 
 pub type ValueChangedCallback = Arc<dyn Fn(ParameterId, i64) + Send + Sync>;
 
@@ -38,10 +38,7 @@ impl IntParameter {
         }
     }
 
-    pub fn with_path(mut self, path: String) -> Self {
-        self.info = self.info.with_path(path);
-        self
-    }
+    info_builders!();
 
     pub fn with_default_value(mut self, value: i64) -> Self {
         let default_normalized_value = self.range.plain_to_normalized(value).unwrap();
@@ -65,13 +62,9 @@ impl IntParameter {
         self
     }
 
-    pub fn as_output(mut self, output: bool) -> Self {
-        self.info = self.info.as_output(output);
-        self
-    }
-
-    pub fn hidden(mut self) -> Self {
-        self.info = self.info.hidden();
+    /// Lets hosts show the formatted values in a list instead of a slider
+    pub fn as_list(mut self) -> Self {
+        self.info = self.info.as_list();
         self
     }
 
@@ -1,6 +1,6 @@
 use std::{io::{Read, Write}, rc::Rc};
 
-use crate::{error::Error, host::HostInfo, processor::ProcessorConfig, Editor, Event, Host, Parameters, Processor};
+use crate::{error::Error, host::HostInfo, processor::ProcessorConfig, Editor, Event, Host, MidiCcAssignment, MidiLearn, Parameters, Processor, StateContext, TrackInfo};
 
 pub trait Plugin {
     const NAME: &'static str;
@@ -13,6 +13,12 @@ pub trait Plugin {
     const HAS_NOTE_INPUT: bool = false;
     const HAS_NOTE_OUTPUT: bool = false;
 
+    /// Set to forward `log` records at this level to the host if it supports logging, otherwise to a log file
+    /// in the temporary directory. This has no effect if another global logger has already been installed.
+    /// Records can't be told apart by instance, so the oldest instance in a process receives the records of all
+    /// instances. When it's destroyed, the next oldest instance takes over.
+    const LOG_LEVEL: Option<log::LevelFilter> = None;
+
     type Processor: Processor;
     type Editor: Editor;
     type Parameters: Parameters;
@@ -26,10 +32,39 @@ pub trait Plugin {
     fn create_processor(&mut self, config: ProcessorConfig) -> Self::Processor;
     fn create_editor(&mut self, host: Rc<dyn Host>) -> Self::Editor;
 
-    fn save_state(&self, writer: &mut impl Write) -> Result<(), Error>;
-    fn load_state(&mut self, reader: &mut impl Read) -> Result<(), Error>;
+    /// The context can be used to leave out machine-specific data, such as file paths, from presets
+    fn save_state(&self, writer: &mut impl Write, context: StateContext) -> Result<(), Error>;
+    fn load_state(&mut self, reader: &mut impl Read, context: StateContext) -> Result<(), Error>;
+
+    /// Editor-only settings, such as UI scale, that don't affect processing
+    /// These are stored separately from the processing state and aren't part of presets in every format
+    /// In formats with a single state, like CLAP, writing editor state adds a header to the state, so builds
+    /// of the plugin from before it had editor state can't load it
+    fn save_editor_state(&self, _writer: &mut impl Write) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn load_editor_state(&mut self, _reader: &mut impl Read) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Default MIDI CC to parameter assignments
+    /// Hosts may let the user override these, and CCs arriving as raw MIDI are mapped by plinth
+    fn midi_cc_assignments(&self) -> Vec<MidiCcAssignment> {
+        Vec::new()
+    }
+
+    /// MIDI learn service used by the plugin
+    /// The wrappers route control changes to it and save its assignments with the editor state.
+    /// Learned assignments take precedence over [`Plugin::midi_cc_assignments`].
+    fn midi_learn(&self) -> Option<&MidiLearn> {
+        None
+    }
 
     fn latency(&self) -> u32 {
         0
     }
+
+    /// Called when the host reports new information about the track the plugin is on
+    fn track_info_changed(&mut self, _info: &TrackInfo) {}
 }
@@ -1,6 +1,6 @@
 use plinth_core::signals::signal::{Signal, SignalMut};
 
-use crate::{event::Event, transport::Transport};
+use crate::{event::Event, thread_pool::ThreadPool, transport::Transport};
 
 #[derive(Clone, Default)]
 pub struct ProcessorConfig {
@@ -8,6 +8,7 @@ pub struct ProcessorConfig {
     pub min_block_size: usize,
     pub max_block_size: usize,
     pub process_mode: ProcessMode,
+    pub thread_pool: ThreadPool,
 }
 
 #[derive(Clone, Copy, Default, PartialEq)]
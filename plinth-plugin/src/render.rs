@@ -0,0 +1,462 @@
+use std::path::{Path, PathBuf};
+
+use midly::{MetaMessage, Smf, Timing, TrackEventKind};
+use plinth_core::{buffers::buffer::Buffer, signals::{signal::{Signal, SignalMut}, signal_base::SignalBase}};
+use serde::Deserialize;
+
+use crate::{formats::PluginFormat, midi::{assigned_parameter, parse_midi}, Event, HostInfo, ParameterId, ParameterValue, Plugin, ProcessMode, Processor, ProcessorConfig, Transport};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("Unsupported channel count {0}, only mono and stereo files are supported")]
+    UnsupportedChannelCount(u16),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("WAV error: {0}")]
+    Wav(#[from] hound::Error),
+    #[error("MIDI file error: {0}")]
+    Midi(#[from] midly::Error),
+    #[error("Automation error: {0}")]
+    Automation(#[from] serde_json::Error),
+}
+
+/// Parameter change in an automation script, `time` is in seconds and `value` is normalized
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AutomationPoint {
+    pub id: ParameterId,
+    pub time: f64,
+    pub value: ParameterValue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockSize {
+    Fixed(usize),
+    /// Block sizes drawn from `min..=max`, the same seed always gives the same sizes
+    Random {
+        min: usize,
+        max: usize,
+        seed: u64,
+    },
+}
+
+impl BlockSize {
+    fn min(&self) -> usize {
+        match *self {
+            BlockSize::Fixed(size) => size,
+            BlockSize::Random { min, .. } => min,
+        }
+    }
+
+    fn max(&self) -> usize {
+        match *self {
+            BlockSize::Fixed(size) => size,
+            BlockSize::Random { max, .. } => max,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderConfig {
+    pub block_size: BlockSize,
+    /// Seconds to keep processing after the input ends
+    pub tail: f64,
+    pub tempo: f64,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            block_size: BlockSize::Fixed(512),
+            tail: 0.0,
+            tempo: 120.0,
+        }
+    }
+}
+
+/// Renders a stereo buffer through a new instance of the plugin in offline mode
+///
+/// Event sample offsets are counted from the start of the input and get moved to the block they fall in.
+/// Control changes the plugin assigns to parameters are sent as parameter changes, like hosts do.
+/// Events also reach [`Plugin::process_event`] before their block is processed, so the plugin's own
+/// parameters follow the automation.
+pub fn render<P: Plugin>(input: &Buffer, sample_rate: f64, events: &[Event], config: &RenderConfig) -> Buffer {
+    assert_eq!(input.channels(), 2, "Only stereo buffers are supported");
+    assert!(config.block_size.min() > 0 && config.block_size.min() <= config.block_size.max(), "Invalid block size");
+
+    let host_info = HostInfo {
+        name: Some("Render".to_string()),
+        format: PluginFormat::Render,
+        bundle_path: None,
+    };
+
+    let mut plugin = P::new(host_info);
+    plugin.init();
+
+    let midi_cc_assignments = plugin.midi_cc_assignments();
+
+    let mut events: Vec<_> = events.iter()
+        .map(|event| match *event {
+            Event::ControlChange { sample_offset, channel, controller, value } => match assigned_parameter(&midi_cc_assignments, channel, controller) {
+                Some(id) => Event::ParameterValue { sample_offset, id, value },
+                None => event.clone(),
+            },
+            _ => event.clone(),
+        })
+        .collect();
+
+    // Stable, so events at the same time keep their order
+    events.sort_by_key(Event::sample_offset);
+
+    let processor_config = ProcessorConfig {
+        sample_rate,
+        // The last block can be shorter than the others
+        min_block_size: 1,
+        max_block_size: config.block_size.max(),
+        process_mode: ProcessMode::Offline,
+        thread_pool: Default::default(),
+    };
+
+    let mut processor = plugin.create_processor(processor_config);
+    processor.reset();
+
+    let tail_length = (config.tail * sample_rate).round() as usize;
+    let length = input.len() + tail_length;
+
+    let mut output = Buffer::new(2, length);
+    output.slice_mut(..input.len()).copy_from_signal(input);
+
+    let mut rng = match config.block_size {
+        BlockSize::Random { seed, .. } => Some(fastrand::Rng::with_seed(seed)),
+        BlockSize::Fixed(_) => None,
+    };
+
+    let mut events = events.into_iter().peekable();
+    let mut position = 0;
+
+    while position < length {
+        let block_size = match (config.block_size, rng.as_mut()) {
+            (BlockSize::Random { min, max, .. }, Some(rng)) => rng.usize(min..=max),
+            _ => config.block_size.max(),
+        };
+        let block_end = usize::min(position + block_size, length);
+
+        let mut block_events = Vec::new();
+        while let Some(event) = events.next_if(|event| event.sample_offset() < block_end) {
+            block_events.push(event.with_sample_offset(|sample_offset| sample_offset.saturating_sub(position)));
+        }
+
+        for event in block_events.iter() {
+            plugin.process_event(event);
+        }
+
+        let transport = Transport::new(true, config.tempo, position as _);
+        let aux: Option<&Buffer> = None;
+        processor.process(&mut output.slice_mut(position..block_end), aux, Some(transport), block_events.into_iter());
+
+        position = block_end;
+    }
+
+    output
+}
+
+/// Renders a WAV file through the plugin to another WAV file
+pub fn render_file<P: Plugin>(
+    input_path: &Path,
+    output_path: &Path,
+    midi_path: Option<&Path>,
+    automation_path: Option<&Path>,
+    config: &RenderConfig,
+) -> Result<(), RenderError> {
+    let (input, sample_rate) = read_wav(input_path)?;
+
+    let mut events = Vec::new();
+    if let Some(midi_path) = midi_path {
+        events.extend(read_midi_file(midi_path, sample_rate)?);
+    }
+    if let Some(automation_path) = automation_path {
+        events.extend(read_automation(automation_path, sample_rate)?);
+    }
+
+    let output = render::<P>(&input, sample_rate, &events, config);
+    write_wav(output_path, &output, sample_rate)
+}
+
+/// Reads a mono or stereo WAV file as a stereo buffer, returns the buffer and its sample rate
+pub fn read_wav(path: &Path) -> Result<(Buffer, f64), RenderError> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    if spec.channels != 1 && spec.channels != 2 {
+        return Err(RenderError::UnsupportedChannelCount(spec.channels));
+    }
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect::<Result<_, _>>()?
+        },
+    };
+
+    let channels = spec.channels as usize;
+    let mut buffer = Buffer::new(2, samples.len() / channels);
+
+    for (index, frame) in samples.chunks_exact(channels).enumerate() {
+        buffer.channel_mut(0)[index] = frame[0];
+        buffer.channel_mut(1)[index] = frame[channels - 1];
+    }
+
+    Ok((buffer, spec.sample_rate as _))
+}
+
+/// Writes a buffer as a 32-bit float WAV file
+pub fn write_wav(path: &Path, buffer: &Buffer, sample_rate: f64) -> Result<(), RenderError> {
+    let spec = hound::WavSpec {
+        channels: buffer.channels() as _,
+        sample_rate: sample_rate as _,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for sample in buffer.iter_interleaved() {
+        writer.write_sample(*sample)?;
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Reads the note, control change and pitch bend events of all tracks in a standard MIDI file
+pub fn read_midi_file(path: &Path, sample_rate: f64) -> Result<Vec<Event>, RenderError> {
+    let data = std::fs::read(path)?;
+    let smf = Smf::parse(&data)?;
+
+    // Tempo changes apply to all tracks, so merge the tracks before converting ticks to time
+    let mut track_events = Vec::new();
+    for track in smf.tracks.iter() {
+        let mut ticks = 0_u64;
+
+        for event in track.iter() {
+            ticks += event.delta.as_int() as u64;
+            track_events.push((ticks, event.kind));
+        }
+    }
+
+    track_events.sort_by_key(|(ticks, _)| *ticks);
+
+    // Microseconds per beat, 120 BPM until the first tempo change
+    let mut tempo = 500_000.0;
+    let mut last_ticks = 0;
+    let mut seconds = 0.0;
+
+    let mut events = Vec::new();
+    let mut midi_data = Vec::new();
+
+    for (ticks, kind) in track_events {
+        let seconds_per_tick = match smf.header.timing {
+            Timing::Metrical(ticks_per_beat) => tempo / 1_000_000.0 / ticks_per_beat.as_int() as f64,
+            Timing::Timecode(fps, subframes) => 1.0 / (fps.as_f32() as f64 * subframes as f64),
+        };
+
+        seconds += (ticks - last_ticks) as f64 * seconds_per_tick;
+        last_ticks = ticks;
+
+        if let TrackEventKind::Meta(MetaMessage::Tempo(new_tempo)) = kind {
+            tempo = new_tempo.as_int() as f64;
+            continue;
+        }
+
+        let Some(live_event) = kind.as_live_event() else {
+            continue;
+        };
+
+        midi_data.clear();
+        live_event.write_std(&mut midi_data)?;
+
+        let sample_offset = (seconds * sample_rate).round() as usize;
+        events.extend(parse_midi(&midi_data, sample_offset, &[], None));
+    }
+
+    Ok(events)
+}
+
+/// Reads an automation script, a JSON list of [`AutomationPoint`]s
+pub fn read_automation(path: &Path, sample_rate: f64) -> Result<Vec<Event>, RenderError> {
+    let data = std::fs::read(path)?;
+    let points: Vec<AutomationPoint> = serde_json::from_slice(&data)?;
+
+    Ok(automation_events(&points, sample_rate))
+}
+
+pub fn automation_events(points: &[AutomationPoint], sample_rate: f64) -> Vec<Event> {
+    points.iter()
+        .map(|point| Event::ParameterValue {
+            sample_offset: (point.time.max(0.0) * sample_rate).round() as usize,
+            id: point.id,
+            value: point.value,
+        })
+        .collect()
+}
+
+const USAGE: &str = "\
+Usage: INPUT.wav OUTPUT.wav [options]
+
+Options:
+  --midi FILE.mid               Send the notes and controllers of a MIDI file
+  --automation FILE.json        Send parameter changes from a JSON list of {\"id\", \"time\", \"value\"} objects
+  --block-size SIZE             Process in blocks of SIZE samples (default 512)
+  --random-block-size MIN MAX   Process in blocks of random size between MIN and MAX samples
+  --seed SEED                   Seed for random block sizes (default 0)
+  --tail SECONDS                Keep processing after the input ends";
+
+/// Runs an offline render from command line arguments, for use as the main function of a binary
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use plinth_plugin::render::run_render;
+///
+/// fn main() {
+///     run_render::<MyPlugin>();
+/// }
+/// ```
+pub fn run_render<P: Plugin>() {
+    let result = parse_args(std::env::args().skip(1))
+        .and_then(|args| render_file::<P>(&args.input, &args.output, args.midi.as_deref(), args.automation.as_deref(), &args.config));
+
+    match result {
+        Ok(()) => {},
+        Err(RenderError::Usage(message)) => {
+            eprintln!("{message}\n\n{USAGE}");
+            std::process::exit(2);
+        },
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        },
+    }
+}
+
+struct RenderArgs {
+    input: PathBuf,
+    output: PathBuf,
+    midi: Option<PathBuf>,
+    automation: Option<PathBuf>,
+    config: RenderConfig,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<RenderArgs, RenderError> {
+    fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, option: &str) -> Result<T, RenderError> {
+        args.next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| RenderError::Usage(format!("Missing or invalid value for {option}")))
+    }
+
+    let mut paths = Vec::new();
+    let mut midi = None;
+    let mut automation = None;
+    let mut config = RenderConfig::default();
+    let mut random_block_size = None;
+    let mut seed = 0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--midi" => midi = Some(value(&mut args, &arg)?),
+            "--automation" => automation = Some(value(&mut args, &arg)?),
+            "--block-size" => config.block_size = BlockSize::Fixed(value(&mut args, &arg)?),
+            "--random-block-size" => random_block_size = Some((value(&mut args, &arg)?, value(&mut args, &arg)?)),
+            "--seed" => seed = value(&mut args, &arg)?,
+            "--tail" => config.tail = value(&mut args, &arg)?,
+            _ if arg.starts_with("--") => return Err(RenderError::Usage(format!("Unknown option {arg}"))),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if let Some((min, max)) = random_block_size {
+        config.block_size = BlockSize::Random { min, max, seed };
+    }
+
+    if config.block_size.min() == 0 || config.block_size.min() > config.block_size.max() {
+        return Err(RenderError::Usage("Invalid block size".to_string()));
+    }
+
+    let [input, output] = <[PathBuf; 2]>::try_from(paths)
+        .map_err(|_| RenderError::Usage("Expected an input and an output file".to_string()))?;
+
+    Ok(RenderArgs {
+        input,
+        output,
+        midi,
+        automation,
+        config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use plinth_core::{buffers::buffer::Buffer, signals::{signal::Signal, signal_base::SignalBase}};
+
+    use crate::{testing::test_plugin::{plugin_events, TestPlugin, GAIN}, Event};
+
+    use super::{automation_events, parse_args, render, AutomationPoint, BlockSize, RenderConfig};
+
+    #[test]
+    fn automation_is_sample_accurate_at_block_boundaries() {
+        let input = Buffer::from(vec![vec![1.0; 256]; 2]);
+        let events = automation_events(&[AutomationPoint { id: GAIN, time: 128.0 / 48000.0, value: 0.5 }], 48000.0);
+
+        let config = RenderConfig {
+            block_size: BlockSize::Fixed(64),
+            tail: 64.0 / 48000.0,
+            ..Default::default()
+        };
+
+        let output = render::<TestPlugin>(&input, 48000.0, &events, &config);
+        assert_eq!(output.len(), 320);
+
+        for channel in output.iter_channels() {
+            assert!(channel[..128].iter().all(|&sample| sample == 1.0));
+            assert!(channel[128..256].iter().all(|&sample| sample == 0.5));
+            assert!(channel[256..].iter().all(|&sample| sample == 0.0));
+        }
+
+        // The plugin sees the automation as well as the processor
+        assert!(matches!(plugin_events()[..], [Event::ParameterValue { id: GAIN, value, .. }] if value == 0.5));
+    }
+
+    #[test]
+    fn random_block_sizes_are_reproducible() {
+        let input = Buffer::from(vec![(0..1000).map(|index| index as f32 / 1000.0).collect(); 2]);
+        let events = [Event::ControlChange { sample_offset: 500, channel: 0, controller: 7, value: 0.25 }];
+
+        let config = RenderConfig {
+            block_size: BlockSize::Random { min: 1, max: 100, seed: 42 },
+            ..Default::default()
+        };
+
+        let output = render::<TestPlugin>(&input, 44100.0, &events, &config);
+        assert!(output == render::<TestPlugin>(&input, 44100.0, &events, &config));
+
+        // The assigned CC 7 reaches the gain in the block containing sample 500
+        assert_eq!(output.channel(0)[999], 0.999 * 0.25);
+    }
+
+    #[test]
+    fn args() {
+        let args = ["in.wav", "out.wav", "--random-block-size", "16", "32", "--seed", "7", "--tail", "1.5"].map(String::from);
+        let args = parse_args(args.into_iter()).unwrap();
+
+        assert_eq!(args.input.to_str(), Some("in.wav"));
+        assert_eq!(args.output.to_str(), Some("out.wav"));
+        assert_eq!(args.config.block_size, BlockSize::Random { min: 16, max: 32, seed: 7 });
+        assert_eq!(args.config.tail, 1.5);
+
+        assert!(parse_args(["in.wav"].map(String::from).into_iter()).is_err());
+        assert!(parse_args(["in.wav", "out.wav", "--block-size", "0"].map(String::from).into_iter()).is_err());
+    }
+}
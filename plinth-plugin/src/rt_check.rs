@@ -0,0 +1,291 @@
+//! Realtime safety checks for the audio thread
+//!
+//! With the `rt-check` feature, the format wrappers run [`Processor::process`](crate::Processor::process) and
+//! [`Processor::process_events`](crate::Processor::process_events) in a realtime section, and the crate installs
+//! a global allocator that watches for heap allocation and deallocation inside one. Only heap allocation is
+//! detected on its own: locks and other blocking calls aren't, unless the code making them calls [`blocking`] first.
+//!
+//! The first violation in a section is reported with a backtrace when the section ends, by logging an error
+//! or by panicking, see [`set_violation_mode`] and [`with_violation_mode`]. The wrappers run sections inside
+//! calls from the host, where a panic can't unwind, so logging is the default. It's a debugging aid: the
+//! feature replaces the global allocator, so it can't be combined with another `#[global_allocator]`. Without
+//! the feature, everything here does nothing.
+
+#[cfg(feature = "rt-check")]
+use std::{alloc::{GlobalAlloc, Layout, System}, backtrace::Backtrace, cell::RefCell};
+use std::{cell::Cell, sync::atomic::{AtomicBool, Ordering}};
+
+static PANIC_ON_VIOLATIONS: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static VIOLATION_MODE: Cell<Option<ViolationMode>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViolationMode {
+    #[default]
+    Log,
+    /// Only for sections that can unwind, like in tests that call a processor directly
+    Panic,
+}
+
+/// Sets how violations are reported, for all threads
+pub fn set_violation_mode(mode: ViolationMode) {
+    PANIC_ON_VIOLATIONS.store(mode == ViolationMode::Panic, Ordering::Relaxed);
+}
+
+/// Runs `f` with violations on this thread reported according to `mode`, whatever the global mode is
+pub fn with_violation_mode<T>(mode: ViolationMode, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<ViolationMode>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            VIOLATION_MODE.set(self.0);
+        }
+    }
+
+    let _restore = Restore(VIOLATION_MODE.replace(Some(mode)));
+    f()
+}
+
+#[cfg(feature = "rt-check")]
+fn violation_mode() -> ViolationMode {
+    VIOLATION_MODE.get().unwrap_or(if PANIC_ON_VIOLATIONS.load(Ordering::Relaxed) {
+        ViolationMode::Panic
+    } else {
+        ViolationMode::Log
+    })
+}
+
+/// Runs `f` in a realtime section
+///
+/// The wrappers do this around processing, it's only needed when calling a processor directly.
+pub fn realtime<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "rt-check")]
+    {
+        let section = Section::enter();
+        let result = f();
+        drop(section);
+
+        report_violation();
+        result
+    }
+
+    #[cfg(not(feature = "rt-check"))]
+    f()
+}
+
+/// Runs `f` without checks, for code that's known to be safe even though it allocates or blocks
+pub fn permit<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "rt-check")]
+    let _suspend = Suspend::enter();
+
+    f()
+}
+
+/// Marks a blocking call like locking a mutex, which is a violation in a realtime section
+///
+/// Call it right before the blocking call, for example in a processor that shares state with the editor
+/// through a lock.
+pub fn blocking(call: &str) {
+    #[cfg(feature = "rt-check")]
+    check(call);
+
+    #[cfg(not(feature = "rt-check"))]
+    let _ = call;
+}
+
+#[cfg(feature = "rt-check")]
+thread_local! {
+    // Number of nested realtime sections
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    // Set while recording a violation or running permitted code
+    static SUSPENDED: Cell<bool> = const { Cell::new(false) };
+    // Set while capturing a backtrace, which allocates and takes the std backtrace lock
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+    static VIOLATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+#[cfg(feature = "rt-check")]
+struct Section;
+
+#[cfg(feature = "rt-check")]
+impl Section {
+    fn enter() -> Self {
+        let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+
+        // A panic can leave a violation behind from an earlier section
+        if depth == 0 {
+            let _suspend = Suspend::enter();
+            VIOLATION.with(|violation| violation.borrow_mut().take());
+        }
+
+        Self
+    }
+}
+
+#[cfg(feature = "rt-check")]
+impl Drop for Section {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+#[cfg(feature = "rt-check")]
+struct Suspend(bool);
+
+#[cfg(feature = "rt-check")]
+impl Suspend {
+    fn enter() -> Self {
+        Self(SUSPENDED.try_with(|suspended| suspended.replace(true)).unwrap_or(true))
+    }
+}
+
+#[cfg(feature = "rt-check")]
+impl Drop for Suspend {
+    fn drop(&mut self) {
+        let _ = SUSPENDED.try_with(|suspended| suspended.set(self.0));
+    }
+}
+
+#[cfg(feature = "rt-check")]
+struct Capture(bool);
+
+#[cfg(feature = "rt-check")]
+impl Capture {
+    fn enter() -> Self {
+        Self(CAPTURING.try_with(|capturing| capturing.replace(true)).unwrap_or(true))
+    }
+}
+
+#[cfg(feature = "rt-check")]
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = CAPTURING.try_with(|capturing| capturing.set(self.0));
+    }
+}
+
+// This runs inside the allocator, so it can't panic or take locks. Thread locals might already be gone
+// when a thread exits, which counts as not being in a realtime section.
+#[cfg(feature = "rt-check")]
+fn check(what: &str) {
+    let in_section = DEPTH.try_with(Cell::get).unwrap_or(0) > 0;
+    if !in_section || SUSPENDED.try_with(Cell::get).unwrap_or(true) {
+        return;
+    }
+
+    // A panic in a section holds the backtrace lock while printing, and capturing again from
+    // inside a capture would deadlock on it, so neither records a violation
+    if std::thread::panicking() || CAPTURING.try_with(Cell::get).unwrap_or(true) {
+        return;
+    }
+
+    let _suspend = Suspend::enter();
+    let _capture = Capture::enter();
+    let _ = VIOLATION.try_with(|violation| {
+        let mut violation = violation.borrow_mut();
+        if violation.is_none() {
+            *violation = Some(format!("{what} in a realtime section\n{}", Backtrace::force_capture()));
+        }
+    });
+}
+
+#[cfg(feature = "rt-check")]
+fn report_violation() {
+    if DEPTH.with(Cell::get) > 0 {
+        return;
+    }
+
+    let _suspend = Suspend::enter();
+    let Some(violation) = VIOLATION.with(|violation| violation.borrow_mut().take()) else {
+        return;
+    };
+
+    match violation_mode() {
+        ViolationMode::Log => log::error!("{violation}"),
+        ViolationMode::Panic => panic!("{violation}"),
+    }
+}
+
+#[cfg(feature = "rt-check")]
+struct CheckingAllocator;
+
+#[cfg(feature = "rt-check")]
+unsafe impl GlobalAlloc for CheckingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        check("Heap allocation");
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        check("Heap allocation");
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        check("Heap reallocation");
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        check("Heap deallocation");
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[cfg(feature = "rt-check")]
+#[global_allocator]
+static ALLOCATOR: CheckingAllocator = CheckingAllocator;
+
+#[cfg(all(test, feature = "rt-check"))]
+mod tests {
+    use std::panic::catch_unwind;
+
+    use super::{blocking, permit, realtime, with_violation_mode, ViolationMode, VIOLATION};
+
+    #[test]
+    fn allocation_panics() {
+        let result = catch_unwind(|| with_violation_mode(ViolationMode::Panic, || realtime(|| drop(std::hint::black_box(vec![0u8; 16])))));
+
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.starts_with("Heap allocation in a realtime section"));
+    }
+
+    #[test]
+    fn blocking_panics() {
+        let result = catch_unwind(|| with_violation_mode(ViolationMode::Panic, || realtime(|| blocking("Mutex lock"))));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn violations_are_logged_by_default() {
+        realtime(|| drop(std::hint::black_box(vec![0u8; 16])));
+    }
+
+    #[test]
+    fn allocating_while_panicking_is_not_recorded() {
+        struct AllocateOnDrop;
+
+        impl Drop for AllocateOnDrop {
+            fn drop(&mut self) {
+                drop(std::hint::black_box(vec![0u8; 16]));
+            }
+        }
+
+        let result = catch_unwind(|| with_violation_mode(ViolationMode::Panic, || realtime(|| {
+            let _allocate = AllocateOnDrop;
+            std::panic::panic_any(7);
+        })));
+        assert_eq!(*result.unwrap_err().downcast::<i32>().unwrap(), 7);
+        assert!(VIOLATION.with_borrow(Option::is_none));
+    }
+
+    #[test]
+    fn permitted_and_outside_are_allowed() {
+        let mut data = realtime(|| permit(|| vec![0u8; 16]));
+        data.push(1);
+
+        realtime(|| data.iter_mut().for_each(|value| *value += 1));
+        assert_eq!(data[16], 2);
+    }
+}
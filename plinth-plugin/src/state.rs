@@ -0,0 +1,201 @@
+use std::io::{Read, Write};
+
+use crate::{Error, Plugin};
+
+/// Why the host is saving or loading plugin state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StateContext {
+    /// State is stored as part of a project/session
+    #[default]
+    Project,
+    /// State is stored as a preset that may be loaded on another machine
+    Preset,
+    /// State is used to duplicate a plugin instance, for example when copying a track
+    Duplicate,
+}
+
+// Header for state that carries editor state, in formats with a single state blob:
+// [magic][version][plugin state length as u64 LE][plugin state][editor state]
+// Plugins without editor state write their plugin state as is.
+const STATE_HEADER_MAGIC: &[u8; 8] = b"plnthSTA";
+const STATE_HEADER_VERSION: u8 = 1;
+
+/// Saves plugin state and editor state as a single blob, for formats with a single state like CLAP
+pub(crate) fn save_plugin_state<P: Plugin>(plugin: &P, writer: &mut impl Write, context: StateContext) -> Result<(), Error> {
+    let mut state = Vec::new();
+    plugin.save_state(&mut state, context)?;
+
+    let mut editor_state = Vec::new();
+    save_editor_state(plugin, &mut editor_state)?;
+
+    if !editor_state.is_empty() {
+        writer.write_all(STATE_HEADER_MAGIC)?;
+        writer.write_all(&[STATE_HEADER_VERSION])?;
+        writer.write_all(&(state.len() as u64).to_le_bytes())?;
+    }
+
+    writer.write_all(&state)?;
+    writer.write_all(&editor_state)?;
+
+    Ok(())
+}
+
+/// Loads state saved with [`save_plugin_state`], or plain plugin state without a header
+pub(crate) fn load_plugin_state<P: Plugin>(plugin: &mut P, reader: &mut impl Read, context: StateContext) -> Result<(), Error> {
+    let mut state = Vec::new();
+    reader.read_to_end(&mut state)?;
+
+    let (mut plugin_state, editor_state) = split_state(&state)?;
+    plugin.load_state(&mut plugin_state, context)?;
+
+    if let Some(mut editor_state) = editor_state {
+        load_editor_state(plugin, &mut editor_state)?;
+    }
+
+    Ok(())
+}
+
+// Plugins with MIDI learn store the learned assignments next to their editor state, because they're
+// controller-side state in VST3:
+// [editor state length as u64 LE][editor state][learned assignments]
+
+/// Saves editor state, and the assignments learned by the plugin's MIDI learn service if it has one
+pub(crate) fn save_editor_state<P: Plugin>(plugin: &P, writer: &mut impl Write) -> Result<(), Error> {
+    let Some(midi_learn) = plugin.midi_learn() else {
+        return plugin.save_editor_state(writer);
+    };
+
+    let mut editor_state = Vec::new();
+    plugin.save_editor_state(&mut editor_state)?;
+
+    // Keep the state empty if there's nothing to store, so it's saved without a header
+    if editor_state.is_empty() && midi_learn.assignments().is_empty() {
+        return Ok(());
+    }
+
+    writer.write_all(&(editor_state.len() as u64).to_le_bytes())?;
+    writer.write_all(&editor_state)?;
+    midi_learn.save(writer)
+}
+
+/// Loads state saved with [`save_editor_state`]
+pub(crate) fn load_editor_state<P: Plugin>(plugin: &mut P, reader: &mut impl Read) -> Result<(), Error> {
+    if plugin.midi_learn().is_none() {
+        return plugin.load_editor_state(reader);
+    }
+
+    let mut state = Vec::new();
+    reader.read_to_end(&mut state)?;
+
+    // Saved without editor state or learned assignments, or an empty state from the host
+    if state.is_empty() {
+        if let Some(midi_learn) = plugin.midi_learn() {
+            midi_learn.set_assignments([]);
+        }
+
+        return plugin.load_editor_state(&mut state.as_slice());
+    }
+
+    let Some((length, rest)) = state.split_first_chunk::<{ size_of::<u64>() }>() else {
+        return Err(Error::SerializationError);
+    };
+
+    let (mut editor_state, mut midi_learn_state) = usize::try_from(u64::from_le_bytes(*length)).ok()
+        .and_then(|length| rest.split_at_checked(length))
+        .ok_or(Error::SerializationError)?;
+
+    plugin.load_editor_state(&mut editor_state)?;
+
+    match plugin.midi_learn() {
+        Some(midi_learn) => midi_learn.load(&mut midi_learn_state),
+        None => Ok(()),
+    }
+}
+
+fn split_state(state: &[u8]) -> Result<(&[u8], Option<&[u8]>), Error> {
+    let Some(rest) = state.strip_prefix(STATE_HEADER_MAGIC) else {
+        return Ok((state, None));
+    };
+
+    // State from a newer version that this build can't read
+    let Some((&STATE_HEADER_VERSION, rest)) = rest.split_first() else {
+        return Err(Error::SerializationError);
+    };
+
+    let Some((length, rest)) = rest.split_first_chunk::<{ size_of::<u64>() }>() else {
+        return Err(Error::SerializationError);
+    };
+
+    let (plugin_state, editor_state) = usize::try_from(u64::from_le_bytes(*length)).ok()
+        .and_then(|length| rest.split_at_checked(length))
+        .ok_or(Error::SerializationError)?;
+
+    Ok((plugin_state, Some(editor_state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HostInfo, Plugin, StateContext};
+    use crate::formats::PluginFormat;
+    use crate::testing::test_plugin::{TestPlugin, GAIN};
+
+    use super::{load_plugin_state, save_plugin_state, split_state};
+
+    #[test]
+    fn split_state_with_header() {
+        let mut state = b"plnthSTA\x01".to_vec();
+        state.extend_from_slice(&6u64.to_le_bytes());
+        state.extend_from_slice(b"plugineditor");
+        assert_eq!(split_state(&state).unwrap(), (&b"plugin"[..], Some(&b"editor"[..])));
+
+        // Unknown versions and corrupt lengths are errors
+        state[8] = 2;
+        assert!(split_state(&state).is_err());
+
+        let mut state = b"plnthSTA\x01".to_vec();
+        state.extend_from_slice(&100u64.to_le_bytes());
+        assert!(split_state(&state).is_err());
+    }
+
+    #[test]
+    fn plugins_without_editor_state_save_plain_state() {
+        let host_info = HostInfo { name: None, format: PluginFormat::Clap, bundle_path: None };
+        let plugin = TestPlugin::new(host_info.clone());
+
+        let mut combined_state = Vec::new();
+        save_plugin_state(&plugin, &mut combined_state, StateContext::Project).unwrap();
+
+        let mut plain_state = Vec::new();
+        plugin.save_state(&mut plain_state, StateContext::Project).unwrap();
+        assert_eq!(combined_state, plain_state);
+    }
+
+    #[test]
+    fn midi_learn_is_saved_with_editor_state() {
+        let host_info = HostInfo { name: None, format: PluginFormat::Clap, bundle_path: None };
+        let plugin = TestPlugin::new(host_info.clone());
+        let midi_learn = plugin.midi_learn().unwrap();
+        midi_learn.start_learning(GAIN);
+        midi_learn.learn(3, 74);
+
+        let mut state = Vec::new();
+        save_plugin_state(&plugin, &mut state, StateContext::Project).unwrap();
+
+        let mut loaded = TestPlugin::new(host_info);
+        load_plugin_state(&mut loaded, &mut state.as_slice(), StateContext::Project).unwrap();
+        assert_eq!(loaded.midi_learn().unwrap().assignments(), midi_learn.assignments());
+    }
+
+    #[test]
+    fn load_old_format_state() {
+        // State saved before editor state existed is plain plugin state
+        let mut old_state = GAIN.to_le_bytes().to_vec();
+        old_state.extend_from_slice(&0.25f64.to_le_bytes());
+
+        let host_info = HostInfo { name: None, format: PluginFormat::Clap, bundle_path: None };
+        let mut plugin = TestPlugin::new(host_info);
+        load_plugin_state(&mut plugin, &mut old_state.as_slice(), StateContext::Project).unwrap();
+
+        assert_eq!(plugin.with_parameters(|parameters| parameters.get(GAIN).unwrap().normalized_value()), 0.25);
+    }
+}
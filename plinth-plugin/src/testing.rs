@@ -0,0 +1,6 @@
+pub mod clap;
+pub mod null_test;
+/// Plugin used by plinth's own tests and fuzz targets
+pub mod test_plugin;
+pub mod validator;
+pub mod vst3;
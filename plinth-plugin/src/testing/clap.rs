@@ -0,0 +1,741 @@
+use std::{cell::{Cell, RefCell}, collections::BTreeMap, ffi::{c_char, c_void, CStr, CString}, marker::PhantomData, mem::MaybeUninit, ptr::{null, null_mut}};
+
+use clap_sys::{audio_buffer::clap_audio_buffer, events::{clap_event_header, clap_event_midi, clap_event_note, clap_event_note_expression, clap_event_param_gesture, clap_event_param_mod, clap_event_param_value, clap_input_events, clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END, CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_TUNING}, ext::{gui::{clap_host_gui, clap_plugin_gui, CLAP_EXT_GUI, CLAP_WINDOW_API_X11}, params::{clap_host_params, clap_param_clear_flags, clap_param_info, clap_param_rescan_flags, clap_plugin_params, CLAP_EXT_PARAMS}, state::{clap_host_state, clap_plugin_state, CLAP_EXT_STATE}}, host::clap_host, id::clap_id, plugin::clap_plugin, process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE, CLAP_PROCESS_CONTINUE_IF_NOT_QUIET, CLAP_PROCESS_SLEEP, CLAP_PROCESS_TAIL}, stream::{clap_istream, clap_ostream}, version::CLAP_VERSION};
+
+use crate::{clap::{ClapPlugin, Factory}, midi::parse_midi, rt_check, Event, ParameterId, ParameterValue};
+
+/// Result of a process call, as reported by the plugin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Error,
+    Continue,
+    ContinueIfNotQuiet,
+    Tail,
+    Sleep,
+}
+
+impl ProcessStatus {
+    fn from_clap(status: clap_process_status) -> Self {
+        match status {
+            CLAP_PROCESS_CONTINUE => Self::Continue,
+            CLAP_PROCESS_CONTINUE_IF_NOT_QUIET => Self::ContinueIfNotQuiet,
+            CLAP_PROCESS_TAIL => Self::Tail,
+            CLAP_PROCESS_SLEEP => Self::Sleep,
+            _ => Self::Error,
+        }
+    }
+}
+
+/// Fake CLAP host for testing plugins without a DAW or a display
+///
+/// The plugin is instantiated through its [`Factory`] and driven through the same C ABI a real host uses.
+/// Everything runs on the calling thread: the host doesn't start timers, open windows or provide a thread pool.
+/// Calls the plugin makes back to the host, such as resize requests and dirty state notifications,
+/// are recorded so tests can assert on them.
+pub struct TestHost<P: ClapPlugin> {
+    // The plugin keeps pointers to these, so they're boxed to keep them in place when the host is moved
+    _raw: Box<clap_host>,
+    recorder: Box<Recorder>,
+    _factory: Factory,
+
+    plugin: *const clap_plugin,
+    parameter_ranges: BTreeMap<ParameterId, (f64, f64)>,
+    output_events: Vec<Event>,
+    active: bool,
+    editor_created: bool,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: ClapPlugin> TestHost<P> {
+    /// Creates and initializes the plugin
+    pub fn new() -> Self {
+        let factory = Factory::new().with_plugin::<P>();
+        let recorder = Box::<Recorder>::default();
+
+        let raw = Box::new(clap_host {
+            clap_version: CLAP_VERSION,
+            host_data: &*recorder as *const Recorder as _,
+            name: c"plinth test host".as_ptr(),
+            vendor: c"plinth".as_ptr(),
+            url: c"".as_ptr(),
+            version: c"0.1".as_ptr(),
+            get_extension: Some(host_get_extension),
+            request_restart: Some(host_request_restart),
+            request_process: Some(host_request_process),
+            request_callback: Some(host_request_callback),
+        });
+
+        let plugin_id = CString::new(P::CLAP_ID).unwrap();
+        let raw_factory = factory.as_raw();
+        let plugin = unsafe { ((*raw_factory).create_plugin.unwrap())(raw_factory, &*raw, plugin_id.as_ptr()) };
+        assert!(!plugin.is_null(), "Factory didn't create a plugin with CLAP id {}", P::CLAP_ID);
+
+        assert!(unsafe { ((*plugin).init.unwrap())(plugin) }, "Plugin failed to initialize");
+
+        let mut host = Self {
+            _raw: raw,
+            recorder,
+            _factory: factory,
+
+            plugin,
+            parameter_ranges: BTreeMap::new(),
+            output_events: Vec::new(),
+            active: false,
+            editor_created: false,
+
+            _phantom_plugin: PhantomData,
+        };
+
+        host.rescan_parameters();
+        host
+    }
+
+    pub fn activate(&mut self, sample_rate: f64, max_block_size: usize) -> bool {
+        assert!(!self.active, "Plugin is already active");
+
+        if !unsafe { ((*self.plugin).activate.unwrap())(self.plugin, sample_rate, 1, max_block_size as _) } {
+            return false;
+        }
+
+        self.active = unsafe { ((*self.plugin).start_processing.unwrap())(self.plugin) };
+        self.active
+    }
+
+    pub fn deactivate(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        unsafe {
+            ((*self.plugin).stop_processing.unwrap())(self.plugin);
+            ((*self.plugin).deactivate.unwrap())(self.plugin);
+        }
+
+        self.active = false;
+    }
+
+    /// Processes a stereo buffer in place
+    /// Plugins with an aux input get silence on it
+    pub fn process(&mut self, buffer: &mut [Vec<f32>], events: &[Event]) -> ProcessStatus {
+        let aux = if P::HAS_AUX_INPUT {
+            vec![vec![0.0; buffer.first().map_or(0, Vec::len)]; 2]
+        } else {
+            Vec::new()
+        };
+
+        self.process_with_aux(buffer, &aux, events)
+    }
+
+    pub fn process_with_aux(&mut self, buffer: &mut [Vec<f32>], aux: &[Vec<f32>], events: &[Event]) -> ProcessStatus {
+        assert!(self.active, "Plugin needs to be activated before processing");
+        assert_eq!(buffer.len(), 2, "Only stereo buffers are supported");
+        assert_eq!(aux.len(), if P::HAS_AUX_INPUT { 2 } else { 0 }, "Aux input doesn't match the plugin");
+
+        let frames_count = buffer[0].len();
+        assert!(buffer.iter().chain(aux).all(|channel| channel.len() == frames_count), "Channel lengths differ");
+
+        // Input and output share the same buffers, so the plugin processes in place
+        let mut channel_pointers: Vec<_> = buffer.iter_mut().map(|channel| channel.as_mut_ptr()).collect();
+        let mut aux_pointers: Vec<_> = aux.iter().map(|channel| channel.as_ptr() as *mut f32).collect();
+
+        let mut audio_inputs = vec![audio_buffer(&mut channel_pointers)];
+        if P::HAS_AUX_INPUT {
+            audio_inputs.push(audio_buffer(&mut aux_pointers));
+        }
+        let mut audio_outputs = [audio_buffer(&mut channel_pointers)];
+
+        let input_events = self.convert_input_events(events);
+        let mut output_events = OutputEvents {
+            parameter_ranges: &self.parameter_ranges,
+            events: &mut self.output_events,
+        };
+
+        let in_events = input_events_as_raw(&input_events);
+        let out_events = output_events_as_raw(&mut output_events);
+
+        let process = clap_process {
+            steady_time: -1,
+            frames_count: frames_count as _,
+            transport: null(),
+            audio_inputs: audio_inputs.as_ptr(),
+            audio_outputs: audio_outputs.as_mut_ptr(),
+            audio_inputs_count: audio_inputs.len() as _,
+            audio_outputs_count: audio_outputs.len() as _,
+            in_events: &in_events,
+            out_events: &out_events,
+        };
+
+        // Editor changes are sent along with processing
+        self.recorder.flush_requested.set(false);
+
+        let status = unsafe { ((*self.plugin).process.unwrap())(self.plugin, &process) };
+        ProcessStatus::from_clap(status)
+    }
+
+    /// Sends parameter events to the plugin outside of processing, like a host does when the transport is stopped
+    pub fn flush(&mut self, events: &[Event]) {
+        let params = self.plugin_extension::<clap_plugin_params>(CLAP_EXT_PARAMS);
+        if params.is_null() {
+            return;
+        }
+
+        let input_events = self.convert_input_events(events);
+        let mut output_events = OutputEvents {
+            parameter_ranges: &self.parameter_ranges,
+            events: &mut self.output_events,
+        };
+
+        let in_events = input_events_as_raw(&input_events);
+        let out_events = output_events_as_raw(&mut output_events);
+
+        self.recorder.flush_requested.set(false);
+        unsafe { ((*params).flush.unwrap())(self.plugin, &in_events, &out_events) };
+    }
+
+    /// Handles pending main thread requests like a host's event loop would
+    pub fn idle(&mut self) {
+        if self.recorder.callback_requested.take() {
+            unsafe { ((*self.plugin).on_main_thread.unwrap())(self.plugin) };
+        }
+
+        // While processing, parameter changes are flushed by the next process call
+        if !self.active && self.recorder.flush_requested.get() {
+            self.flush(&[]);
+        }
+    }
+
+    /// Events sent by the plugin to the host since the last call
+    pub fn take_output_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.output_events)
+    }
+
+    /// Current value of a parameter as reported to the host
+    pub fn parameter_value(&self, id: impl Into<ParameterId>) -> Option<ParameterValue> {
+        let id = id.into();
+
+        let params = self.plugin_extension::<clap_plugin_params>(CLAP_EXT_PARAMS);
+        if params.is_null() {
+            return None;
+        }
+
+        let mut value = 0.0;
+        if !unsafe { ((*params).get_value.unwrap())(self.plugin, id, &mut value) } {
+            return None;
+        }
+
+        Some(normalize(&self.parameter_ranges, id, value))
+    }
+
+    pub fn save_state(&mut self) -> Option<Vec<u8>> {
+        let state = self.plugin_extension::<clap_plugin_state>(CLAP_EXT_STATE);
+        if state.is_null() {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as _,
+            write: Some(ostream_write),
+        };
+
+        unsafe { ((*state).save.unwrap())(self.plugin, &stream) }.then_some(data)
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> bool {
+        let state = self.plugin_extension::<clap_plugin_state>(CLAP_EXT_STATE);
+        if state.is_null() {
+            return false;
+        }
+
+        let mut remaining = data;
+        let stream = clap_istream {
+            ctx: &mut remaining as *mut &[u8] as _,
+            read: Some(istream_read),
+        };
+
+        unsafe { ((*state).load.unwrap())(self.plugin, &stream) }
+    }
+
+    /// Creates the editor without opening a window
+    pub fn create_editor(&mut self) -> bool {
+        let gui = self.plugin_extension::<clap_plugin_gui>(CLAP_EXT_GUI);
+        if gui.is_null() || self.editor_created {
+            return false;
+        }
+
+        self.editor_created = unsafe { ((*gui).create.unwrap())(self.plugin, CLAP_WINDOW_API_X11.as_ptr(), false) };
+        self.editor_created
+    }
+
+    pub fn set_editor_scale(&mut self, scale: f64) -> bool {
+        let gui = self.plugin_extension::<clap_plugin_gui>(CLAP_EXT_GUI);
+        if gui.is_null() || !self.editor_created {
+            return false;
+        }
+
+        unsafe { ((*gui).set_scale.unwrap())(self.plugin, scale) }
+    }
+
+    pub fn destroy_editor(&mut self) {
+        let gui = self.plugin_extension::<clap_plugin_gui>(CLAP_EXT_GUI);
+        if gui.is_null() || !self.editor_created {
+            return;
+        }
+
+        unsafe { ((*gui).destroy.unwrap())(self.plugin) };
+        self.editor_created = false;
+    }
+
+    /// Sizes requested through the GUI extension
+    pub fn resize_requests(&self) -> Vec<(u32, u32)> {
+        self.recorder.resize_requests.borrow().clone()
+    }
+
+    /// Number of times the plugin marked its state as dirty
+    pub fn dirty_count(&self) -> usize {
+        self.recorder.dirty_count.get()
+    }
+
+    pub fn restart_requested(&self) -> bool {
+        self.recorder.restart_requested.get()
+    }
+
+    pub fn parameter_rescan_requested(&self) -> bool {
+        self.recorder.rescan_requested.get()
+    }
+
+    /// Raw plugin pointer, for calling parts of the CLAP API this host doesn't wrap
+    pub fn as_raw(&self) -> *const clap_plugin {
+        self.plugin
+    }
+
+    fn plugin_extension<T>(&self, id: &CStr) -> *const T {
+        unsafe { ((*self.plugin).get_extension.unwrap())(self.plugin, id.as_ptr()) as _ }
+    }
+
+    fn rescan_parameters(&mut self) {
+        self.parameter_ranges.clear();
+
+        let params = self.plugin_extension::<clap_plugin_params>(CLAP_EXT_PARAMS);
+        if params.is_null() {
+            return;
+        }
+
+        let count = unsafe { ((*params).count.unwrap())(self.plugin) };
+
+        for index in 0..count {
+            let mut info = MaybeUninit::<clap_param_info>::zeroed();
+            if !unsafe { ((*params).get_info.unwrap())(self.plugin, index, info.as_mut_ptr()) } {
+                continue;
+            }
+
+            let info = unsafe { info.assume_init() };
+            self.parameter_ranges.insert(info.id, (info.min_value, info.max_value));
+        }
+    }
+
+    fn convert_input_events(&self, events: &[Event]) -> Vec<InputEvent> {
+        let mut input_events: Vec<_> = events.iter()
+            .filter_map(|event| InputEvent::from_event(event, &self.parameter_ranges))
+            .collect();
+
+        // CLAP requires input events to be sorted by time
+        input_events.sort_by_key(|event| unsafe { (*event.header()).time });
+        input_events
+    }
+}
+
+impl<P: ClapPlugin> Default for TestHost<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: ClapPlugin> Drop for TestHost<P> {
+    fn drop(&mut self) {
+        self.destroy_editor();
+        self.deactivate();
+
+        unsafe { ((*self.plugin).destroy.unwrap())(self.plugin) };
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    resize_requests: RefCell<Vec<(u32, u32)>>,
+    dirty_count: Cell<usize>,
+    callback_requested: Cell<bool>,
+    flush_requested: Cell<bool>,
+    rescan_requested: Cell<bool>,
+    restart_requested: Cell<bool>,
+}
+
+impl Recorder {
+    unsafe fn from_host<'a>(host: *const clap_host) -> &'a Self {
+        unsafe { &*((*host).host_data as *const Self) }
+    }
+}
+
+static HOST_GUI: clap_host_gui = clap_host_gui {
+    resize_hints_changed: Some(gui_resize_hints_changed),
+    request_resize: Some(gui_request_resize),
+    request_show: Some(gui_request_show),
+    request_hide: Some(gui_request_hide),
+    closed: Some(gui_closed),
+};
+
+static HOST_PARAMS: clap_host_params = clap_host_params {
+    rescan: Some(params_rescan),
+    clear: Some(params_clear),
+    request_flush: Some(params_request_flush),
+};
+
+static HOST_STATE: clap_host_state = clap_host_state {
+    mark_dirty: Some(state_mark_dirty),
+};
+
+unsafe extern "C" fn host_get_extension(_host: *const clap_host, extension_id: *const c_char) -> *const c_void {
+    let id = unsafe { CStr::from_ptr(extension_id) };
+
+    if id == CLAP_EXT_GUI {
+        &HOST_GUI as *const clap_host_gui as _
+    } else if id == CLAP_EXT_PARAMS {
+        &HOST_PARAMS as *const clap_host_params as _
+    } else if id == CLAP_EXT_STATE {
+        &HOST_STATE as *const clap_host_state as _
+    } else {
+        null()
+    }
+}
+
+unsafe extern "C" fn host_request_restart(host: *const clap_host) {
+    unsafe { Recorder::from_host(host) }.restart_requested.set(true);
+}
+
+unsafe extern "C" fn host_request_process(_host: *const clap_host) {
+}
+
+unsafe extern "C" fn host_request_callback(host: *const clap_host) {
+    unsafe { Recorder::from_host(host) }.callback_requested.set(true);
+}
+
+unsafe extern "C" fn gui_resize_hints_changed(_host: *const clap_host) {
+}
+
+unsafe extern "C" fn gui_request_resize(host: *const clap_host, width: u32, height: u32) -> bool {
+    unsafe { Recorder::from_host(host) }.resize_requests.borrow_mut().push((width, height));
+    true
+}
+
+unsafe extern "C" fn gui_request_show(_host: *const clap_host) -> bool {
+    false
+}
+
+unsafe extern "C" fn gui_request_hide(_host: *const clap_host) -> bool {
+    false
+}
+
+unsafe extern "C" fn gui_closed(_host: *const clap_host, _was_destroyed: bool) {
+}
+
+unsafe extern "C" fn params_rescan(host: *const clap_host, _flags: clap_param_rescan_flags) {
+    unsafe { Recorder::from_host(host) }.rescan_requested.set(true);
+}
+
+unsafe extern "C" fn params_clear(_host: *const clap_host, _param_id: clap_id, _flags: clap_param_clear_flags) {
+}
+
+unsafe extern "C" fn params_request_flush(host: *const clap_host) {
+    unsafe { Recorder::from_host(host) }.flush_requested.set(true);
+}
+
+unsafe extern "C" fn state_mark_dirty(host: *const clap_host) {
+    let recorder = unsafe { Recorder::from_host(host) };
+    recorder.dirty_count.set(recorder.dirty_count.get() + 1);
+}
+
+unsafe extern "C" fn ostream_write(stream: *const clap_ostream, buffer: *const c_void, size: u64) -> i64 {
+    let data = unsafe { &mut *((*stream).ctx as *mut Vec<u8>) };
+    data.extend_from_slice(unsafe { std::slice::from_raw_parts(buffer as *const u8, size as _) });
+
+    size as _
+}
+
+unsafe extern "C" fn istream_read(stream: *const clap_istream, buffer: *mut c_void, size: u64) -> i64 {
+    let remaining = unsafe { &mut *((*stream).ctx as *mut &[u8]) };
+    let count = usize::min(size as _, remaining.len());
+
+    unsafe { std::ptr::copy_nonoverlapping(remaining.as_ptr(), buffer as *mut u8, count) };
+    *remaining = &remaining[count..];
+
+    count as _
+}
+
+fn audio_buffer(channel_pointers: &mut [*mut f32]) -> clap_audio_buffer {
+    clap_audio_buffer {
+        data32: channel_pointers.as_mut_ptr(),
+        data64: null_mut(),
+        channel_count: channel_pointers.len() as _,
+        latency: 0,
+        constant_mask: 0,
+    }
+}
+
+fn normalize(parameter_ranges: &BTreeMap<ParameterId, (f64, f64)>, id: ParameterId, value: f64) -> ParameterValue {
+    match parameter_ranges.get(&id) {
+        Some(&(min, max)) if max > min => (value - min) / (max - min),
+        _ => value,
+    }
+}
+
+fn denormalize(parameter_ranges: &BTreeMap<ParameterId, (f64, f64)>, id: ParameterId, value: ParameterValue) -> f64 {
+    match parameter_ranges.get(&id) {
+        Some(&(min, max)) => min + value * (max - min),
+        None => value,
+    }
+}
+
+fn event_header<T>(type_: u16, sample_offset: usize) -> clap_event_header {
+    clap_event_header {
+        size: size_of::<T>() as _,
+        time: sample_offset as _,
+        space_id: CLAP_CORE_EVENT_SPACE_ID,
+        type_,
+        flags: 0,
+    }
+}
+
+enum InputEvent {
+    Note(clap_event_note),
+    NoteExpression(clap_event_note_expression),
+    Midi(clap_event_midi),
+    ParamValue(clap_event_param_value),
+    ParamMod(clap_event_param_mod),
+}
+
+impl InputEvent {
+    fn from_event(event: &Event, parameter_ranges: &BTreeMap<ParameterId, (f64, f64)>) -> Option<Self> {
+        let input_event = match *event {
+            Event::NoteOn { sample_offset, channel, key, note, velocity } => Self::Note(clap_event_note {
+                header: event_header::<clap_event_note>(CLAP_EVENT_NOTE_ON, sample_offset),
+                note_id: note,
+                port_index: 0,
+                channel,
+                key,
+                velocity,
+            }),
+
+            Event::NoteOff { sample_offset, channel, key, note, velocity } => Self::Note(clap_event_note {
+                header: event_header::<clap_event_note>(CLAP_EVENT_NOTE_OFF, sample_offset),
+                note_id: note,
+                port_index: 0,
+                channel,
+                key,
+                velocity,
+            }),
+
+            Event::PitchBend { sample_offset, channel, key, note, semitones } => Self::NoteExpression(clap_event_note_expression {
+                header: event_header::<clap_event_note_expression>(CLAP_EVENT_NOTE_EXPRESSION, sample_offset),
+                expression_id: CLAP_NOTE_EXPRESSION_TUNING,
+                note_id: note,
+                port_index: 0,
+                channel,
+                key,
+                value: semitones,
+            }),
+
+            Event::ControlChange { sample_offset, channel, controller, value } => Self::Midi(clap_event_midi {
+                header: event_header::<clap_event_midi>(CLAP_EVENT_MIDI, sample_offset),
+                port_index: 0,
+                data: [0xB0 | (channel as u8 & 0x0F), controller as u8 & 0x7F, (value.clamp(0.0, 1.0) * 127.0).round() as u8],
+            }),
+
+            Event::ParameterValue { sample_offset, id, value } => Self::ParamValue(clap_event_param_value {
+                header: event_header::<clap_event_param_value>(CLAP_EVENT_PARAM_VALUE, sample_offset),
+                param_id: id,
+                cookie: null_mut(),
+                note_id: -1,
+                port_index: -1,
+                channel: -1,
+                key: -1,
+                value: denormalize(parameter_ranges, id, value),
+            }),
+
+            Event::ParameterModulation { sample_offset, id, amount } => Self::ParamMod(clap_event_param_mod {
+                header: event_header::<clap_event_param_mod>(CLAP_EVENT_PARAM_MOD, sample_offset),
+                param_id: id,
+                cookie: null_mut(),
+                note_id: -1,
+                port_index: -1,
+                channel: -1,
+                key: -1,
+                amount: denormalize(parameter_ranges, id, amount) - denormalize(parameter_ranges, id, 0.0),
+            }),
+
+            // Gestures only go from the plugin to the host
+            _ => return None,
+        };
+
+        Some(input_event)
+    }
+
+    fn header(&self) -> *const clap_event_header {
+        match self {
+            Self::Note(event) => &event.header,
+            Self::NoteExpression(event) => &event.header,
+            Self::Midi(event) => &event.header,
+            Self::ParamValue(event) => &event.header,
+            Self::ParamMod(event) => &event.header,
+        }
+    }
+}
+
+fn input_events_as_raw(events: &Vec<InputEvent>) -> clap_input_events {
+    clap_input_events {
+        ctx: events as *const Vec<InputEvent> as _,
+        size: Some(input_events_size),
+        get: Some(input_events_get),
+    }
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events = unsafe { &*((*list).ctx as *const Vec<InputEvent>) };
+    events.len() as _
+}
+
+unsafe extern "C" fn input_events_get(list: *const clap_input_events, index: u32) -> *const clap_event_header {
+    let events = unsafe { &*((*list).ctx as *const Vec<InputEvent>) };
+
+    match events.get(index as usize) {
+        Some(event) => event.header(),
+        None => null(),
+    }
+}
+
+struct OutputEvents<'a> {
+    parameter_ranges: &'a BTreeMap<ParameterId, (f64, f64)>,
+    events: &'a mut Vec<Event>,
+}
+
+impl OutputEvents<'_> {
+    fn push(&mut self, header: *const clap_event_header) {
+        let header_ref = unsafe { &*header };
+        if header_ref.space_id != CLAP_CORE_EVENT_SPACE_ID {
+            return;
+        }
+
+        let sample_offset = header_ref.time as usize;
+
+        let event = match header_ref.type_ {
+            CLAP_EVENT_NOTE_ON | CLAP_EVENT_NOTE_OFF => {
+                let event = unsafe { &*(header as *const clap_event_note) };
+
+                if header_ref.type_ == CLAP_EVENT_NOTE_ON {
+                    Event::NoteOn { sample_offset, channel: event.channel, key: event.key, note: event.note_id, velocity: event.velocity }
+                } else {
+                    Event::NoteOff { sample_offset, channel: event.channel, key: event.key, note: event.note_id, velocity: event.velocity }
+                }
+            },
+
+            CLAP_EVENT_MIDI => {
+                let event = unsafe { &*(header as *const clap_event_midi) };
+
+                let Some(event) = parse_midi(&event.data, sample_offset, &[], None) else {
+                    return;
+                };
+
+                event
+            },
+
+            CLAP_EVENT_PARAM_GESTURE_BEGIN => {
+                let event = unsafe { &*(header as *const clap_event_param_gesture) };
+                Event::StartParameterChange { id: event.param_id }
+            },
+
+            CLAP_EVENT_PARAM_GESTURE_END => {
+                let event = unsafe { &*(header as *const clap_event_param_gesture) };
+                Event::EndParameterChange { id: event.param_id }
+            },
+
+            CLAP_EVENT_PARAM_VALUE => {
+                let event = unsafe { &*(header as *const clap_event_param_value) };
+
+                Event::ParameterValue {
+                    sample_offset,
+                    id: event.param_id,
+                    value: normalize(self.parameter_ranges, event.param_id, event.value),
+                }
+            },
+
+            _ => return,
+        };
+
+        self.events.push(event);
+    }
+}
+
+fn output_events_as_raw(events: &mut OutputEvents) -> clap_output_events {
+    clap_output_events {
+        ctx: events as *mut OutputEvents as _,
+        try_push: Some(output_events_try_push),
+    }
+}
+
+unsafe extern "C" fn output_events_try_push(list: *const clap_output_events, event: *const clap_event_header) -> bool {
+    let events = unsafe { &mut *((*list).ctx as *mut OutputEvents) };
+
+    // A real host would have preallocated space, so this isn't the plugin allocating on the audio thread
+    rt_check::permit(|| events.push(event));
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Event;
+    use crate::testing::test_plugin::{TestPlugin, GAIN};
+
+    use super::{ProcessStatus, TestHost};
+
+    #[test]
+    fn process_and_state_round_trip() {
+        let mut host = TestHost::<TestPlugin>::new();
+        assert!(host.activate(48000.0, 64));
+
+        let mut buffer = vec![vec![1.0; 64]; 2];
+        let events = [Event::ParameterValue { sample_offset: 0, id: GAIN, value: 0.5 }];
+        assert_eq!(host.process(&mut buffer, &events), ProcessStatus::ContinueIfNotQuiet);
+        assert!(buffer.iter().flatten().all(|&sample| sample == 0.5));
+
+        // Assigned MIDI CCs are echoed back to the host as parameter changes
+        let events = [Event::ControlChange { sample_offset: 0, channel: 0, controller: 7, value: 0.0 }];
+        host.process(&mut buffer, &events);
+        assert!(buffer.iter().flatten().all(|&sample| sample == 0.0));
+        assert!(matches!(
+            host.take_output_events().as_slice(),
+            [Event::ParameterValue { id: GAIN, value: 0.0, .. }]
+        ));
+
+        host.idle();
+        assert_eq!(host.parameter_value(GAIN), Some(0.0));
+
+        let state = host.save_state().unwrap();
+        host.deactivate();
+
+        let mut other_host = TestHost::<TestPlugin>::new();
+        assert_eq!(other_host.parameter_value(GAIN), Some(1.0));
+        assert!(other_host.load_state(&state));
+        assert_eq!(other_host.parameter_value(GAIN), Some(0.0));
+    }
+
+    #[test]
+    fn editor_callbacks() {
+        let mut host = TestHost::<TestPlugin>::new();
+
+        assert!(host.create_editor());
+        assert!(host.set_editor_scale(2.0));
+        assert_eq!(host.resize_requests(), [(400, 200)]);
+        assert_eq!(host.dirty_count(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{clap::ClapPlugin, vst3::Vst3Plugin, Event};
+
+use super::clap::{self, ProcessStatus};
+use super::vst3;
+
+#[derive(Clone, Debug)]
+pub struct NullTestConfig {
+    pub sample_rate: f64,
+    pub block_size: usize,
+}
+
+impl Default for NullTestConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000.0,
+            block_size: 512,
+        }
+    }
+}
+
+/// Outputs of both formats and where they differ the most
+#[derive(Clone, Debug)]
+pub struct NullTestResult {
+    pub max_difference: f32,
+    pub channel: usize,
+    pub frame: usize,
+    pub clap_output: Vec<Vec<f32>>,
+    pub vst3_output: Vec<Vec<f32>>,
+}
+
+/// Runs the same stereo input and events through the plugin's CLAP and VST3 wrappers and compares the output
+///
+/// Event sample offsets are from the start of the input, events past the end are dropped.
+/// Automation is given as [`Event::ParameterValue`] events, which reach VST3 as parameter changes like
+/// other events that map to parameters. Both wrappers get the events in the given order, so differences
+/// in how they order events show up in the result.
+pub fn null_test<P: ClapPlugin + Vst3Plugin + 'static>(input: &[Vec<f32>], events: &[Event], config: &NullTestConfig) -> NullTestResult {
+    assert_eq!(input.len(), 2, "Only stereo input is supported");
+    assert!(config.block_size > 0, "Block size needs to be at least 1");
+
+    let mut clap_host = clap::TestHost::<P>::new();
+    let mut vst3_host = vst3::TestHost::<P>::new();
+
+    assert!(clap_host.activate(config.sample_rate, config.block_size), "CLAP plugin failed to activate");
+    assert!(vst3_host.activate(config.sample_rate, config.block_size), "VST3 plugin failed to activate");
+
+    let length = input[0].len();
+    let mut clap_output = input.to_vec();
+    let mut vst3_output = input.to_vec();
+
+    for start in (0..length).step_by(config.block_size) {
+        let end = usize::min(start + config.block_size, length);
+
+        let block_events: Vec<_> = events.iter()
+            .filter(|event| (start..end).contains(&event.sample_offset()))
+            .map(|event| event.clone().with_sample_offset(|sample_offset| sample_offset - start))
+            .collect();
+
+        let mut clap_block: Vec<_> = clap_output.iter().map(|channel| channel[start..end].to_vec()).collect();
+        let mut vst3_block: Vec<_> = vst3_output.iter().map(|channel| channel[start..end].to_vec()).collect();
+
+        assert_ne!(clap_host.process(&mut clap_block, &block_events), ProcessStatus::Error, "CLAP plugin failed to process");
+        assert!(vst3_host.process(&mut vst3_block, &block_events), "VST3 plugin failed to process");
+
+        for (output, block) in clap_output.iter_mut().zip(clap_block) {
+            output[start..end].copy_from_slice(&block);
+        }
+        for (output, block) in vst3_output.iter_mut().zip(vst3_block) {
+            output[start..end].copy_from_slice(&block);
+        }
+    }
+
+    let mut result = NullTestResult {
+        max_difference: 0.0,
+        channel: 0,
+        frame: 0,
+        clap_output,
+        vst3_output,
+    };
+
+    for (channel, (clap_channel, vst3_channel)) in result.clap_output.iter().zip(result.vst3_output.iter()).enumerate() {
+        for (frame, (clap_sample, vst3_sample)) in clap_channel.iter().zip(vst3_channel.iter()).enumerate() {
+            // NaN in only one of the outputs counts as the largest possible difference
+            let difference = match (clap_sample.is_nan(), vst3_sample.is_nan()) {
+                (false, false) => (clap_sample - vst3_sample).abs(),
+                (true, true) => 0.0,
+                _ => f32::INFINITY,
+            };
+
+            if difference > result.max_difference {
+                result.max_difference = difference;
+                result.channel = channel;
+                result.frame = frame;
+            }
+        }
+    }
+
+    result
+}
+
+/// Runs [`null_test`] with the default config and panics if the formats differ by more than `tolerance`
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[test]
+/// fn formats_null() {
+///     let input = vec![vec![0.5; 4096]; 2];
+///     let events = [Event::ParameterValue { sample_offset: 1000, id: GAIN, value: 0.25 }];
+///
+///     plinth_plugin::testing::null_test::assert_nulls::<MyPlugin>(&input, &events, 1e-6);
+/// }
+/// ```
+pub fn assert_nulls<P: ClapPlugin + Vst3Plugin + 'static>(input: &[Vec<f32>], events: &[Event], tolerance: f32) {
+    let result = null_test::<P>(input, events, &NullTestConfig::default());
+
+    assert!(
+        result.max_difference <= tolerance,
+        "CLAP and VST3 output of {} differ by {} on channel {} at frame {}: {} vs {}",
+        P::NAME,
+        result.max_difference,
+        result.channel,
+        result.frame,
+        result.clap_output[result.channel][result.frame],
+        result.vst3_output[result.channel][result.frame],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::test_plugin::{TestPlugin, GAIN, LEVEL}, Event};
+
+    use super::{null_test, NullTestConfig};
+
+    fn note_on(sample_offset: usize) -> Event {
+        Event::NoteOn { sample_offset, channel: 0, key: 60, note: -1, velocity: 1.0 }
+    }
+
+    #[test]
+    fn gain_automation_nulls() {
+        let input = vec![vec![0.5; 2000]; 2];
+        let events = [
+            Event::ParameterValue { sample_offset: 700, id: GAIN, value: 0.25 },
+            Event::ParameterValue { sample_offset: 1500, id: GAIN, value: 0.75 },
+        ];
+
+        let config = NullTestConfig {
+            block_size: 256,
+            ..Default::default()
+        };
+
+        let result = null_test::<TestPlugin>(&input, &events, &config);
+        assert_eq!(result.max_difference, 0.0);
+        assert_eq!(result.clap_output[0][1999], 0.375);
+    }
+
+    #[test]
+    fn stepped_automation_nulls() {
+        let input = vec![vec![0.5; 2000]; 2];
+        let events = [
+            note_on(0),
+            // Between steps, snaps to 1
+            Event::ParameterValue { sample_offset: 300, id: LEVEL, value: 0.3 },
+            note_on(400),
+            Event::ParameterValue { sample_offset: 900, id: LEVEL, value: 0.5 },
+            Event::NoteOff { sample_offset: 1000, channel: 0, key: 60, note: -1, velocity: 0.0 },
+            note_on(1200),
+        ];
+
+        // Every event in a block of its own
+        let config = NullTestConfig {
+            block_size: 100,
+            ..Default::default()
+        };
+
+        let result = null_test::<TestPlugin>(&input, &events, &config);
+        assert_eq!(result.max_difference, 0.0);
+        assert_eq!(result.clap_output[0][399], 0.5);
+        assert_eq!(result.clap_output[0][999], 0.125);
+        assert_eq!(result.clap_output[0][1100], 0.0);
+        assert_eq!(result.clap_output[0][1999], 0.25);
+    }
+
+    #[test]
+    fn note_sees_parameter_change_on_the_same_sample() {
+        let input = vec![vec![0.5; 1000]; 2];
+        let events = [
+            Event::ParameterValue { sample_offset: 300, id: LEVEL, value: 0.3 },
+            note_on(300),
+        ];
+
+        let config = NullTestConfig {
+            block_size: 256,
+            ..Default::default()
+        };
+
+        let result = null_test::<TestPlugin>(&input, &events, &config);
+        assert_eq!(result.max_difference, 0.0);
+        assert_eq!(result.clap_output[0][299], 0.5);
+        assert_eq!(result.clap_output[0][300], 0.125);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{cell::{Cell, RefCell}, io::{Read, Write}, rc::Rc, sync::Arc};
+
+use plinth_core::signals::signal::{Signal, SignalMut};
+
+use crate::{clap::{ClapPlugin, Feature}, vst3::{Subcategory, Vst3Plugin}, BoolParameter, Editor, Error, Event, FloatParameter, Host, HostInfo, IntParameter, IntRange, LinearFloatRange, MidiCcAssignment, MidiLearn, ParameterMap, Parameters, Plugin, ProcessState, Processor, ProcessorConfig, StateContext, Transport};
+
+pub const GAIN: u32 = 1;
+pub const LEVEL: u32 = 2;
+pub const BYPASS: u32 = 3;
+
+// The wrappers own the plugin and its editor, so tests reach them through these
+thread_local! {
+    static STATE_LOADS: Cell<usize> = const { Cell::new(0) };
+    static LAST_EDITOR: RefCell<Option<TestEditor>> = const { RefCell::new(None) };
+}
+
+// Only recorded in plinth's own tests, fuzz targets would grow it without bound
+#[cfg(test)]
+thread_local! {
+    static PLUGIN_EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Events passed to `Plugin::process_event` of the test plugins on this thread
+#[cfg(test)]
+pub fn plugin_events() -> Vec<Event> {
+    PLUGIN_EVENTS.with_borrow(Clone::clone)
+}
+
+/// Number of times a test plugin on this thread has loaded its state
+pub fn state_load_count() -> usize {
+    STATE_LOADS.get()
+}
+
+/// Gain plugin shared by the format test hosts and the fuzz targets
+///
+/// It has a continuous gain, a stepped level and a bypass switch, so the wrappers' value mappings all get used.
+/// Events are applied at their sample offsets. A note on latches the level as an extra gain and a note off
+/// mutes the output, so the order of notes and parameter changes on the same sample shows in the output.
+/// Without notes, the output is only scaled by the gain.
+pub struct TestPlugin {
+    parameters: ParameterMap,
+    midi_learn: Rc<MidiLearn>,
+}
+
+impl Plugin for TestPlugin {
+    const NAME: &'static str = "Test";
+    const VENDOR: &'static str = "plinth";
+    const VERSION: &'static str = "0.1";
+
+    const HAS_NOTE_INPUT: bool = true;
+
+    type Processor = TestProcessor;
+    type Editor = TestEditor;
+    type Parameters = ParameterMap;
+
+    fn new(_host_info: HostInfo) -> Self {
+        let mut parameters = ParameterMap::new();
+        parameters.add(FloatParameter::new(GAIN, "Gain", Arc::new(LinearFloatRange::new(0.0, 1.0))).with_default_value(1.0));
+        parameters.add(IntParameter::new(LEVEL, "Level", Arc::new(IntRange::new(0, 4))).with_default_value(4));
+        parameters.add(BoolParameter::new(BYPASS, "Bypass").as_bypass());
+
+        Self {
+            parameters,
+            midi_learn: Default::default(),
+        }
+    }
+
+    fn init(&mut self) {}
+
+    fn with_parameters<T>(&self, mut f: impl FnMut(&Self::Parameters) -> T) -> T {
+        f(&self.parameters)
+    }
+
+    fn process_event(&mut self, event: &Event) {
+        #[cfg(test)]
+        PLUGIN_EVENTS.with_borrow_mut(|events| events.push(event.clone()));
+
+        self.parameters.process_event(event);
+    }
+
+    fn create_processor(&mut self, _config: ProcessorConfig) -> Self::Processor {
+        TestProcessor {
+            parameters: self.parameters.clone(),
+            level: 1.0,
+        }
+    }
+
+    fn create_editor(&mut self, host: Rc<dyn Host>) -> Self::Editor {
+        let editor = TestEditor {
+            host,
+            midi_learn: self.midi_learn.clone(),
+        };
+
+        LAST_EDITOR.set(Some(editor.clone()));
+        editor
+    }
+
+    fn save_state(&self, writer: &mut impl Write, _context: StateContext) -> Result<(), Error> {
+        for (id, value) in self.parameters.serialize() {
+            writer.write_all(&id.to_le_bytes())?;
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn load_state(&mut self, reader: &mut impl Read, _context: StateContext) -> Result<(), Error> {
+        STATE_LOADS.set(STATE_LOADS.get() + 1);
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let values = data.chunks_exact(12)
+            .map(|chunk| {
+                let (id, value) = chunk.split_at(4);
+                (u32::from_le_bytes(id.try_into().unwrap()), f64::from_le_bytes(value.try_into().unwrap()))
+            });
+
+        self.parameters.deserialize(values)
+    }
+
+    fn midi_cc_assignments(&self) -> Vec<MidiCcAssignment> {
+        vec![MidiCcAssignment::new(7, GAIN)]
+    }
+
+    fn midi_learn(&self) -> Option<&MidiLearn> {
+        Some(self.midi_learn.as_ref())
+    }
+}
+
+impl ClapPlugin for TestPlugin {
+    const CLAP_ID: &'static str = "plinth.test";
+    const FEATURES: &'static [Feature] = &[Feature::AudioEffect];
+}
+
+impl Vst3Plugin for TestPlugin {
+    const CLASS_ID: u128 = 0x706C696E746854657374506C7567696E;
+    const SUBCATEGORIES: &'static [Subcategory] = &[Subcategory::Fx];
+}
+
+pub struct TestProcessor {
+    parameters: ParameterMap,
+    // Latched from the level parameter by note ons
+    level: f32,
+}
+
+impl TestProcessor {
+    fn render(&self, buffer: &mut impl SignalMut, start: usize, end: usize) {
+        if self.parameters.value::<BoolParameter>(BYPASS) {
+            return;
+        }
+
+        let gain = self.parameters.value::<FloatParameter>(GAIN) as f32 * self.level;
+
+        for channel in buffer.iter_channels_mut() {
+            let end = usize::min(end, channel.len());
+            for sample in channel[start.min(end)..end].iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn apply_event(&mut self, event: &Event) {
+        match event {
+            Event::NoteOn { .. } => self.level = self.parameters.value::<IntParameter>(LEVEL) as f32 / 4.0,
+            Event::NoteOff { .. } => self.level = 0.0,
+            _ => self.parameters.process_event(event),
+        }
+    }
+}
+
+impl Processor for TestProcessor {
+    fn reset(&mut self) {}
+
+    // Events are applied at their own sample, so their order within a block matters
+    fn process(&mut self, buffer: &mut impl SignalMut, _aux: Option<&impl Signal>, _transport: Option<Transport>, events: impl Iterator<Item = Event>) -> ProcessState {
+        let mut position = 0;
+
+        for event in events {
+            let sample_offset = usize::max(event.sample_offset(), position);
+            self.render(buffer, position, sample_offset);
+            self.apply_event(&event);
+            position = sample_offset;
+        }
+
+        self.render(buffer, position, usize::MAX);
+
+        ProcessState::Normal
+    }
+
+    fn process_events(&mut self, events: impl Iterator<Item = Event>) {
+        for event in events {
+            self.apply_event(&event);
+        }
+    }
+}
+
+/// Headless editor that talks to the host the way a real one would
+#[derive(Clone)]
+pub struct TestEditor {
+    host: Rc<dyn Host>,
+    midi_learn: Rc<MidiLearn>,
+}
+
+impl TestEditor {
+    /// Editor created last by a test plugin on this thread
+    pub fn last_created() -> Option<Self> {
+        LAST_EDITOR.with_borrow(Clone::clone)
+    }
+
+    /// Changes the gain like a user dragging a control
+    pub fn set_gain(&self, value: f64) {
+        self.host.start_parameter_change(GAIN);
+        self.host.change_parameter_value(GAIN, value);
+        self.host.end_parameter_change(GAIN);
+    }
+
+    /// Puts the gain into MIDI learn mode like a learn button
+    pub fn start_gain_midi_learn(&self) {
+        self.midi_learn.start_learning(GAIN);
+    }
+}
+
+impl Editor for TestEditor {
+    const DEFAULT_SIZE: (f64, f64) = (200.0, 100.0);
+
+    fn open(&mut self, _parent: raw_window_handle::RawWindowHandle) {}
+    fn close(&mut self) {}
+
+    // The scale is editor state, so changing it resizes the window and dirties the state
+    fn set_scale(&self, scale: f64) {
+        self.host.resize_view(Self::DEFAULT_SIZE.0 * scale, Self::DEFAULT_SIZE.1 * scale);
+        self.host.mark_state_dirty();
+    }
+
+    fn on_frame(&self) {}
+}
@@ -0,0 +1,513 @@
+use std::{any::Any, collections::HashSet, fmt::Display, panic::{catch_unwind, AssertUnwindSafe}};
+
+use plinth_core::{buffers::buffer::Buffer, signals::{signal::{Signal, SignalMut}, signal_base::SignalBase}};
+
+use crate::{formats::PluginFormat, rt_check::{self, ViolationMode}, state, Event, HostInfo, Parameter, ParameterId, ParameterValue, Parameters, Plugin, ProcessMode, ProcessState, Processor, ProcessorConfig, StateContext};
+
+/// Problem found by [`validate`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    ZeroParameterId,
+    DuplicateParameterId(ParameterId),
+    MultipleBypassParameters,
+    MidiCcAssignedToMissingParameter(ParameterId),
+    DefaultValueOutOfRange {
+        id: ParameterId,
+        value: ParameterValue,
+    },
+    /// Parsing the string a value was formatted as gives a different value
+    StringRoundTrip {
+        id: ParameterId,
+        value: ParameterValue,
+        string: String,
+    },
+    StateSaveFailed,
+    StateLoadFailed,
+    /// Saving state after loading it gives different state
+    StateNotIdempotent,
+    /// A parameter doesn't have the value it was saved with after loading the state
+    StateParameterMismatch {
+        id: ParameterId,
+        saved: ParameterValue,
+        loaded: ParameterValue,
+    },
+    ProcessError {
+        block_size: usize,
+    },
+    NonFiniteOutput {
+        block_size: usize,
+        sample: usize,
+    },
+    DenormalOutput {
+        block_size: usize,
+        sample: usize,
+    },
+    Panic {
+        check: &'static str,
+        message: String,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::ZeroParameterId => write!(f, "Parameter id 0 is reserved"),
+            Violation::DuplicateParameterId(id) => write!(f, "Parameter id {id} is used more than once"),
+            Violation::MultipleBypassParameters => write!(f, "There can only be one bypass parameter"),
+            Violation::MidiCcAssignedToMissingParameter(id) => write!(f, "MIDI CC is assigned to parameter {id} which doesn't exist"),
+            Violation::DefaultValueOutOfRange { id, value } => write!(f, "Default value {value} of parameter {id} is outside 0..=1"),
+            Violation::StringRoundTrip { id, value, string } => write!(f, "Value {value} of parameter {id} doesn't survive formatting as \"{string}\" and parsing back"),
+            Violation::StateSaveFailed => write!(f, "Saving state failed"),
+            Violation::StateLoadFailed => write!(f, "Loading saved state failed"),
+            Violation::StateNotIdempotent => write!(f, "Loading state and saving it again gives different state"),
+            Violation::StateParameterMismatch { id, saved, loaded } => write!(f, "Parameter {id} was saved as {saved} but loaded as {loaded}"),
+            Violation::ProcessError { block_size } => write!(f, "Processing failed with block size {block_size}"),
+            Violation::NonFiniteOutput { block_size, sample } => write!(f, "Output sample {sample} isn't finite with block size {block_size}"),
+            Violation::DenormalOutput { block_size, sample } => write!(f, "Output sample {sample} is denormal with block size {block_size}"),
+            Violation::Panic { check, message } => write!(f, "Panicked while checking {check}: {message}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ValidationConfig {
+    pub sample_rate: f64,
+    pub max_block_size: usize,
+    /// Samples to process with each block size, the second half of the input is silence
+    pub length: usize,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000.0,
+            max_block_size: 1024,
+            length: 8192,
+        }
+    }
+}
+
+/// Checks that a plugin follows the rules the format wrappers and hosts rely on, returns the violations found
+///
+/// This runs directly against the plugin traits, without a plugin format or host in between.
+/// With the `rt-check` feature, allocating while processing, or making a call marked with [`rt_check::blocking`],
+/// shows up as a [`Violation::Panic`].
+pub fn validate<P: Plugin>(config: &ValidationConfig) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let mut plugin = match catch("creating the plugin", || {
+        let mut plugin = P::new(host_info());
+        plugin.init();
+        plugin
+    }) {
+        Ok(plugin) => plugin,
+        Err(violation) => return vec![violation],
+    };
+
+    plugin.with_parameters(|parameters| violations.extend(validate_parameters(parameters)));
+
+    let midi_cc_assignments = plugin.midi_cc_assignments();
+    plugin.with_parameters(|parameters| {
+        for assignment in midi_cc_assignments.iter() {
+            if parameters.get(assignment.parameter_id).is_none() {
+                violations.push(Violation::MidiCcAssignedToMissingParameter(assignment.parameter_id));
+            }
+        }
+    });
+
+    violations.extend(validate_state(&mut plugin));
+
+    for block_size in [1, config.max_block_size] {
+        violations.extend(validate_processing(&mut plugin, config, block_size));
+    }
+
+    violations.extend(validate_late_events(&mut plugin, config));
+
+    violations
+}
+
+/// Validates the plugin and panics with a list of the violations if there are any, for use in tests
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[test]
+/// fn conformance() {
+///     plinth_plugin::testing::validator::assert_valid::<MyPlugin>();
+/// }
+/// ```
+pub fn assert_valid<P: Plugin>() {
+    let violations = validate::<P>(&ValidationConfig::default());
+
+    if !violations.is_empty() {
+        let list: Vec<_> = violations.iter()
+            .map(|violation| format!("- {violation}"))
+            .collect();
+
+        panic!("{} violated {} rule(s):\n{}", P::NAME, violations.len(), list.join("\n"));
+    }
+}
+
+/// Checks parameter ids, the bypass parameter, default values and string conversions
+pub fn validate_parameters(parameters: &impl Parameters) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut ids = HashSet::new();
+    let mut bypass_count = 0;
+
+    for &id in parameters.ids() {
+        if id == 0 {
+            violations.push(Violation::ZeroParameterId);
+        }
+        if !ids.insert(id) {
+            violations.push(Violation::DuplicateParameterId(id));
+            continue;
+        }
+
+        let Some(parameter) = parameters.get(id) else {
+            continue;
+        };
+        let info = parameter.info();
+
+        if info.is_bypass() {
+            bypass_count += 1;
+        }
+
+        let default_value = info.default_normalized_value();
+        if !(0.0..=1.0).contains(&default_value) {
+            violations.push(Violation::DefaultValueOutOfRange { id, value: default_value });
+        }
+
+        match catch("parameter string conversion", || string_round_trip_failures(parameter)) {
+            Ok(failures) => violations.extend(failures),
+            Err(violation) => violations.push(violation),
+        }
+    }
+
+    if bypass_count > 1 {
+        violations.push(Violation::MultipleBypassParameters);
+    }
+
+    violations
+}
+
+// Continuous parameters are checked at a few points, stepped parameters at every step
+fn string_round_trip_failures(parameter: &dyn Parameter) -> Vec<Violation> {
+    let info = parameter.info();
+
+    let values: Vec<ParameterValue> = match info.steps() {
+        0 => vec![0.0, 0.25, 0.5, 0.75, 1.0],
+        steps => (0..=steps).map(|step| step as f64 / steps as f64).collect(),
+    };
+
+    values.into_iter()
+        .filter_map(|value| {
+            let string = parameter.normalized_to_string(value);
+
+            // Formatting may round, so compare strings and not values
+            let round_trips = parameter.string_to_normalized(&string)
+                .is_some_and(|parsed| parameter.normalized_to_string(parsed) == string);
+
+            (!round_trips).then(|| Violation::StringRoundTrip { id: info.id(), value, string })
+        })
+        .collect()
+}
+
+/// Checks that state survives a save and load unchanged
+///
+/// Parameters are moved away from their defaults first, so state that leaves them out doesn't pass.
+/// State is saved the way the CLAP wrapper does, so the editor state and learned MIDI assignments are
+/// included.
+fn validate_state<P: Plugin>(plugin: &mut P) -> Vec<Violation> {
+    let result = catch("state", || {
+        for event in non_default_value_events(plugin) {
+            plugin.process_event(&event);
+        }
+
+        let mut saved_state = Vec::new();
+        if state::save_plugin_state(plugin, &mut saved_state, StateContext::Project).is_err() {
+            return vec![Violation::StateSaveFailed];
+        }
+
+        let mut other_plugin = P::new(host_info());
+        other_plugin.init();
+
+        if state::load_plugin_state(&mut other_plugin, &mut saved_state.as_slice(), StateContext::Project).is_err() {
+            return vec![Violation::StateLoadFailed];
+        }
+
+        let mut violations = parameter_mismatches(plugin, &other_plugin);
+
+        let mut saved_again = Vec::new();
+        if state::save_plugin_state(&other_plugin, &mut saved_again, StateContext::Project).is_err() {
+            violations.push(Violation::StateSaveFailed);
+        } else if saved_again != saved_state {
+            violations.push(Violation::StateNotIdempotent);
+        }
+
+        violations
+    });
+
+    result.unwrap_or_else(|violation| vec![violation])
+}
+
+// Read-only parameters are set by the plugin, so they're left alone
+fn non_default_value_events<P: Plugin>(plugin: &P) -> Vec<Event> {
+    plugin.with_parameters(|parameters| {
+        parameters.ids().iter()
+            .filter_map(|&id| parameters.get(id))
+            .filter(|parameter| !parameter.info().is_read_only())
+            .map(|parameter| Event::ParameterValue {
+                sample_offset: 0,
+                id: parameter.info().id(),
+                value: non_default_value(parameter),
+            })
+            .collect()
+    })
+}
+
+fn non_default_value(parameter: &dyn Parameter) -> ParameterValue {
+    let info = parameter.info();
+    let default_value = info.default_normalized_value();
+
+    match info.steps() {
+        0 if default_value < 0.5 => 0.75,
+        0 => 0.25,
+        steps => {
+            let default_step = (default_value * steps as f64).round() as usize;
+            let step = if default_step == 0 { 1 } else { default_step - 1 };
+            step as f64 / steps as f64
+        }
+    }
+}
+
+fn parameter_mismatches<P: Plugin>(saved_plugin: &P, loaded_plugin: &P) -> Vec<Violation> {
+    // Continuous values may go through plain values when saved
+    const EPSILON: f64 = 1e-6;
+
+    saved_plugin.with_parameters(|saved_parameters| {
+        loaded_plugin.with_parameters(|loaded_parameters| {
+            saved_parameters.ids().iter()
+                .filter_map(|&id| {
+                    let saved = saved_parameters.get(id)?.normalized_value();
+                    let loaded = loaded_parameters.get(id)?.normalized_value();
+
+                    ((saved - loaded).abs() > EPSILON).then_some(Violation::StateParameterMismatch { id, saved, loaded })
+                })
+                .collect()
+        })
+    })
+}
+
+/// Processes a sine followed by silence and checks the output for NaNs, infinities and denormals
+fn validate_processing<P: Plugin>(plugin: &mut P, config: &ValidationConfig, block_size: usize) -> Vec<Violation> {
+    let result = catch("processing", || {
+        let mut processor = create_processor(plugin, config);
+        let mut buffer = test_signal(config);
+
+        for (index, start) in (0..config.length).step_by(block_size).enumerate() {
+            let end = usize::min(start + block_size, config.length);
+            let mut block = buffer.slice_mut(start..end);
+
+            // Start with the parameters at their defaults, like a host that just loaded the plugin
+            let mut events = if index == 0 { default_value_events(plugin, 0) } else { Vec::new() };
+
+            let aux: Option<&Buffer> = None;
+            if let ProcessState::Error = rt_check::realtime(|| processor.process(&mut block, aux, None, events.drain(..))) {
+                return vec![Violation::ProcessError { block_size }];
+            }
+        }
+
+        output_violations(&buffer, block_size)
+    });
+
+    result.unwrap_or_else(|violation| vec![violation])
+}
+
+/// Sends events with sample offsets beyond the end of the block, which buggy hosts do
+fn validate_late_events<P: Plugin>(plugin: &mut P, config: &ValidationConfig) -> Vec<Violation> {
+    let block_size = config.max_block_size;
+
+    let result = catch("events beyond the block", || {
+        let mut processor = create_processor(plugin, config);
+        let mut buffer = test_signal(config);
+        let mut block = buffer.slice_mut(..usize::min(block_size, config.length));
+
+        let mut events = default_value_events(plugin, block.len());
+        if P::HAS_NOTE_INPUT {
+            events.push(Event::NoteOn { sample_offset: block.len(), channel: 0, key: 60, note: -1, velocity: 1.0 });
+            events.push(Event::NoteOff { sample_offset: block.len() + 1, channel: 0, key: 60, note: -1, velocity: 1.0 });
+        }
+
+        let aux: Option<&Buffer> = None;
+        if let ProcessState::Error = rt_check::realtime(|| processor.process(&mut block, aux, None, events.drain(..))) {
+            return vec![Violation::ProcessError { block_size }];
+        }
+
+        output_violations(&block, block_size)
+    });
+
+    result.unwrap_or_else(|violation| vec![violation])
+}
+
+fn create_processor<P: Plugin>(plugin: &mut P, config: &ValidationConfig) -> P::Processor {
+    let mut processor = plugin.create_processor(ProcessorConfig {
+        sample_rate: config.sample_rate,
+        min_block_size: 1,
+        max_block_size: config.max_block_size,
+        process_mode: ProcessMode::Realtime,
+        thread_pool: Default::default(),
+    });
+
+    processor.reset();
+    processor
+}
+
+fn test_signal(config: &ValidationConfig) -> Buffer {
+    let mut buffer = Buffer::new(2, config.length);
+
+    for channel in buffer.iter_channels_mut() {
+        for (index, sample) in channel.iter_mut().take(config.length / 2).enumerate() {
+            *sample = 0.5 * (std::f64::consts::TAU * 440.0 * index as f64 / config.sample_rate).sin() as f32;
+        }
+    }
+
+    buffer
+}
+
+fn default_value_events<P: Plugin>(plugin: &P, sample_offset: usize) -> Vec<Event> {
+    plugin.with_parameters(|parameters| {
+        parameters.ids().iter()
+            .filter_map(|&id| parameters.get(id))
+            .filter(|parameter| !parameter.info().is_read_only())
+            .map(|parameter| Event::ParameterValue {
+                sample_offset,
+                id: parameter.info().id(),
+                value: parameter.info().default_normalized_value(),
+            })
+            .collect()
+    })
+}
+
+fn output_violations(output: &impl Signal, block_size: usize) -> Vec<Violation> {
+    for channel in output.iter_channels() {
+        for (sample, value) in channel.iter().enumerate() {
+            if !value.is_finite() {
+                return vec![Violation::NonFiniteOutput { block_size, sample }];
+            }
+            if value.is_subnormal() {
+                return vec![Violation::DenormalOutput { block_size, sample }];
+            }
+        }
+    }
+
+    Vec::new()
+}
+
+// The format only exists with the testing feature, unit tests of this crate run without it
+#[cfg(feature = "testing")]
+const VALIDATOR_FORMAT: PluginFormat = PluginFormat::Testing;
+#[cfg(not(feature = "testing"))]
+const VALIDATOR_FORMAT: PluginFormat = PluginFormat::Clap;
+
+fn host_info() -> HostInfo {
+    HostInfo {
+        name: Some("Validator".to_string()),
+        format: VALIDATOR_FORMAT,
+        bundle_path: None,
+    }
+}
+
+// Realtime violations panic here whatever the global mode is, so they show up as violations
+fn catch<T>(check: &'static str, f: impl FnOnce() -> T) -> Result<T, Violation> {
+    catch_unwind(AssertUnwindSafe(|| rt_check::with_violation_mode(ViolationMode::Panic, f)))
+        .map_err(|payload| Violation::Panic { check, message: panic_message(payload) })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{Read, Write}, rc::Rc, sync::Arc};
+
+    use crate::{testing::test_plugin::{TestEditor, TestPlugin, TestProcessor, BYPASS, GAIN, LEVEL}, BoolParameter, Error, Event, FloatParameter, Host, HostInfo, LinearFloatRange, ParameterMap, Plugin, ProcessorConfig, StateContext};
+
+    use super::{assert_valid, validate_parameters, validate_state, Violation};
+
+    /// Test plugin that doesn't save its parameters
+    struct ForgetfulPlugin(TestPlugin);
+
+    impl Plugin for ForgetfulPlugin {
+        const NAME: &'static str = "Forgetful";
+        const VENDOR: &'static str = "plinth";
+        const VERSION: &'static str = "0.1";
+
+        type Processor = TestProcessor;
+        type Editor = TestEditor;
+        type Parameters = ParameterMap;
+
+        fn new(host_info: HostInfo) -> Self {
+            Self(TestPlugin::new(host_info))
+        }
+
+        fn init(&mut self) {}
+
+        fn with_parameters<T>(&self, f: impl FnMut(&Self::Parameters) -> T) -> T {
+            self.0.with_parameters(f)
+        }
+
+        fn process_event(&mut self, event: &Event) {
+            self.0.process_event(event);
+        }
+
+        fn create_processor(&mut self, config: ProcessorConfig) -> Self::Processor {
+            self.0.create_processor(config)
+        }
+
+        fn create_editor(&mut self, host: Rc<dyn Host>) -> Self::Editor {
+            self.0.create_editor(host)
+        }
+
+        fn save_state(&self, _writer: &mut impl Write, _context: StateContext) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn load_state(&mut self, _reader: &mut impl Read, _context: StateContext) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plugin_is_valid() {
+        assert_valid::<TestPlugin>();
+    }
+
+    #[test]
+    fn state_has_to_restore_parameters() {
+        let mut plugin = ForgetfulPlugin::new(super::host_info());
+
+        // Every parameter is moved away from its default before saving
+        assert_eq!(validate_state(&mut plugin), [
+            Violation::StateParameterMismatch { id: GAIN, saved: 0.25, loaded: 1.0 },
+            Violation::StateParameterMismatch { id: LEVEL, saved: 0.75, loaded: 1.0 },
+            Violation::StateParameterMismatch { id: BYPASS, saved: 1.0, loaded: 0.0 },
+        ]);
+    }
+
+    #[test]
+    fn parameter_violations() {
+        let mut parameters = ParameterMap::new();
+        parameters.add(FloatParameter::new(0u32, "Zero", Arc::new(LinearFloatRange::new(0.0, 1.0))));
+        parameters.add(BoolParameter::new(1u32, "Bypass").as_bypass());
+        parameters.add(BoolParameter::new(2u32, "Also bypass").as_bypass());
+
+        assert_eq!(validate_parameters(&parameters), [Violation::ZeroParameterId, Violation::MultipleBypassParameters]);
+    }
+}
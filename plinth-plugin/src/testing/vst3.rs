@@ -0,0 +1,731 @@
+use std::{cell::{Cell, RefCell}, ffi::c_void, marker::PhantomData, ptr::null_mut};
+
+use vst3::{ComPtr, ComWrapper, Interface};
+use vst3::Steinberg::{int32, int64, kInvalidArgument, kResultOk, kResultTrue, tresult, FIDString, IBStream, IBStreamTrait, IBStream_::IStreamSeekMode_, IPlugFrame, IPlugFrameTrait, IPlugView, IPlugViewContentScaleSupport, IPlugViewContentScaleSupportTrait, IPlugViewTrait, IPluginBaseTrait, IPluginFactoryTrait, TBool, ViewRect};
+use vst3::Steinberg::Vst::{AudioBusBuffers, ControllerNumbers_::kPitchBend, Event_::EventTypes_, IAudioProcessor, IAudioProcessorTrait, IComponent, IComponentHandler, IComponentHandler2, IComponentHandler2Trait, IComponentHandlerTrait, IComponentTrait, IEditController, IEditControllerTrait, IEventList, IEventListTrait, IMidiLearn, IMidiLearnTrait, IMidiMapping, IMidiMappingTrait, IParamValueQueue, IParamValueQueueTrait, IParameterChanges, IParameterChangesTrait, NoteOffEvent, NoteOnEvent, ParamID, ParamValue, ProcessData, ProcessModes_, ProcessSetup, SymbolicSampleSizes_, ViewType::kEditor};
+
+use crate::{rt_check, vst3::{Factory, Vst3Plugin}, Event, ParameterId};
+
+/// Call made by the plugin on the component handler
+#[derive(Clone, Debug, PartialEq)]
+pub enum HandlerCall {
+    BeginEdit(ParameterId),
+    PerformEdit(ParameterId, ParamValue),
+    EndEdit(ParameterId),
+    RestartComponent(i32),
+    SetDirty(bool),
+}
+
+/// Fake VST3 host for testing plugins without a DAW or a display
+///
+/// The plugin is created through its [`Factory`] and driven through COM like a real host would,
+/// with in-memory streams, parameter changes and event lists. Calls to the component handler and
+/// resize requests from the editor are recorded so tests can assert on them.
+pub struct TestHost<P: Vst3Plugin + 'static> {
+    component: ComPtr<IComponent>,
+    processor: ComPtr<IAudioProcessor>,
+    controller: ComPtr<IEditController>,
+
+    handler: ComWrapper<ComponentHandler>,
+    frame: ComWrapper<PlugFrame>,
+    view: Option<ComPtr<IPlugView>>,
+    active: bool,
+
+    _phantom_plugin: PhantomData<P>,
+}
+
+impl<P: Vst3Plugin + 'static> TestHost<P> {
+    /// Creates and initializes the plugin and connects the component handler
+    pub fn new() -> Self {
+        let factory = unsafe { ComPtr::from_raw(Factory::new().with_plugin::<P>().into_raw()) }.unwrap();
+
+        let class_id = P::CLASS_ID.to_be_bytes();
+        let mut instance = null_mut();
+        let result = unsafe { factory.createInstance(class_id.as_ptr() as _, IComponent::IID.as_ptr() as _, &mut instance) };
+        assert_eq!(result, kResultOk, "Factory didn't create a plugin with class id {:x}", P::CLASS_ID);
+
+        let component = unsafe { ComPtr::from_raw(instance as *mut IComponent) }.unwrap();
+        assert_eq!(unsafe { component.initialize(null_mut()) }, kResultOk, "Plugin failed to initialize");
+
+        let processor = component.cast::<IAudioProcessor>().unwrap();
+        let controller = component.cast::<IEditController>().unwrap();
+
+        let handler = ComWrapper::new(ComponentHandler::default());
+        unsafe { controller.setComponentHandler(handler.to_com_ptr::<IComponentHandler>().unwrap().as_ptr()) };
+
+        Self {
+            component,
+            processor,
+            controller,
+
+            handler,
+            frame: ComWrapper::new(PlugFrame::default()),
+            view: None,
+            active: false,
+
+            _phantom_plugin: PhantomData,
+        }
+    }
+
+    pub fn activate(&mut self, sample_rate: f64, max_block_size: usize) -> bool {
+        assert!(!self.active, "Plugin is already active");
+
+        let mut setup = ProcessSetup {
+            processMode: ProcessModes_::kRealtime as _,
+            symbolicSampleSize: SymbolicSampleSizes_::kSample32 as _,
+            maxSamplesPerBlock: max_block_size as _,
+            sampleRate: sample_rate,
+        };
+
+        unsafe {
+            if self.processor.setupProcessing(&mut setup) != kResultOk
+                || self.component.setActive(1) != kResultOk
+                || self.processor.setProcessing(1) != kResultOk
+            {
+                return false;
+            }
+        }
+
+        self.active = true;
+        true
+    }
+
+    pub fn deactivate(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        unsafe {
+            self.processor.setProcessing(0);
+            self.component.setActive(0);
+        }
+
+        self.active = false;
+    }
+
+    /// Processes a stereo buffer in place
+    ///
+    /// Note events go to the event list and parameter changes to the parameter changes. MIDI CCs and pitch bend
+    /// are mapped to parameters through `IMidiMapping` like hosts do, and dropped if the plugin doesn't map them.
+    /// Plugins with an aux input get silence on it.
+    pub fn process(&mut self, buffer: &mut [Vec<f32>], events: &[Event]) -> bool {
+        assert!(self.active, "Plugin needs to be activated before processing");
+        assert_eq!(buffer.len(), 2, "Only stereo buffers are supported");
+
+        let frames_count = buffer[0].len();
+        assert!(buffer.iter().all(|channel| channel.len() == frames_count), "Channel lengths differ");
+
+        let mut aux = vec![vec![0.0; frames_count]; 2];
+
+        // Input and output share the same buffers, so the plugin processes in place
+        let mut channel_pointers: Vec<_> = buffer.iter_mut().map(|channel| channel.as_mut_ptr()).collect();
+        let mut aux_pointers: Vec<_> = aux.iter_mut().map(|channel| channel.as_mut_ptr()).collect();
+
+        let mut inputs = vec![audio_bus_buffers(&mut channel_pointers)];
+        if P::HAS_AUX_INPUT {
+            inputs.push(audio_bus_buffers(&mut aux_pointers));
+        }
+        let mut outputs = [audio_bus_buffers(&mut channel_pointers)];
+
+        let input_parameter_changes = ComWrapper::new(ParameterChanges::default());
+        let output_parameter_changes = ComWrapper::new(ParameterChanges::default());
+        let input_events = ComWrapper::new(EventList::default());
+        let output_events = ComWrapper::new(EventList::default());
+
+        for event in events {
+            self.add_event(event, &input_parameter_changes, &input_events);
+        }
+
+        let mut data: ProcessData = unsafe { std::mem::zeroed() };
+        data.processMode = ProcessModes_::kRealtime as _;
+        data.symbolicSampleSize = SymbolicSampleSizes_::kSample32 as _;
+        data.numSamples = frames_count as _;
+        data.numInputs = inputs.len() as _;
+        data.numOutputs = outputs.len() as _;
+        data.inputs = inputs.as_mut_ptr();
+        data.outputs = outputs.as_mut_ptr();
+        data.inputParameterChanges = input_parameter_changes.to_com_ptr::<IParameterChanges>().unwrap().as_ptr();
+        data.outputParameterChanges = output_parameter_changes.to_com_ptr::<IParameterChanges>().unwrap().as_ptr();
+        data.inputEvents = input_events.to_com_ptr::<IEventList>().unwrap().as_ptr();
+        data.outputEvents = output_events.to_com_ptr::<IEventList>().unwrap().as_ptr();
+
+        unsafe { self.processor.process(&mut data) == kResultOk }
+    }
+
+    pub fn set_parameter(&mut self, id: impl Into<ParameterId>, value: ParamValue) -> bool {
+        unsafe { self.controller.setParamNormalized(id.into(), value) == kResultOk }
+    }
+
+    pub fn parameter_value(&self, id: impl Into<ParameterId>) -> ParamValue {
+        unsafe { self.controller.getParamNormalized(id.into()) }
+    }
+
+    pub fn save_state(&mut self) -> Option<Vec<u8>> {
+        let stream = ComWrapper::new(MemoryStream::default());
+
+        if unsafe { self.component.getState(stream.to_com_ptr::<IBStream>().unwrap().as_ptr()) } != kResultOk {
+            return None;
+        }
+
+        Some(stream.data.take())
+    }
+
+    /// Loads the state into the component and then into the controller, like hosts do
+    pub fn load_state(&mut self, data: &[u8]) -> bool {
+        let stream = ComWrapper::new(MemoryStream::new(data));
+        let stream_ptr = stream.to_com_ptr::<IBStream>().unwrap();
+
+        if unsafe { self.component.setState(stream_ptr.as_ptr()) } != kResultOk {
+            return false;
+        }
+
+        stream.position.set(0);
+        unsafe { self.controller.setComponentState(stream_ptr.as_ptr()) == kResultOk }
+    }
+
+    pub fn save_editor_state(&mut self) -> Option<Vec<u8>> {
+        let stream = ComWrapper::new(MemoryStream::default());
+
+        if unsafe { self.controller.getState(stream.to_com_ptr::<IBStream>().unwrap().as_ptr()) } != kResultOk {
+            return None;
+        }
+
+        Some(stream.data.take())
+    }
+
+    pub fn load_editor_state(&mut self, data: &[u8]) -> bool {
+        let stream = ComWrapper::new(MemoryStream::new(data));
+        unsafe { self.controller.setState(stream.to_com_ptr::<IBStream>().unwrap().as_ptr()) == kResultOk }
+    }
+
+    /// Parameter the plugin maps a MIDI controller to, on the first event bus
+    pub fn midi_controller_assignment(&self, channel: i16, controller: i16) -> Option<ParameterId> {
+        let midi_mapping = self.controller.cast::<IMidiMapping>()?;
+
+        let mut id = 0;
+        let result = unsafe { midi_mapping.getMidiControllerAssignment(0, channel, controller, &mut id) };
+
+        (result == kResultTrue).then_some(id)
+    }
+
+    /// Sends live controller input for MIDI learn, returns true if the plugin learned it
+    pub fn live_midi_controller_input(&mut self, channel: i16, controller: i16) -> bool {
+        let Some(midi_learn) = self.controller.cast::<IMidiLearn>() else {
+            return false;
+        };
+
+        unsafe { midi_learn.onLiveMIDIControllerInput(0, channel, controller) == kResultOk }
+    }
+
+    /// Creates the editor view without attaching it to a window
+    pub fn create_view(&mut self) -> bool {
+        if self.view.is_some() {
+            return false;
+        }
+
+        let Some(view) = (unsafe { ComPtr::from_raw(self.controller.createView(kEditor)) }) else {
+            return false;
+        };
+
+        unsafe { view.setFrame(self.frame.to_com_ptr::<IPlugFrame>().unwrap().as_ptr()) };
+        self.view = Some(view);
+
+        true
+    }
+
+    /// The editor view, for calling parts of `IPlugView` this host doesn't wrap
+    pub fn view(&self) -> Option<&ComPtr<IPlugView>> {
+        self.view.as_ref()
+    }
+
+    pub fn set_content_scale(&mut self, scale: f32) -> bool {
+        let Some(scale_support) = self.view.as_ref().and_then(|view| view.cast::<IPlugViewContentScaleSupport>()) else {
+            return false;
+        };
+
+        unsafe { scale_support.setContentScaleFactor(scale) == kResultOk }
+    }
+
+    /// Calls made on the component handler since the last call
+    pub fn take_handler_calls(&mut self) -> Vec<HandlerCall> {
+        self.handler.calls.take()
+    }
+
+    /// Sizes requested through `IPlugFrame::resizeView`
+    pub fn resize_requests(&self) -> Vec<(i32, i32)> {
+        self.frame.resize_requests.borrow().clone()
+    }
+
+    fn add_event(&self, event: &Event, parameter_changes: &ParameterChanges, event_list: &EventList) {
+        match *event {
+            Event::NoteOn { sample_offset, channel, key, note, velocity } => {
+                let mut vst3_event: vst3::Steinberg::Vst::Event = unsafe { std::mem::zeroed() };
+                vst3_event.sampleOffset = sample_offset as _;
+                vst3_event.r#type = EventTypes_::kNoteOnEvent as _;
+                vst3_event.__field0.noteOn = NoteOnEvent {
+                    channel,
+                    pitch: key,
+                    tuning: 0.0,
+                    velocity: velocity as _,
+                    length: 0,
+                    noteId: note,
+                };
+
+                event_list.events.borrow_mut().push(vst3_event);
+            },
+
+            Event::NoteOff { sample_offset, channel, key, note, velocity } => {
+                let mut vst3_event: vst3::Steinberg::Vst::Event = unsafe { std::mem::zeroed() };
+                vst3_event.sampleOffset = sample_offset as _;
+                vst3_event.r#type = EventTypes_::kNoteOffEvent as _;
+                vst3_event.__field0.noteOff = NoteOffEvent {
+                    channel,
+                    pitch: key,
+                    velocity: velocity as _,
+                    noteId: note,
+                    tuning: 0.0,
+                };
+
+                event_list.events.borrow_mut().push(vst3_event);
+            },
+
+            Event::PitchBend { sample_offset, channel, semitones, .. } => {
+                if let Some(id) = self.midi_controller_assignment(channel, kPitchBend as _) {
+                    parameter_changes.add_point(id, sample_offset, semitones / 4.0 + 0.5);
+                }
+            },
+
+            Event::ControlChange { sample_offset, channel, controller, value } => {
+                if let Some(id) = self.midi_controller_assignment(channel, controller) {
+                    parameter_changes.add_point(id, sample_offset, value);
+                }
+            },
+
+            Event::ParameterValue { sample_offset, id, value } => {
+                parameter_changes.add_point(id, sample_offset, value);
+            },
+
+            _ => {},
+        }
+    }
+}
+
+impl<P: Vst3Plugin + 'static> Default for TestHost<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Vst3Plugin + 'static> Drop for TestHost<P> {
+    fn drop(&mut self) {
+        self.view = None;
+        self.deactivate();
+
+        unsafe {
+            self.controller.setComponentHandler(null_mut());
+            self.component.terminate();
+        }
+    }
+}
+
+fn audio_bus_buffers(channel_pointers: &mut [*mut f32]) -> AudioBusBuffers {
+    let mut buffers: AudioBusBuffers = unsafe { std::mem::zeroed() };
+    buffers.numChannels = channel_pointers.len() as _;
+    buffers.__field0.channelBuffers32 = channel_pointers.as_mut_ptr();
+    buffers
+}
+
+#[derive(Default)]
+struct ComponentHandler {
+    calls: RefCell<Vec<HandlerCall>>,
+}
+
+impl vst3::Class for ComponentHandler {
+    type Interfaces = (IComponentHandler, IComponentHandler2);
+}
+
+impl IComponentHandlerTrait for ComponentHandler {
+    unsafe fn beginEdit(&self, id: ParamID) -> tresult {
+        self.calls.borrow_mut().push(HandlerCall::BeginEdit(id));
+        kResultOk
+    }
+
+    unsafe fn performEdit(&self, id: ParamID, value_normalized: ParamValue) -> tresult {
+        self.calls.borrow_mut().push(HandlerCall::PerformEdit(id, value_normalized));
+        kResultOk
+    }
+
+    unsafe fn endEdit(&self, id: ParamID) -> tresult {
+        self.calls.borrow_mut().push(HandlerCall::EndEdit(id));
+        kResultOk
+    }
+
+    unsafe fn restartComponent(&self, flags: int32) -> tresult {
+        self.calls.borrow_mut().push(HandlerCall::RestartComponent(flags));
+        kResultOk
+    }
+}
+
+impl IComponentHandler2Trait for ComponentHandler {
+    unsafe fn setDirty(&self, state: TBool) -> tresult {
+        self.calls.borrow_mut().push(HandlerCall::SetDirty(state != 0));
+        kResultOk
+    }
+
+    unsafe fn requestOpenEditor(&self, _name: FIDString) -> tresult {
+        kResultOk
+    }
+
+    unsafe fn startGroupEdit(&self) -> tresult {
+        kResultOk
+    }
+
+    unsafe fn finishGroupEdit(&self) -> tresult {
+        kResultOk
+    }
+}
+
+#[derive(Default)]
+struct PlugFrame {
+    resize_requests: RefCell<Vec<(i32, i32)>>,
+}
+
+impl vst3::Class for PlugFrame {
+    type Interfaces = (IPlugFrame,);
+}
+
+impl IPlugFrameTrait for PlugFrame {
+    unsafe fn resizeView(&self, _view: *mut IPlugView, new_size: *mut ViewRect) -> tresult {
+        if new_size.is_null() {
+            return kInvalidArgument;
+        }
+
+        let new_size = unsafe { &*new_size };
+        self.resize_requests.borrow_mut().push((new_size.right - new_size.left, new_size.bottom - new_size.top));
+
+        kResultOk
+    }
+}
+
+/// In-memory `IBStream`, also used by the unit tests of the VST3 wrapper
+#[derive(Default)]
+pub(crate) struct MemoryStream {
+    pub(crate) data: RefCell<Vec<u8>>,
+    position: Cell<usize>,
+    max_chunk_size: Option<usize>,
+}
+
+impl MemoryStream {
+    pub(crate) fn new(data: &[u8]) -> Self {
+        Self {
+            data: RefCell::new(data.to_vec()),
+            ..Default::default()
+        }
+    }
+
+    /// Reads and writes at most this many bytes per call, like hosts that stream state in chunks
+    pub(crate) fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        self.max_chunk_size = Some(max_chunk_size);
+        self
+    }
+
+    fn chunk_size(&self, num_bytes: int32) -> usize {
+        usize::min(num_bytes.max(0) as _, self.max_chunk_size.unwrap_or(usize::MAX))
+    }
+}
+
+impl vst3::Class for MemoryStream {
+    type Interfaces = (IBStream,);
+}
+
+impl IBStreamTrait for MemoryStream {
+    unsafe fn read(&self, buffer: *mut c_void, num_bytes: int32, num_bytes_read: *mut int32) -> tresult {
+        let data = self.data.borrow();
+        let position = self.position.get();
+        let count = usize::min(self.chunk_size(num_bytes), data.len().saturating_sub(position));
+
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr().add(position), buffer as *mut u8, count) };
+        self.position.set(position + count);
+
+        if !num_bytes_read.is_null() {
+            unsafe { *num_bytes_read = count as _ };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn write(&self, buffer: *mut c_void, num_bytes: int32, num_bytes_written: *mut int32) -> tresult {
+        let mut data = self.data.borrow_mut();
+        let position = self.position.get();
+        let count = self.chunk_size(num_bytes);
+
+        if data.len() < position + count {
+            data.resize(position + count, 0);
+        }
+
+        data[position..position + count].copy_from_slice(unsafe { std::slice::from_raw_parts(buffer as *const u8, count) });
+        self.position.set(position + count);
+
+        if !num_bytes_written.is_null() {
+            unsafe { *num_bytes_written = count as _ };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn seek(&self, pos: int64, mode: int32, result: *mut int64) -> tresult {
+        // On some platforms, these casts are needed
+        #[allow(clippy::unnecessary_cast)]
+        let base = if mode == IStreamSeekMode_::kIBSeekSet as int32 {
+            0
+        } else if mode == IStreamSeekMode_::kIBSeekCur as int32 {
+            self.position.get() as int64
+        } else if mode == IStreamSeekMode_::kIBSeekEnd as int32 {
+            self.data.borrow().len() as int64
+        } else {
+            return kInvalidArgument;
+        };
+
+        let position = (base + pos).max(0);
+        self.position.set(position as _);
+
+        if !result.is_null() {
+            unsafe { *result = position };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn tell(&self, pos: *mut int64) -> tresult {
+        if pos.is_null() {
+            return kInvalidArgument;
+        }
+
+        unsafe { *pos = self.position.get() as _ };
+        kResultOk
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ParameterChanges {
+    queues: RefCell<Vec<ComWrapper<ParamValueQueue>>>,
+}
+
+impl ParameterChanges {
+    fn queue_index(&self, id: ParamID) -> usize {
+        let mut queues = self.queues.borrow_mut();
+
+        match queues.iter().position(|queue| queue.id == id) {
+            Some(index) => index,
+            None => {
+                queues.push(ComWrapper::new(ParamValueQueue {
+                    id,
+                    points: Default::default(),
+                }));
+
+                queues.len() - 1
+            },
+        }
+    }
+
+    pub(crate) fn add_point(&self, id: ParamID, sample_offset: usize, value: ParamValue) {
+        let index = self.queue_index(id);
+        self.queues.borrow()[index].points.borrow_mut().push((sample_offset as _, value));
+    }
+}
+
+impl vst3::Class for ParameterChanges {
+    type Interfaces = (IParameterChanges,);
+}
+
+impl IParameterChangesTrait for ParameterChanges {
+    unsafe fn getParameterCount(&self) -> int32 {
+        self.queues.borrow().len() as _
+    }
+
+    unsafe fn getParameterData(&self, index: int32) -> *mut IParamValueQueue {
+        let queues = self.queues.borrow();
+
+        match usize::try_from(index).ok().and_then(|index| queues.get(index)) {
+            Some(queue) => queue.as_com_ref::<IParamValueQueue>().unwrap().as_ptr(),
+            None => null_mut(),
+        }
+    }
+
+    unsafe fn addParameterData(&self, id: *const ParamID, index: *mut int32) -> *mut IParamValueQueue {
+        if id.is_null() {
+            return null_mut();
+        }
+
+        // Hosts preallocate these, so the output changes the plugin sends while processing aren't its allocations
+        let queue_index = rt_check::permit(|| self.queue_index(unsafe { *id }));
+
+        if !index.is_null() {
+            unsafe { *index = queue_index as _ };
+        }
+
+        unsafe { self.getParameterData(queue_index as _) }
+    }
+}
+
+struct ParamValueQueue {
+    id: ParamID,
+    points: RefCell<Vec<(int32, ParamValue)>>,
+}
+
+impl vst3::Class for ParamValueQueue {
+    type Interfaces = (IParamValueQueue,);
+}
+
+impl IParamValueQueueTrait for ParamValueQueue {
+    unsafe fn getParameterId(&self) -> ParamID {
+        self.id
+    }
+
+    unsafe fn getPointCount(&self) -> int32 {
+        self.points.borrow().len() as _
+    }
+
+    unsafe fn getPoint(&self, index: int32, sample_offset: *mut int32, value: *mut ParamValue) -> tresult {
+        let points = self.points.borrow();
+        let Some(&(point_offset, point_value)) = usize::try_from(index).ok().and_then(|index| points.get(index)) else {
+            return kInvalidArgument;
+        };
+
+        unsafe {
+            *sample_offset = point_offset;
+            *value = point_value;
+        }
+
+        kResultOk
+    }
+
+    unsafe fn addPoint(&self, sample_offset: int32, value: ParamValue, index: *mut int32) -> tresult {
+        let mut points = self.points.borrow_mut();
+        rt_check::permit(|| points.push((sample_offset, value)));
+
+        if !index.is_null() {
+            unsafe { *index = points.len() as int32 - 1 };
+        }
+
+        kResultOk
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EventList {
+    pub(crate) events: RefCell<Vec<vst3::Steinberg::Vst::Event>>,
+}
+
+impl vst3::Class for EventList {
+    type Interfaces = (IEventList,);
+}
+
+impl IEventListTrait for EventList {
+    unsafe fn getEventCount(&self) -> int32 {
+        self.events.borrow().len() as _
+    }
+
+    unsafe fn getEvent(&self, index: int32, e: *mut vst3::Steinberg::Vst::Event) -> tresult {
+        let events = self.events.borrow();
+        let Some(event) = usize::try_from(index).ok().and_then(|index| events.get(index)) else {
+            return kInvalidArgument;
+        };
+
+        unsafe { *e = *event };
+        kResultOk
+    }
+
+    unsafe fn addEvent(&self, e: *mut vst3::Steinberg::Vst::Event) -> tresult {
+        if e.is_null() {
+            return kInvalidArgument;
+        }
+
+        rt_check::permit(|| self.events.borrow_mut().push(unsafe { *e }));
+        kResultOk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vst3::Steinberg::Vst::RestartFlags_::kMidiCCAssignmentChanged;
+
+    use crate::Event;
+    use crate::testing::test_plugin::{state_load_count, TestEditor, TestPlugin, GAIN};
+
+    use super::{HandlerCall, TestHost};
+
+    #[test]
+    fn process_and_state_round_trip() {
+        let mut host = TestHost::<TestPlugin>::new();
+        assert!(host.activate(48000.0, 64));
+
+        let mut buffer = vec![vec![1.0; 64]; 2];
+        let events = [Event::ParameterValue { sample_offset: 0, id: GAIN, value: 0.5 }];
+        assert!(host.process(&mut buffer, &events));
+        assert!(buffer.iter().flatten().all(|&sample| sample == 0.5));
+
+        // CC 7 is mapped to the gain by default
+        assert_eq!(host.midi_controller_assignment(0, 7), Some(GAIN));
+        let events = [Event::ControlChange { sample_offset: 0, channel: 0, controller: 7, value: 0.0 }];
+        assert!(host.process(&mut buffer, &events));
+        assert!(buffer.iter().flatten().all(|&sample| sample == 0.0));
+
+        assert!(host.set_parameter(GAIN, 0.25));
+        let state = host.save_state().unwrap();
+        host.deactivate();
+
+        let mut other_host = TestHost::<TestPlugin>::new();
+        assert_eq!(other_host.parameter_value(GAIN), 1.0);
+        assert!(other_host.load_state(&state));
+        assert_eq!(other_host.parameter_value(GAIN), 0.25);
+
+        // Loading into the component and the controller only loads the plugin state once
+        assert_eq!(state_load_count(), 1);
+    }
+
+    #[test]
+    fn invalid_parameter_changes_are_rejected() {
+        let mut host = TestHost::<TestPlugin>::new();
+
+        assert!(!host.set_parameter(GAIN + 100, 0.5));
+        assert!(!host.set_parameter(GAIN, f64::NAN));
+        assert!(!host.set_parameter(GAIN, f64::INFINITY));
+        assert_eq!(host.parameter_value(GAIN), 1.0);
+
+        assert!(host.set_parameter(GAIN, 0.5));
+        assert_eq!(host.parameter_value(GAIN), 0.5);
+    }
+
+    #[test]
+    fn editor_edits() {
+        let mut host = TestHost::<TestPlugin>::new();
+        assert!(host.create_view());
+
+        TestEditor::last_created().unwrap().set_gain(0.5);
+        assert_eq!(host.take_handler_calls(), [
+            HandlerCall::BeginEdit(GAIN),
+            HandlerCall::PerformEdit(GAIN, 0.5),
+            HandlerCall::EndEdit(GAIN),
+        ]);
+        assert_eq!(host.parameter_value(GAIN), 0.5);
+
+        assert!(host.set_content_scale(2.0));
+        assert_eq!(host.resize_requests(), [(400, 200)]);
+        assert_eq!(host.take_handler_calls(), [HandlerCall::SetDirty(true)]);
+    }
+
+    #[test]
+    fn midi_learn() {
+        let mut host = TestHost::<TestPlugin>::new();
+        assert!(host.create_view());
+
+        // Not learning yet
+        assert!(!host.live_midi_controller_input(0, 74));
+
+        TestEditor::last_created().unwrap().start_gain_midi_learn();
+        assert!(host.live_midi_controller_input(0, 74));
+        assert_eq!(host.take_handler_calls(), [HandlerCall::RestartComponent(kMidiCCAssignmentChanged as _)]);
+        assert_eq!(host.midi_controller_assignment(0, 74), Some(GAIN));
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+pub(crate) trait ThreadPoolBackend: Send + Sync {
+    /// Run `task` for every index in `0..task_count` and wait for all of them to finish
+    ///
+    /// Return false if the tasks couldn't be executed
+    fn execute(&self, task_count: usize, task: &(dyn Fn(usize) + Sync)) -> bool;
+}
+
+/// Host-provided thread pool for splitting up processing work
+///
+/// If the host doesn't provide a thread pool, tasks are executed serially on the calling thread.
+#[derive(Clone, Default)]
+pub struct ThreadPool {
+    backend: Option<Arc<dyn ThreadPoolBackend>>,
+}
+
+impl ThreadPool {
+    pub(crate) fn new(backend: Arc<dyn ThreadPoolBackend>) -> Self {
+        Self {
+            backend: Some(backend),
+        }
+    }
+
+    pub fn is_parallel(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Call `task` for every index in `0..task_count`, possibly in parallel
+    ///
+    /// Must only be called from `Processor::process`. Returns when all tasks have finished.
+    pub fn parallel_for(&self, task_count: usize, task: impl Fn(usize) + Sync) {
+        if task_count == 0 {
+            return;
+        }
+
+        if let Some(backend) = self.backend.as_ref() && task_count > 1 && backend.execute(task_count, &task) {
+            return;
+        }
+
+        for task_index in 0..task_count {
+            task(task_index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct FailingBackend;
+
+    impl ThreadPoolBackend for FailingBackend {
+        fn execute(&self, _task_count: usize, _task: &(dyn Fn(usize) + Sync)) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn serial_fallback() {
+        for thread_pool in [ThreadPool::default(), ThreadPool::new(Arc::new(FailingBackend))] {
+            let sum = AtomicUsize::new(0);
+            thread_pool.parallel_for(10, |index| { sum.fetch_add(index, Ordering::Relaxed); });
+            assert_eq!(sum.load(Ordering::Relaxed), 45);
+        }
+    }
+}
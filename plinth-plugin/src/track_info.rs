@@ -0,0 +1,21 @@
+use crate::Color;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrackType {
+    #[default]
+    Regular,
+    Return,
+    Bus,
+    Master,
+}
+
+/// Information about the track/channel the plugin is inserted on
+/// 
+/// Hosts only provide the fields they know about, so everything is optional.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackInfo {
+    pub name: Option<String>,
+    pub color: Option<Color>,
+    pub channel_count: Option<usize>,
+    pub track_type: TrackType,
+}
@@ -1,9 +1,9 @@
-use std::cell::OnceCell;
+use std::cell::{OnceCell, RefCell};
 use std::rc::{Rc, Weak};
 use std::sync::Arc;
 
 use raw_window_handle::RawWindowHandle;
-use plugin_canvas::{event::EventResponse, window::WindowAttributes, Event};
+use plugin_canvas::{event::{EventCallback, EventResponse}, window::WindowAttributes, Event};
 use slint::platform::WindowAdapter;
 
 use crate::{platform::PluginCanvasPlatform, view::PluginView, window_adapter::{PluginCanvasWindowAdapter, WINDOW_ADAPTER_FROM_SLINT, WINDOW_TO_SLINT}};
@@ -16,39 +16,64 @@ impl SlintEditor {
         window_attributes: WindowAttributes,
         view_builder: B
     ) -> Rc<EditorHandle>
+    where
+        C: PluginView + 'static,
+        B: Fn(Arc<plugin_canvas::Window>) -> C + 'static,
+    {
+        Self::open_window(Some(parent), window_attributes, view_builder)
+    }
+
+    /// Opens the editor in a top-level window which stays hidden until [`EditorHandle::show`] is called
+    pub fn open_floating<C, B>(
+        window_attributes: WindowAttributes,
+        view_builder: B
+    ) -> Rc<EditorHandle>
+    where
+        C: PluginView + 'static,
+        B: Fn(Arc<plugin_canvas::Window>) -> C + 'static,
+    {
+        Self::open_window(None, window_attributes, view_builder)
+    }
+
+    fn open_window<C, B>(
+        parent: Option<RawWindowHandle>,
+        window_attributes: WindowAttributes,
+        view_builder: B
+    ) -> Rc<EditorHandle>
     where
         C: PluginView + 'static,
         B: Fn(Arc<plugin_canvas::Window>) -> C + 'static,
     {
         let editor_handle = Rc::new(EditorHandle::new());
 
-        let window = plugin_canvas::Window::open(
-            parent,
-            window_attributes.clone(),
-            {
-                let editor_weak_ptr = Rc::downgrade(&editor_handle).into_raw();
-                let editor_thread = std::thread::current().id();
-
-                Box::new(move |event| {
-                    if std::thread::current().id() != editor_thread {
-                        log::warn!("Tried to call event callback from non-editor thread");
-                        return EventResponse::Ignored;
-                    }
-
-                    let editor_weak = unsafe { Weak::from_raw(editor_weak_ptr) };
-                    let response = if let Some(editor_handle) = editor_weak.upgrade() {
-                        editor_handle.on_event(&event)
-                    } else {
-                        EventResponse::Ignored
-                    };
-
-                    // Leak the weak reference to avoid dropping it
-                    let _ = editor_weak.into_raw();
-
-                    response
-                })
-            },
-        ).unwrap();
+        let event_callback: Box<EventCallback> = {
+            let editor_weak_ptr = Rc::downgrade(&editor_handle).into_raw();
+            let editor_thread = std::thread::current().id();
+
+            Box::new(move |event| {
+                if std::thread::current().id() != editor_thread {
+                    log::warn!("Tried to call event callback from non-editor thread");
+                    return EventResponse::Ignored;
+                }
+
+                let editor_weak = unsafe { Weak::from_raw(editor_weak_ptr) };
+                let response = if let Some(editor_handle) = editor_weak.upgrade() {
+                    editor_handle.on_event(&event)
+                } else {
+                    EventResponse::Ignored
+                };
+
+                // Leak the weak reference to avoid dropping it
+                let _ = editor_weak.into_raw();
+
+                response
+            })
+        };
+
+        let window = match parent {
+            Some(parent) => plugin_canvas::Window::open(parent, window_attributes, event_callback),
+            None => plugin_canvas::Window::open_floating(window_attributes, event_callback),
+        }.unwrap();
 
         // It's ok if this fails as it just means it has already been set
         slint::platform::set_platform(Box::new(PluginCanvasPlatform::default())).ok();
@@ -69,6 +94,7 @@ impl SlintEditor {
 
 pub struct EditorHandle {
     window_adapter: OnceCell<Rc<PluginCanvasWindowAdapter>>,
+    close_callback: RefCell<Option<Box<dyn Fn()>>>,
 }
 
 impl EditorHandle {
@@ -77,6 +103,10 @@ impl EditorHandle {
     }
 
     pub fn on_event(&self, event: &Event) -> EventResponse {
+        if let Event::CloseRequested = event && let Some(close_callback) = self.close_callback.borrow().as_ref() {
+            close_callback();
+        }
+
         if let Some(window_adapter) = self.window_adapter() {
             window_adapter.on_event(event)
         } else {
@@ -109,9 +139,39 @@ impl EditorHandle {
         }
     }
 
+    pub fn set_title(&self, title: &str) {
+        if let Some(window_adapter) = self.window_adapter() && let Err(error) = window_adapter.plugin_canvas_window().set_title(title) {
+            log::warn!("Failed to set window title: {error:?}");
+        }
+    }
+
+    pub fn set_transient_for(&self, window: RawWindowHandle) {
+        if let Some(window_adapter) = self.window_adapter() && let Err(error) = window_adapter.plugin_canvas_window().set_transient_for(window) {
+            log::warn!("Failed to set transient window: {error:?}");
+        }
+    }
+
+    pub fn show(&self) {
+        if let Some(window_adapter) = self.window_adapter() && let Err(error) = window_adapter.plugin_canvas_window().show() {
+            log::warn!("Failed to show window: {error:?}");
+        }
+    }
+
+    pub fn hide(&self) {
+        if let Some(window_adapter) = self.window_adapter() && let Err(error) = window_adapter.plugin_canvas_window().hide() {
+            log::warn!("Failed to hide window: {error:?}");
+        }
+    }
+
+    /// Called when the user closes a floating window, which is hidden instead of destroyed
+    pub fn set_close_callback(&self, callback: impl Fn() + 'static) {
+        *self.close_callback.borrow_mut() = Some(Box::new(callback));
+    }
+
     fn new() -> Self {
         Self {
             window_adapter: Default::default(),
+            close_callback: Default::default(),
         }
     }
 
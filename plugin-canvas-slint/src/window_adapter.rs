@@ -119,6 +119,10 @@ impl PluginCanvasWindowAdapter {
         self.input_focus.load(Ordering::Acquire)
     }
 
+    pub fn plugin_canvas_window(&self) -> &plugin_canvas::Window {
+        &self.plugin_canvas_window
+    }
+
     pub fn close(&self) {
         // Remove component to unravel the cyclic reference
         self.view.borrow_mut().take();
@@ -272,6 +276,10 @@ impl PluginCanvasWindowAdapter {
             plugin_canvas::Event::DragDropped { .. } => {
                 EventResponse::Ignored
             },
+
+            plugin_canvas::Event::CloseRequested => {
+                EventResponse::Ignored
+            },
         };
 
         if view_response != EventResponse::Ignored {
@@ -30,6 +30,16 @@ impl From<x11rb::errors::ConnectionError> for Error {
     }
 }
 
+#[cfg(target_os="linux")]
+impl From<x11rb::errors::ReplyError> for Error {
+    fn from(error: x11rb::errors::ReplyError) -> Self {
+        match error {
+            x11rb::errors::ReplyError::ConnectionError(error) => Self::X11ConnectionError(error),
+            x11rb::errors::ReplyError::X11Error(error) => Self::X11Error(error),
+        }
+    }
+}
+
 #[cfg(target_os="linux")]
 impl From<x11rb::errors::ReplyOrIdError> for Error {
     fn from(error: x11rb::errors::ReplyOrIdError) -> Self {
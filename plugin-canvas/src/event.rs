@@ -63,6 +63,9 @@ pub enum Event {
         position: LogicalPosition,
         data: DropData,
     },
+
+    /// The user closed a floating window, which has been hidden instead of destroyed
+    CloseRequested,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -6,8 +6,9 @@ use crate::{error::Error, event::EventCallback, window::WindowAttributes, Logica
 use super::os_window_handle::OsWindowHandle;
 
 pub(crate) trait OsWindowInterface: HasDisplayHandle + HasWindowHandle + Sized {
+    /// Opens a window embedded in `parent_window_handle`, or a hidden top-level window if there is no parent
     fn open(
-        parent_window_handle: RawWindowHandle,
+        parent_window_handle: Option<RawWindowHandle>,
         window_attributes: WindowAttributes,
         event_callback: Box<EventCallback>,
     ) -> Result<OsWindowHandle, Error>;
@@ -20,5 +21,9 @@ pub(crate) trait OsWindowInterface: HasDisplayHandle + HasWindowHandle + Sized {
     fn set_input_focus(&self, focus: bool);
     fn warp_mouse(&self, position: LogicalPosition);
 
+    fn set_title(&self, title: &str) -> Result<(), Error>;
+    fn set_transient_for(&self, window_handle: RawWindowHandle) -> Result<(), Error>;
+    fn set_visible(&self, visible: bool) -> Result<(), Error>;
+
     fn poll_events(&self) -> Result<(), Error>;
 }
@@ -41,10 +41,14 @@ impl OsWindow {
 
 impl OsWindowInterface for OsWindow {
     fn open(
-        parent_window_handle: RawWindowHandle,
+        parent_window_handle: Option<RawWindowHandle>,
         window_attributes: WindowAttributes,
         event_callback: Box<EventCallback>,
     ) -> Result<OsWindowHandle, Error> {
+        let Some(parent_window_handle) = parent_window_handle else {
+            return Err(Error::PlatformError("Floating windows are not supported on macOS".into()));
+        };
+
         let RawWindowHandle::AppKit(parent_window_handle) = parent_window_handle else {
             return Err(Error::PlatformError("Not an AppKit window".into()));
         };
@@ -270,6 +274,18 @@ impl OsWindowInterface for OsWindow {
         let cg_point = CGPoint::new(screen_position.x, screen_height - screen_position.y);
         CGWarpMouseCursorPosition(cg_point);
     }
+
+    fn set_title(&self, _title: &str) -> Result<(), Error> {
+        Err(Error::PlatformError("Floating windows are not supported on macOS".into()))
+    }
+
+    fn set_transient_for(&self, _window_handle: RawWindowHandle) -> Result<(), Error> {
+        Err(Error::PlatformError("Floating windows are not supported on macOS".into()))
+    }
+
+    fn set_visible(&self, _visible: bool) -> Result<(), Error> {
+        Err(Error::PlatformError("Floating windows are not supported on macOS".into()))
+    }
     
     fn poll_events(&self) -> Result<(), Error> {
         Ok(())
@@ -9,19 +9,22 @@ use uuid::Uuid;
 use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
 use windows::Win32::UI::WindowsAndMessaging::{GetParent, WM_CANCELMODE, WM_SETFOCUS};
 use windows::{core::PCWSTR, Win32::UI::Input::KeyboardAndMouse::{VK_LWIN, VK_RWIN}};
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
 use windows::Win32::Graphics::{Dwm::{DwmFlush, DwmIsCompositionEnabled}, Dxgi::{CreateDXGIFactory, IDXGIFactory, IDXGIOutput}, Gdi::{ClientToScreen, MonitorFromWindow, ScreenToClient, HBRUSH, MONITOR_DEFAULTTOPRIMARY}};
 use windows::Win32::System::Ole::{IDropTarget, OleInitialize, RegisterDragDrop, RevokeDragDrop};
-use windows::Win32::UI::{Controls::WM_MOUSELEAVE, Input::KeyboardAndMouse::{GetAsyncKeyState, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT, VK_CONTROL, VK_MENU, VK_SHIFT}, WindowsAndMessaging::{CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowLongPtrW, LoadCursorW, MoveWindow, RegisterClassW, SendMessageW, SetCursor, SetCursorPos, SetWindowLongPtrW, ShowCursor, UnregisterClassW, CS_OWNDC, GWLP_USERDATA, HICON, IDC_ARROW, WINDOW_EX_STYLE, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_MOVE, WM_RBUTTONDOWN, WM_RBUTTONUP, WNDCLASSW, WS_CHILD, WS_VISIBLE}};
+use windows::Win32::UI::{Controls::WM_MOUSELEAVE, Input::KeyboardAndMouse::{GetAsyncKeyState, SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT, VK_CONTROL, VK_MENU, VK_SHIFT}, WindowsAndMessaging::{AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowLongPtrW, LoadCursorW, MoveWindow, RegisterClassW, SendMessageW, SetCursor, SetCursorPos, SetWindowLongPtrW, SetWindowPos, SetWindowTextW, ShowCursor, ShowWindow, UnregisterClassW, CS_OWNDC, CW_USEDEFAULT, GWLP_HWNDPARENT, GWLP_USERDATA, HICON, IDC_ARROW, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOZORDER, SW_HIDE, SW_SHOW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_MOVE, WM_RBUTTONDOWN, WM_RBUTTONUP, WNDCLASSW, WS_CAPTION, WS_CHILD, WS_MINIMIZEBOX, WS_OVERLAPPED, WS_SYSMENU, WS_VISIBLE}};
 
 use crate::thread_bound::ThreadBound;
 use crate::{dimensions::Size, error::Error, event::{Event, EventCallback, EventResponse, MouseButton}, keyboard::KeyboardModifiers, platform::{interface::OsWindowInterface, os_window_handle::OsWindowHandle}, window::WindowAttributes, LogicalPosition, LogicalSize, PhysicalPosition};
 
 use super::{cursors::Cursors, drop_target::DropTarget, message_window::MessageWindow, to_wstr, version::is_windows10_or_greater, PLUGIN_HINSTANCE, WM_APP_CHAR, WM_APP_FRAME_TIMER, WM_APP_KEY_DOWN, WM_APP_KEY_UP};
 
+const FLOATING_WINDOW_STYLE: WINDOW_STYLE = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0 | WS_MINIMIZEBOX.0);
+
 pub struct OsWindow {
     window_class: u16,
     window_handle: Win32WindowHandle,
+    floating: bool,
     event_callback: Box<EventCallback>,
     drop_target: RefCell<Option<Box<IDropTarget>>>,
     message_window: Arc<MessageWindow>,
@@ -99,17 +102,31 @@ impl OsWindow {
             self.send_event(Event::KeyboardModifiers { modifiers: new_modifiers });
         }
     }
+
+    /// Converts a client area size to the outer size of a floating window
+    fn floating_window_size(width: i32, height: i32) -> (i32, i32) {
+        let mut rect = RECT { left: 0, top: 0, right: width, bottom: height };
+
+        if unsafe { AdjustWindowRectEx(&mut rect, FLOATING_WINDOW_STYLE, false, WINDOW_EX_STYLE(0)) }.is_err() {
+            return (width, height);
+        }
+
+        (rect.right - rect.left, rect.bottom - rect.top)
+    }
 }
 
 impl OsWindowInterface for OsWindow {
     fn open(
-        parent_window_handle: RawWindowHandle,
+        parent_window_handle: Option<RawWindowHandle>,
         window_attributes: WindowAttributes,
         event_callback: Box<EventCallback>,
     ) -> Result<OsWindowHandle, Error> {
-        let RawWindowHandle::Win32(parent_window_handle) = parent_window_handle else {
-            return Err(Error::PlatformError("Not a win32 window".into()));
+        let parent_hwnd = match parent_window_handle {
+            Some(RawWindowHandle::Win32(parent_window_handle)) => Some(HWND(parent_window_handle.hwnd.get() as _)),
+            Some(_) => { return Err(Error::PlatformError("Not a win32 window".into())); }
+            None => None,
         };
+        let floating = parent_hwnd.is_none();
 
         let class_name = to_wstr("plugin-canvas-".to_string() + &Uuid::new_v4().simple().to_string());
         let size = Size::with_logical_size(window_attributes.size, window_attributes.scale);
@@ -134,16 +151,24 @@ impl OsWindowInterface for OsWindow {
             return Err(Error::PlatformError("Failed to register window class".into()));
         }
 
+        let (style, x, y, width, height) = if floating {
+            // Floating windows start out hidden
+            let (width, height) = Self::floating_window_size(size.physical_size().width as i32, size.physical_size().height as i32);
+            (FLOATING_WINDOW_STYLE, CW_USEDEFAULT, CW_USEDEFAULT, width, height)
+        } else {
+            (WS_CHILD | WS_VISIBLE, 0, 0, size.physical_size().width as i32, size.physical_size().height as i32)
+        };
+
         let hwnd = unsafe { CreateWindowExW(
             WINDOW_EX_STYLE(0),
             PCWSTR(window_class as _),
             PCWSTR(null()),
-            WS_CHILD | WS_VISIBLE,
-            0,
-            0,
-            size.physical_size().width as i32,
-            size.physical_size().height as i32,
-            Some(HWND(parent_window_handle.hwnd.get() as _)),
+            style,
+            x,
+            y,
+            width,
+            height,
+            parent_hwnd,
             None,
             Some(PLUGIN_HINSTANCE.with(|hinstance| *hinstance)),
             None,
@@ -182,6 +207,7 @@ impl OsWindowInterface for OsWindow {
         let window = Self {
             window_class,
             window_handle,
+            floating,
             event_callback,
             drop_target: Default::default(),
             message_window,
@@ -217,7 +243,12 @@ impl OsWindowInterface for OsWindow {
     }
 
     fn resized(&self, size: LogicalSize) {
-        unsafe { MoveWindow(self.hwnd(), 0, 0, size.width as _, size.height as _, true).unwrap(); }
+        if self.floating {
+            let (width, height) = Self::floating_window_size(size.width as _, size.height as _);
+            unsafe { SetWindowPos(self.hwnd(), None, 0, 0, width, height, SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE).unwrap(); }
+        } else {
+            unsafe { MoveWindow(self.hwnd(), 0, 0, size.width as _, size.height as _, true).unwrap(); }
+        }
     }
 
     fn set_cursor(&self, cursor: Option<CursorIcon>) {
@@ -290,6 +321,31 @@ impl OsWindowInterface for OsWindow {
         }
     }
 
+    fn set_title(&self, title: &str) -> Result<(), Error> {
+        let title = to_wstr(title);
+        unsafe { SetWindowTextW(self.hwnd(), PCWSTR(title.as_ptr()))?; }
+
+        Ok(())
+    }
+
+    fn set_transient_for(&self, window_handle: RawWindowHandle) -> Result<(), Error> {
+        let RawWindowHandle::Win32(window_handle) = window_handle else {
+            return Err(Error::PlatformError("Not a win32 window".into()));
+        };
+
+        // For top-level windows, GWLP_HWNDPARENT sets the owner window
+        unsafe { SetWindowLongPtrW(self.hwnd(), GWLP_HWNDPARENT, window_handle.hwnd.get()) };
+
+        Ok(())
+    }
+
+    fn set_visible(&self, visible: bool) -> Result<(), Error> {
+        let command = if visible { SW_SHOW } else { SW_HIDE };
+        let _ = unsafe { ShowWindow(self.hwnd(), command) };
+
+        Ok(())
+    }
+
     fn poll_events(&self) -> Result<(), Error> {
         Ok(())
     }
@@ -457,6 +513,13 @@ unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam:
                 LRESULT(0)
             }
 
+            // Floating windows are hidden instead of destroyed when the user closes them
+            WM_CLOSE if window.floating => {
+                let _ = unsafe { ShowWindow(window.hwnd(), SW_HIDE) };
+                window.send_event(Event::CloseRequested);
+                LRESULT(0)
+            }
+
             WM_SETFOCUS => {
                 // We don't want keyboard focus thanks since we have the message window, give it to the parent window
                 // This fixes keyboard focus issues in Reaper and possibly other hosts
@@ -6,7 +6,8 @@ use keyboard_types::Code;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle, XcbDisplayHandle, XcbWindowHandle};
 use x11rb::{COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT};
 use x11rb::connection::Connection;
-use x11rb::protocol::{xfixes::{hide_cursor, show_cursor}, xproto::{change_window_attributes, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt, CreateWindowAux, EventMask, KeyButMask, WindowClass}};
+use x11rb::protocol::{xfixes::{hide_cursor, show_cursor}, xproto::{change_window_attributes, AtomEnum, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt, CreateWindowAux, EventMask, KeyButMask, PropMode, WindowClass}};
+use x11rb::wrapper::ConnectionExt as _;
 use x11rb::xcb_ffi::XCBConnection;
 use xkbcommon::xkb;
 
@@ -16,10 +17,20 @@ use crate::{dimensions::Size, error::Error, event::{EventCallback, EventResponse
 
 use super::{cursors::Cursors, keyboard::x11_to_keyboard_types_code};
 
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        _NET_WM_NAME,
+        UTF8_STRING,
+    }
+}
+
 pub struct OsWindow {
     event_callback: Box<EventCallback>,
 
     connection: XCBConnection,
+    atoms: Atoms,
     cursors: Cursors,
     xkb_state: RefCell<xkb::State>,
     xkb_compose_state: RefCell<xkb::compose::State>,
@@ -153,6 +164,14 @@ impl OsWindow {
 
                 self.send_event(Event::MouseMoved { position });
             }
+
+            // Floating windows are hidden instead of destroyed when the user closes them
+            x11rb::protocol::Event::ClientMessage(event)
+                if event.type_ == self.atoms.WM_PROTOCOLS && event.data.as_data32()[0] == self.atoms.WM_DELETE_WINDOW =>
+            {
+                self.set_visible(false)?;
+                self.send_event(Event::CloseRequested);
+            }
             
             _ => {},
         }
@@ -236,20 +255,21 @@ impl OsWindow {
 
 impl OsWindowInterface for OsWindow {
     fn open(
-        parent_window_handle: RawWindowHandle,
+        parent_window_handle: Option<RawWindowHandle>,
         window_attributes: WindowAttributes,
         event_callback: Box<EventCallback>,
     ) -> Result<OsWindowHandle, Error>
     {
-        let parent_window_id = match parent_window_handle {
-            RawWindowHandle::Xlib(parent_window_handle) => parent_window_handle.window as u32,
-            RawWindowHandle::Xcb(parent_window_handle) => parent_window_handle.window.get(),
-            _ => { return Err(Error::PlatformError("Not an X11 window".into())); }
-        };
-
         let size = Size::with_logical_size(window_attributes.size, window_attributes.scale);
 
         let (connection, screen) = XCBConnection::connect(None)?;
+        let atoms = Atoms::new(&connection)?.reply()?;
+
+        // Floating windows are parented to the root window
+        let parent_window_id = match parent_window_handle {
+            Some(parent_window_handle) => x11_window_id(parent_window_handle)?,
+            None => connection.setup().roots[screen].root,
+        };
 
         let window_id = connection.generate_id()?;
         connection.create_window(
@@ -274,7 +294,18 @@ impl OsWindowInterface for OsWindow {
                 ),
         )?;
 
-        connection.map_window(window_id)?;
+        if parent_window_handle.is_some() {
+            connection.map_window(window_id)?;
+        } else {
+            connection.change_property32(
+                PropMode::REPLACE,
+                window_id,
+                atoms.WM_PROTOCOLS,
+                AtomEnum::ATOM,
+                &[atoms.WM_DELETE_WINDOW],
+            )?;
+        }
+
         connection.flush()?;
 
         let (xkb_state, xkb_compose_state) = Self::init_xkb(&connection);
@@ -288,6 +319,7 @@ impl OsWindowInterface for OsWindow {
             event_callback,
 
             connection,
+            atoms,
             cursors,
             xkb_state: xkb_state.into(),
             xkb_compose_state: xkb_compose_state.into(),
@@ -386,6 +418,43 @@ impl OsWindowInterface for OsWindow {
         // TODO
     }
 
+    fn set_title(&self, title: &str) -> Result<(), Error> {
+        let window_id = self.window_handle.window.get();
+
+        self.connection.change_property8(PropMode::REPLACE, window_id, AtomEnum::WM_NAME, AtomEnum::STRING, title.as_bytes())?;
+        self.connection.change_property8(PropMode::REPLACE, window_id, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING, title.as_bytes())?;
+        self.connection.flush()?;
+
+        Ok(())
+    }
+
+    fn set_transient_for(&self, window_handle: RawWindowHandle) -> Result<(), Error> {
+        let transient_for = x11_window_id(window_handle)?;
+
+        self.connection.change_property32(
+            PropMode::REPLACE,
+            self.window_handle.window.get(),
+            AtomEnum::WM_TRANSIENT_FOR,
+            AtomEnum::WINDOW,
+            &[transient_for],
+        )?;
+        self.connection.flush()?;
+
+        Ok(())
+    }
+
+    fn set_visible(&self, visible: bool) -> Result<(), Error> {
+        if visible {
+            self.connection.map_window(self.window_handle.window.get())?;
+        } else {
+            self.connection.unmap_window(self.window_handle.window.get())?;
+        }
+
+        self.connection.flush()?;
+
+        Ok(())
+    }
+
     fn poll_events(&self) -> Result<(), Error> {
         while let Some(event) = self.connection.poll_for_event()? {
             self.handle_event(event)?;
@@ -395,6 +464,14 @@ impl OsWindowInterface for OsWindow {
     }
 }
 
+fn x11_window_id(window_handle: RawWindowHandle) -> Result<u32, Error> {
+    match window_handle {
+        RawWindowHandle::Xlib(window_handle) => Ok(window_handle.window as u32),
+        RawWindowHandle::Xcb(window_handle) => Ok(window_handle.window.get()),
+        _ => Err(Error::PlatformError("Not an X11 window".into())),
+    }
+}
+
 impl HasDisplayHandle for OsWindow {
     fn display_handle(&self) -> Result<raw_window_handle::DisplayHandle<'_>, raw_window_handle::HandleError> {
         let raw_display_handle = RawDisplayHandle::Xcb(self.display_handle);
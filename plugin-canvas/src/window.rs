@@ -51,7 +51,27 @@ impl Window {
         event_callback: Box<EventCallback>,
     ) -> Result<Window, Error> {
         let os_window_handle = OsWindow::open(
-            parent,
+            Some(parent),
+            attributes.clone(),
+            event_callback,
+        )?;
+
+        Ok(Self {
+            attributes,
+            os_window_handle,
+        })
+    }
+
+    /// Opens a top-level window which stays hidden until [`Window::show`] is called.
+    /// Closing the window from its title bar hides it instead of destroying it.
+    ///
+    /// Not supported on macOS.
+    pub fn open_floating(
+        attributes: WindowAttributes,
+        event_callback: Box<EventCallback>,
+    ) -> Result<Window, Error> {
+        let os_window_handle = OsWindow::open(
+            None,
             attributes.clone(),
             event_callback,
         )?;
@@ -90,6 +110,26 @@ impl Window {
     pub fn warp_mouse(&self, position: LogicalPosition) {
         self.os_window_handle.warp_mouse(position);
     }
+
+    /// Sets the title of a floating window
+    pub fn set_title(&self, title: &str) -> Result<(), Error> {
+        self.os_window_handle.set_title(title)
+    }
+
+    /// Keeps a floating window on top of `window`
+    pub fn set_transient_for(&self, window: RawWindowHandle) -> Result<(), Error> {
+        self.os_window_handle.set_transient_for(window)
+    }
+
+    /// Shows a floating window
+    pub fn show(&self) -> Result<(), Error> {
+        self.os_window_handle.set_visible(true)
+    }
+
+    /// Hides a floating window
+    pub fn hide(&self) -> Result<(), Error> {
+        self.os_window_handle.set_visible(false)
+    }
 }
 
 impl HasWindowHandle for Window {
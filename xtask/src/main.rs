@@ -1,3 +1,48 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+// nih_plug_xtask doesn't write moduleinfo.json, so these packages' binaries add it to their VST3 bundle
+const MODULE_INFO_BINARIES: &[(&str, &str)] = &[
+    ("gain-plugin", "gain-module-info"),
+];
+
 fn main() -> nih_plug_xtask::Result<()> {
-    nih_plug_xtask::main()
+    nih_plug_xtask::chdir_workspace_root()?;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    nih_plug_xtask::main_with_args("cargo xtask", args.clone())?;
+
+    if let [command, package, build_args @ ..] = args.as_slice() {
+        if command == "bundle" || command == "bundle-universal" {
+            write_module_info(package, build_args)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_module_info(package: &str, build_args: &[String]) -> nih_plug_xtask::Result<()> {
+    let Some((_, binary)) = MODULE_INFO_BINARIES.iter().find(|(name, _)| *name == package) else {
+        return Ok(());
+    };
+
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"));
+    let bundle_path = target_dir.join("bundled").join(format!("{package}.vst3"));
+
+    let release = build_args.iter().any(|arg| arg == "--release" || arg == "-r");
+
+    let status = Command::new("cargo")
+        .args(["run", "--package", package, "--bin", binary, "--features", "moduleinfo"])
+        .args(release.then_some("--release"))
+        .arg("--")
+        .arg(&bundle_path)
+        .status()?;
+
+    if !status.success() {
+        return Err(std::io::Error::other(format!("{binary} failed to write moduleinfo.json to {}", bundle_path.display())).into());
+    }
+
+    Ok(())
 }